@@ -6,24 +6,20 @@
 mod utils;
 
 use bts_rs::prelude::*;
-use ta::{
-    indicators::{
-        ExponentialMovingAverage, MovingAverageConvergenceDivergence, MovingAverageConvergenceDivergenceOutput,
-    },
-    *,
-};
 
 fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let candles = utils::example_candles();
     let initial_balance = 1_000.0;
     let mut bts = Backtest::new(candles.clone(), initial_balance, None)?;
-    let mut ema = ExponentialMovingAverage::new(100)?;
-    let mut macd = MovingAverageConvergenceDivergence::default();
+    let mut ema = Ema::new(100)?;
+    let mut macd = Macd::new(12, 26, 9)?;
 
     bts.run(|bt, candle| {
         let close = candle.close();
-        let output = ema.next(close);
-        let MovingAverageConvergenceDivergenceOutput { histogram, .. } = macd.next(close);
+        let (Some(output), Some(MacdOutput { histogram, .. })) = (ema.next(candle), macd.next(candle)) else {
+            // warm-up of the indicators
+            return Ok(());
+        };
 
         let balance = bt.free_balance()?;
         // 21: minimum to trade
@@ -0,0 +1,356 @@
+use super::{Candle, Order, OrderType};
+use crate::{
+    errors::{Error, Result},
+    utils::random_id,
+};
+
+/// Execution algorithm slicing a parent order into child orders across candles.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionAlgo {
+    /// Time-weighted average price: equal slices over a number of candles.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of candles (slices).
+    Twap(usize),
+
+    /// Volume-weighted average price: slices over a number of candles, sized in proportion
+    /// to the volume of each candle.
+    ///
+    /// The volume of the coming candles is unknown, so it is estimated with the average volume
+    /// of the previous candles over the same number of candles.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of candles (slices).
+    Vwap(usize),
+
+    /// Iceberg order: only a fixed quantity is shown, and it is refilled after each fill.
+    ///
+    /// ### Arguments
+    /// * `0` - The visible quantity.
+    Iceberg(f64),
+}
+
+/// Status of a parent order.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParentStatus {
+    /// The parent order is being executed.
+    Active,
+    /// The parent order is filled, or its schedule has ended.
+    Completed,
+    /// The parent order has been cancelled.
+    Cancelled,
+}
+
+/// Large order sliced by the engine into child orders across candles.
+///
+/// Each child is a normal [`Order`] with the entry and exit rules of the parent, and each fill opens
+/// a normal [`Position`](crate::engine::Position). Market children are placed at the close of the candle,
+/// and limit children at the limit price of the parent.
+///
+/// TWAP and VWAP children are only valid for their candle: an unfilled quantity is spread over
+/// the next slices, and the parent is completed at the end of the schedule even if it is not filled.
+/// Iceberg children stay pending until they are filled.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// let order: Order = (OrderType::Market(100.0), 10.0, OrderSide::Buy).into();
+/// let parent = ParentOrder::new(order, ExecutionAlgo::Twap(5)).unwrap();
+/// assert_eq!(parent.remaining_quantity(), 10.0);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ParentOrder {
+    id: u32,
+    order: Order,
+    algo: ExecutionAlgo,
+    slices: usize,
+    filled: f64,
+    filled_cost: f64,
+    status: ParentStatus,
+    child: Option<Order>,
+}
+
+impl PartialEq for ParentOrder {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl ParentOrder {
+    /// Creates a new parent order.
+    ///
+    /// ### Arguments
+    /// * `order` - The order to execute, giving the total quantity, the side, the entry and exit rules.
+    /// * `algo` - The execution algorithm.
+    ///
+    /// ### Returns
+    /// The new parent order or an error.
+    pub fn new(order: Order, algo: ExecutionAlgo) -> Result<Self> {
+        order.entry_price()?;
+        let valid = match algo {
+            ExecutionAlgo::Twap(candles) | ExecutionAlgo::Vwap(candles) => candles > 0,
+            ExecutionAlgo::Iceberg(visible) => visible > 0.0,
+        };
+        if !valid || order.quantity <= 0.0 {
+            return Err(Error::InvalidExecutionAlgo);
+        }
+
+        Ok(Self {
+            id: random_id(),
+            order,
+            algo,
+            slices: 0,
+            filled: 0.0,
+            filled_cost: 0.0,
+            status: ParentStatus::Active,
+            child: None,
+        })
+    }
+
+    /// Returns the id of the parent order.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the order to execute.
+    pub fn order(&self) -> &Order {
+        &self.order
+    }
+
+    /// Returns the execution algorithm.
+    pub fn algo(&self) -> &ExecutionAlgo {
+        &self.algo
+    }
+
+    /// Returns the status of the parent order.
+    pub fn status(&self) -> ParentStatus {
+        self.status
+    }
+
+    /// Returns true if the parent order is being executed.
+    pub fn is_active(&self) -> bool {
+        self.status == ParentStatus::Active
+    }
+
+    /// Returns the pending child order, if any.
+    pub fn child(&self) -> Option<&Order> {
+        self.child.as_ref()
+    }
+
+    /// Returns the filled quantity.
+    pub fn filled_quantity(&self) -> f64 {
+        self.filled
+    }
+
+    /// Returns the quantity left to fill.
+    pub fn remaining_quantity(&self) -> f64 {
+        (self.order.quantity - self.filled).max(0.0)
+    }
+
+    /// Returns the filled part of the parent order, from 0.0 to 1.0.
+    pub fn progress(&self) -> f64 {
+        self.filled / self.order.quantity
+    }
+
+    /// Returns the average fill price, if any.
+    pub fn average_price(&self) -> Option<f64> {
+        (self.filled > 0.0).then(|| self.filled_cost / self.filled)
+    }
+
+    /// Returns true if the schedule of the parent order has ended.
+    fn is_schedule_over(&self) -> bool {
+        match self.algo {
+            ExecutionAlgo::Twap(candles) | ExecutionAlgo::Vwap(candles) => self.slices >= candles,
+            ExecutionAlgo::Iceberg(_) => false,
+        }
+    }
+
+    /// Returns true if the parent order is filled.
+    fn is_filled(&self) -> bool {
+        self.remaining_quantity() <= self.order.quantity * f64::EPSILON
+    }
+
+    /// Creates the next child order on the candle, if any.
+    ///
+    /// ### Arguments
+    /// * `candle` - The current candle.
+    /// * `history` - The candles before the current one.
+    pub(crate) fn next_child(&mut self, candle: &Candle, history: &[Candle]) -> Result<Option<Order>> {
+        if !self.is_active() || self.child.is_some() || self.is_schedule_over() {
+            return Ok(None);
+        }
+
+        let remaining = self.remaining_quantity();
+        let quantity = match self.algo {
+            ExecutionAlgo::Twap(candles) => remaining / (candles - self.slices) as f64,
+            ExecutionAlgo::Vwap(candles) => {
+                let slices_left = candles - self.slices;
+                let lookback = &history[history.len().saturating_sub(candles)..];
+                let volume = candle.volume();
+                let expected_volume = if lookback.is_empty() {
+                    volume
+                } else {
+                    lookback.iter().map(|c| c.volume()).sum::<f64>() / lookback.len() as f64
+                };
+                let total_volume = volume + expected_volume * (slices_left - 1) as f64;
+                if total_volume > 0.0 {
+                    remaining * volume / total_volume
+                } else {
+                    remaining / slices_left as f64
+                }
+            }
+            ExecutionAlgo::Iceberg(visible) => visible.min(remaining),
+        };
+        self.slices += 1;
+
+        if quantity <= 0.0 {
+            return Ok(None);
+        }
+        let entry_type = match self.order.entry_type() {
+            OrderType::Market(_) => OrderType::Market(candle.close()),
+            entry_type => OrderType::Limit(entry_type.inner()?),
+        };
+        let child = self.order.child(self.id, entry_type, quantity);
+        self.child = Some(child.clone());
+        Ok(Some(child))
+    }
+
+    /// Records the fill of a child order.
+    pub(crate) fn fill(&mut self, price: f64, quantity: f64) {
+        self.filled += quantity;
+        self.filled_cost += price * quantity;
+    }
+
+    /// Updates the status at the end of a candle.
+    ///
+    /// ### Arguments
+    /// * `child_pending` - True if the child order is still pending.
+    ///
+    /// ### Returns
+    /// The child order to cancel, if any.
+    pub(crate) fn update(&mut self, child_pending: bool) -> Option<Order> {
+        let mut to_cancel = None;
+        if let Some(child) = self.child.take() {
+            match (child_pending, &self.algo) {
+                (true, ExecutionAlgo::Iceberg(_)) => self.child = Some(child),
+                (true, _) => to_cancel = Some(child),
+                (false, _) => {}
+            }
+        }
+        if self.is_filled() || (self.is_schedule_over() && self.child.is_none()) {
+            self.status = ParentStatus::Completed;
+        }
+        to_cancel
+    }
+
+    /// Cancels the parent order.
+    ///
+    /// ### Returns
+    /// The pending child order to cancel, if any.
+    pub(crate) fn cancel(&mut self) -> Option<Order> {
+        self.status = ParentStatus::Cancelled;
+        self.child.take()
+    }
+}
+
+#[cfg(test)]
+use super::{
+    OrderSide,
+    testing::{get_candle, hour},
+};
+
+#[cfg(test)]
+#[test]
+fn parent_order_invalid() {
+    let order: Order = (OrderType::Market(100.0), 10.0, OrderSide::Buy).into();
+    assert!(ParentOrder::new(order.clone(), ExecutionAlgo::Twap(0)).is_err());
+    assert!(ParentOrder::new(order.clone(), ExecutionAlgo::Iceberg(0.0)).is_err());
+    let order: Order = (OrderType::TrailingStop(100.0, 1.0), 10.0, OrderSide::Buy).into();
+    assert!(ParentOrder::new(order, ExecutionAlgo::Twap(2)).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn parent_order_twap_slices() {
+    let order: Order = (OrderType::Market(100.0), 9.0, OrderSide::Buy).into();
+    let mut parent = ParentOrder::new(order, ExecutionAlgo::Twap(3)).unwrap();
+    let candle = get_candle(hour(0), 100.0, 100.0, 100.0, 1.0);
+
+    let child = parent.next_child(&candle, &[]).unwrap().unwrap();
+    assert_eq!(child.quantity, 3.0);
+    assert_eq!(child.parent_id(), Some(parent.id()));
+    assert!(parent.next_child(&candle, &[]).unwrap().is_none());
+
+    // the first slice is not filled, the quantity is spread over the next slices
+    assert!(parent.update(true).is_some());
+    let child = parent.next_child(&candle, &[]).unwrap().unwrap();
+    assert_eq!(child.quantity, 4.5);
+    parent.fill(100.0, 4.5);
+    assert!(parent.update(false).is_none());
+
+    let child = parent.next_child(&candle, &[]).unwrap().unwrap();
+    assert_eq!(child.quantity, 4.5);
+    parent.fill(110.0, 4.5);
+    parent.update(false);
+    assert_eq!(parent.status(), ParentStatus::Completed);
+    assert_eq!(parent.average_price(), Some(105.0));
+    assert_eq!(parent.progress(), 1.0);
+}
+
+#[cfg(test)]
+#[test]
+fn parent_order_vwap_slices() {
+    let order: Order = (OrderType::Market(100.0), 10.0, OrderSide::Buy).into();
+    let mut parent = ParentOrder::new(order, ExecutionAlgo::Vwap(2)).unwrap();
+    let history = vec![
+        get_candle(hour(0), 100.0, 100.0, 100.0, 10.0),
+        get_candle(hour(0), 100.0, 100.0, 100.0, 30.0),
+    ];
+
+    // expected volume of 20 for the last slice
+    let child = parent
+        .next_child(&get_candle(hour(0), 100.0, 100.0, 100.0, 30.0), &history)
+        .unwrap()
+        .unwrap();
+    assert_eq!(child.quantity, 6.0);
+    parent.fill(100.0, 6.0);
+    parent.update(false);
+
+    // the last slice fills the remaining quantity
+    let child = parent
+        .next_child(&get_candle(hour(0), 100.0, 100.0, 100.0, 5.0), &history)
+        .unwrap()
+        .unwrap();
+    assert_eq!(child.quantity, 4.0);
+}
+
+#[cfg(test)]
+#[test]
+fn parent_order_iceberg_refill() {
+    let order: Order = (OrderType::Limit(95.0), 5.0, OrderSide::Buy).into();
+    let mut parent = ParentOrder::new(order, ExecutionAlgo::Iceberg(2.0)).unwrap();
+    let candle = get_candle(hour(0), 100.0, 100.0, 100.0, 1.0);
+
+    let child = parent.next_child(&candle, &[]).unwrap().unwrap();
+    assert_eq!(child.quantity, 2.0);
+    assert_eq!(child.entry_price().unwrap(), 95.0);
+
+    // the child stays pending until it is filled
+    assert!(parent.update(true).is_none());
+    assert!(parent.next_child(&candle, &[]).unwrap().is_none());
+
+    parent.fill(95.0, 2.0);
+    parent.update(false);
+    parent.next_child(&candle, &[]).unwrap().unwrap();
+    parent.fill(95.0, 2.0);
+    parent.update(false);
+    let child = parent.next_child(&candle, &[]).unwrap().unwrap();
+    assert_eq!(child.quantity, 1.0);
+    assert!(parent.cancel().is_some());
+    assert_eq!(parent.status(), ParentStatus::Cancelled);
+    assert_eq!(parent.remaining_quantity(), 1.0);
+}
@@ -0,0 +1,214 @@
+use super::{Backtest, Candle, ExitReason};
+use crate::errors::{Error, Result};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::Event;
+
+/// When trading resumes after a halt of the [`CircuitBreaker`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Resume {
+    /// Trading stays halted until the end of the backtest.
+    #[default]
+    Never,
+    /// Trading resumes after a number of candles, the drawdown and the losses being counted again from the resume.
+    Cooldown(usize),
+    /// Trading resumes when the equity makes a new high.
+    ///
+    /// The open positions must be kept through the halt: with [`CircuitBreaker::with_flatten`],
+    /// the equity stops moving once flattened, and trading stays halted until the end of the backtest.
+    NewHigh,
+}
+
+/// Reason of a trading halt.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HaltReason {
+    /// The drawdown from the equity peak reached the limit.
+    ///
+    /// ### Arguments
+    /// * `0` - The drawdown as a percentage of the peak.
+    Drawdown(f64),
+    /// The last closed trades were all losing.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of consecutive losing trades.
+    ConsecutiveLosses(usize),
+}
+
+/// Kill-switch rules halting the trading of the backtest.
+///
+/// The rules are checked at the close of each candle. On a halt, the pending orders are cancelled,
+/// the open positions are optionally closed, and new orders are rejected with [`Error::TradingHalted`]
+/// until trading resumes.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// // halt on a 10% drawdown or 5 losing trades in a row, flatten, and resume 24 candles later
+/// let breaker = CircuitBreaker::new()
+///     .with_max_drawdown(10.0)
+///     .with_max_consecutive_losses(5)
+///     .with_flatten(true)
+///     .with_resume(Resume::Cooldown(24));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CircuitBreaker {
+    max_drawdown: Option<f64>,
+    max_consecutive_losses: Option<usize>,
+    flatten: bool,
+    resume: Resume,
+}
+
+impl CircuitBreaker {
+    /// Creates a new circuit breaker without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Halts trading when the drawdown from the equity peak reaches a percentage (e.g., 10.0 for 10%).
+    ///
+    /// The peak moves with the deposits and withdrawals, which are not gains or losses.
+    pub fn with_max_drawdown(mut self, percent: f64) -> Self {
+        self.max_drawdown = Some(percent);
+        self
+    }
+
+    /// Halts trading after a number of consecutive losing trades.
+    pub fn with_max_consecutive_losses(mut self, count: usize) -> Self {
+        self.max_consecutive_losses = Some(count);
+        self
+    }
+
+    /// Closes all the open positions on a halt.
+    ///
+    /// A flat backtest can't make a new equity high, so this never resumes with [`Resume::NewHigh`].
+    pub fn with_flatten(mut self, flatten: bool) -> Self {
+        self.flatten = flatten;
+        self
+    }
+
+    /// Sets when trading resumes after a halt.
+    pub fn with_resume(mut self, resume: Resume) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Returns the maximum drawdown in percent, if any.
+    pub fn max_drawdown(&self) -> Option<f64> {
+        self.max_drawdown
+    }
+
+    /// Returns the maximum number of consecutive losing trades, if any.
+    pub fn max_consecutive_losses(&self) -> Option<usize> {
+        self.max_consecutive_losses
+    }
+
+    /// Returns true if the open positions are closed on a halt.
+    pub fn flatten(&self) -> bool {
+        self.flatten
+    }
+
+    /// Returns when trading resumes after a halt.
+    pub fn resume(&self) -> Resume {
+        self.resume
+    }
+}
+
+impl Backtest {
+    /// Sets the kill-switch rules of the backtest.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// Returns the kill-switch rules of the backtest.
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// Returns the reason of the current halt, or `None` if trading is allowed.
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt.map(|(reason, _)| reason)
+    }
+
+    /// Returns true if trading is halted by the circuit breaker.
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_some()
+    }
+
+    /// Moves the equity peak by a deposit (positive) or a withdrawal (negative).
+    pub(crate) fn shift_equity_peak(&mut self, amount: f64) {
+        self.equity_peak = (self.equity_peak + amount).max(0.0);
+    }
+
+    /// Returns an error if trading is halted.
+    pub(crate) fn check_halt(&self) -> Result<()> {
+        match self.halt {
+            Some(_) => Err(Error::TradingHalted),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the kill-switch rules at the close of the candle, halting or resuming trading.
+    pub(crate) fn check_circuit_breaker(&mut self, candle: &Candle) -> Result<()> {
+        if self.breaker == CircuitBreaker::default() {
+            return Ok(());
+        }
+        let equity = self.equity_snapshot(candle)?.equity();
+
+        if let Some((_, index)) = self.halt {
+            let resume = match self.breaker.resume {
+                Resume::Never => false,
+                Resume::Cooldown(candles) => self.index >= index + candles,
+                Resume::NewHigh => equity > self.equity_peak,
+            };
+            self.equity_peak = self.equity_peak.max(equity);
+            if resume {
+                if let Resume::Cooldown(_) = self.breaker.resume {
+                    self.equity_peak = equity;
+                }
+                self.losses_from = self.trade_pnls.len();
+                self.halt = None;
+                #[cfg(feature = "metrics")]
+                self.events.push(Event::Resume {
+                    time: candle.close_time(),
+                });
+            }
+            return Ok(());
+        }
+
+        self.equity_peak = self.equity_peak.max(equity);
+        let drawdown = (self.equity_peak - equity) / self.equity_peak * 100.0;
+        let losses = self.trade_pnls[self.losses_from..]
+            .iter()
+            .rev()
+            .take_while(|pnl| **pnl < 0.0)
+            .count();
+        let reason = match (self.breaker.max_drawdown, self.breaker.max_consecutive_losses) {
+            (Some(max), _) if drawdown >= max => HaltReason::Drawdown(drawdown),
+            (_, Some(max)) if losses >= max => HaltReason::ConsecutiveLosses(losses),
+            _ => return Ok(()),
+        };
+        self.halt = Some((reason, self.index));
+        #[cfg(feature = "metrics")]
+        self.events.push(Event::Halt {
+            reason,
+            time: candle.close_time(),
+        });
+
+        while let Some(order) = self.orders.pop_front() {
+            self.delete_order(&order, false)?;
+        }
+        if self.breaker.flatten {
+            while let Some(position) = self.positions.pop_front() {
+                let exit_price = self.mark_price(position.symbol(), candle);
+                self.close_position_with(&position, exit_price, false, ExitReason::CircuitBreaker)?;
+            }
+            self.update_unrealized_pnl(candle)?;
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 use chrono::DateTime;
 
 use super::*;
+use crate::PercentCalculus;
 
 fn get_data() -> Vec<Candle> {
     let candle = CandleBuilder::builder()
@@ -606,7 +607,7 @@ fn scenario_with_aggregator() {
     let mut ic = 0;
     let aggregator = TestAggregator;
     bt.run_with_aggregator(&aggregator, |_, candles| {
-        let candle_one = candles.get(0);
+        let candle_one = candles.first();
         let candle_two = candles.get(1);
 
         // candle_two is none at ic = 0
@@ -623,3 +624,1682 @@ fn scenario_with_aggregator() {
     })
     .unwrap();
 }
+
+#[test]
+fn scenario_open_long_position_with_break_even() {
+    // enter at 100 with a stop-loss at 90 (1R = 10)
+    // the close reaches 108 (0.8R, above the 0.5R trigger), the stop moves to break-even
+    // the close reaches 135 (35% gain, above the 30% trigger), the stop locks 50% of the gain at 117.5
+    // the position stays open
+    let data = get_long_data_trailing_stop();
+    let balance = 1000.0;
+    let mut bt = Backtest::new(data, balance, None).unwrap();
+
+    let candle = bt.next().unwrap();
+    let price = candle.close();
+
+    let stop_loss = OrderType::TakeProfitAndStopLoss(0.0, price - 10.0);
+    let order = Order::from((OrderType::Market(price), stop_loss, 1.0, OrderSide::Buy))
+        .with_stop_adjustment(StopAdjustment::BreakEven(ProfitTrigger::RMultiple(0.5), 0.0))
+        .with_stop_adjustment(StopAdjustment::LockProfit(ProfitTrigger::Percent(30.0), 50.0));
+    bt.place_order(order).unwrap();
+    bt.execute_orders(&candle).unwrap();
+    bt.execute_positions(&candle).unwrap();
+    assert_eq!(bt.positions().next().unwrap().stop_loss(), Some(90.0));
+
+    // next tick, close = 108
+    let candle = bt.next().unwrap();
+    bt.execute_positions(&candle).unwrap();
+    assert_eq!(bt.positions().next().unwrap().stop_loss(), Some(100.0));
+
+    // next tick, close = 135, lock 50% of the gains
+    let candle = bt.next().unwrap();
+    bt.execute_positions(&candle).unwrap();
+    assert_eq!(bt.positions().next().unwrap().stop_loss(), Some(117.5));
+
+    #[cfg(feature = "metrics")]
+    {
+        let moves = bt
+            .events()
+            .filter_map(|e| match e {
+                Event::StopUpdate { from, to, .. } => Some((*from, *to)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(moves, vec![(Some(90.0), 100.0), (Some(100.0), 117.5)]);
+    }
+
+    // next tick, low = 126, close = 130
+    let candle = bt.next().unwrap();
+    bt.execute_positions(&candle).unwrap();
+    assert!(!bt.positions.is_empty());
+    assert_eq!(bt.positions().next().unwrap().stop_loss(), Some(117.5));
+}
+
+#[test]
+fn scenario_time_exit_on_close() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let order = Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy))
+                .with_time_exit(TimeExit::Candles(1));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 100, exit at the close of the next candle (110)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1010.0);
+}
+
+#[test]
+fn scenario_time_exit_next_open() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_execution(Execution::NextOpen);
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let order = Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy))
+                .with_time_exit(TimeExit::Duration(chrono::Duration::zero()));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 100, exit at the open of the next candle (100)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1000.0);
+}
+
+#[test]
+fn scenario_time_exit_with_take_profit() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let take_profit = OrderType::TakeProfitAndStopLoss(115.0, 0.0);
+            let order = Order::from((OrderType::Market(candle.close()), take_profit, 1.0, OrderSide::Buy))
+                .with_time_exit(TimeExit::Candles(2));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // the take-profit triggers before the time exit
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1015.0);
+}
+
+fn get_session_data() -> Vec<Candle> {
+    // Monday 2025-12-22, from 08:00 to 16:00 UTC
+    let start = DateTime::from_timestamp_secs(1766390400).unwrap();
+    (0..8)
+        .map(|i| {
+            let open = 100.0 + i as f64;
+            let open_time = start + chrono::Duration::hours(i);
+            CandleBuilder::builder()
+                .open(open)
+                .high(open + 2.0)
+                .low(open - 1.0)
+                .close(open + 1.0)
+                .volume(1.0)
+                .open_time(open_time)
+                .close_time(open_time + chrono::Duration::hours(1))
+                .build()
+                .unwrap()
+        })
+        .collect()
+}
+
+fn get_session_calendar() -> Calendar<chrono::Utc> {
+    // 10:00 to 14:00 UTC
+    Calendar::new(chrono::Utc).weekdays(
+        chrono::NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+        chrono::NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+    )
+}
+
+#[test]
+fn scenario_orders_blocked_outside_session() {
+    let data = get_session_data();
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_calendar(get_session_calendar());
+
+    let mut sessions = vec![];
+    bt.run(|bt, candle| {
+        sessions.push((bt.is_session_open(), bt.minutes_to_close()));
+        if bt.index == 0 {
+            // pre-market order, filled when the session opens
+            let order = Order::from((OrderType::Limit(candle.close() + 1.5), 1.0, OrderSide::Buy));
+            bt.place_order(order)?;
+        }
+        if bt.index == 1 {
+            assert_eq!(bt.orders().count(), 1);
+            assert_eq!(bt.positions().count(), 0);
+        }
+        if bt.index == 3 {
+            assert_eq!(bt.orders().count(), 0);
+            assert_eq!(bt.positions().count(), 1);
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(
+        sessions,
+        vec![
+            (false, None),
+            (false, None),
+            (true, Some(180)),
+            (true, Some(120)),
+            (true, Some(60)),
+            (true, Some(0)),
+            (false, None),
+            (false, None),
+        ]
+    );
+    // the position is still open after the session
+    assert_eq!(bt.positions().count(), 1);
+}
+
+#[test]
+fn scenario_flatten_at_session_close() {
+    let data = get_session_data();
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_calendar(get_session_calendar())
+        .with_flatten_at_close(true);
+
+    bt.run(|bt, candle| {
+        if bt.index == 2 {
+            // take-profit in the post-market candles range
+            let take_profit = OrderType::TakeProfitAndStopLoss(107.5, 0.0);
+            let order = Order::from((OrderType::Market(candle.close()), take_profit, 1.0, OrderSide::Buy));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 103, exit at the close of the last candle of the session (106)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1003.0);
+}
+
+#[derive(Debug, Clone)]
+struct CloseAboveExit(f64);
+
+impl ExitRule for CloseAboveExit {
+    fn exit_price(&mut self, _position: &Position, candle: &Candle, context: &ExitContext) -> Result<Option<f64>> {
+        assert_eq!(context.candles().len(), context.index() + 1);
+        Ok((candle.close() > self.0).then_some(candle.close()))
+    }
+}
+
+#[test]
+fn scenario_open_long_position_with_custom_exit_rule() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let exit_rule = OrderType::custom(CloseAboveExit(105.0));
+            // an order holding a custom rule is found by value
+            let limit = Order::from((OrderType::Limit(50.0), exit_rule.clone(), 1.0, OrderSide::Buy));
+            bt.place_order(limit.clone())?;
+            bt.delete_order(&limit, true)?;
+            assert_eq!(bt.orders().count(), 0);
+            let order = Order::from((OrderType::Market(candle.close()), exit_rule, 1.0, OrderSide::Buy));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 100, exit at the first close above 105 (110)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1010.0);
+}
+
+/// Exits 30 above the stop-loss of the position, like a rule trailing the stop of another rule.
+#[derive(Debug, Clone)]
+struct AboveStopExit;
+
+impl ExitRule for AboveStopExit {
+    fn exit_price(&mut self, position: &Position, candle: &Candle, _context: &ExitContext) -> Result<Option<f64>> {
+        // the rule sees the position with all its exit rules
+        let stop_loss = position.stop_loss().ok_or(Error::MissingStopLoss)?;
+        Ok((candle.close() > stop_loss + 30.0).then_some(candle.close()))
+    }
+}
+
+#[test]
+fn scenario_custom_exit_rule_reads_the_position_rules() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let stop_loss = OrderType::TakeProfitAndStopLoss(0.0, 75.0);
+            let order = Order::from((OrderType::Market(candle.close()), stop_loss, 1.0, OrderSide::Buy))
+                .with_exit_rule(OrderType::custom(AboveStopExit));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 100, exit at the first close above 105 (110)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1010.0);
+}
+
+#[cfg(feature = "metrics")]
+fn exit_reasons(bt: &Backtest) -> Vec<ExitReason> {
+    bt.events()
+        .filter_map(|e| match e {
+            Event::DelPosition(position) => position.exit_reason().cloned(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn scenario_composite_exit_rules_first_trigger_wins() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let order = Order::from((
+                OrderType::Market(candle.close()),
+                OrderType::TakeProfitAndStopLoss(105.0, 85.0),
+                1.0,
+                OrderSide::Buy,
+            ))
+            .with_exit_rule(OrderType::TrailingStop(candle.close(), 10.0));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 100, the candle opens at the trailing stop (90)
+    // before going down to the stop-loss (85) and up to the take-profit (105)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 990.0);
+    #[cfg(feature = "metrics")]
+    assert_eq!(exit_reasons(&bt), vec![ExitReason::TrailingStop]);
+}
+
+#[test]
+fn scenario_composite_exit_rules_intrabar_order() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let order = Order::from((
+                OrderType::Market(candle.close()),
+                OrderType::custom(CloseAboveExit(105.0)),
+                1.0,
+                OrderSide::Buy,
+            ))
+            .with_exit_rule(OrderType::TakeProfitAndStopLoss(105.0, 0.0));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // both rules trigger on the next candle, the take-profit (105) is reached before the close (110)
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1005.0);
+    #[cfg(feature = "metrics")]
+    assert_eq!(exit_reasons(&bt), vec![ExitReason::TakeProfit]);
+}
+
+#[test]
+fn scenario_composite_exit_rules_with_time_exit() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let order = Order::from((
+                OrderType::Market(candle.close()),
+                OrderType::TakeProfitAndStopLoss(150.0, 50.0),
+                1.0,
+                OrderSide::Buy,
+            ))
+            .with_exit_rule(OrderType::TrailingStop(candle.close(), 50.0))
+            .with_time_exit(TimeExit::Candles(1));
+            bt.place_order(order)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // no price rule is reached, the time exit closes the position at 110
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1010.0);
+    #[cfg(feature = "metrics")]
+    assert_eq!(exit_reasons(&bt), vec![ExitReason::Time]);
+}
+
+#[test]
+fn scenario_twap_parent_order() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let order = Order::from((OrderType::Market(candle.close()), 3.0, OrderSide::Buy));
+            bt.place_parent_order(ParentOrder::new(order, ExecutionAlgo::Twap(3))?)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // one slice at the close of each candle (100, 110, 120)
+    let parent = bt.parent_orders().next().unwrap();
+    assert_eq!(parent.status(), ParentStatus::Completed);
+    assert_eq!(parent.filled_quantity(), 3.0);
+    assert_eq!(parent.average_price(), Some(110.0));
+    assert_eq!(bt.positions().count(), 3);
+    assert!(
+        bt.positions()
+            .all(|p| p.quantity == 1.0 && p.parent_id() == Some(parent.id()))
+    );
+    assert_eq!(bt.free_balance().unwrap(), 670.0);
+}
+
+#[test]
+fn scenario_twap_parent_order_rejected_child() {
+    let data = get_long_data();
+    let place_twap = |bt: &mut Backtest, candle: &Candle| {
+        if bt.index == 0 {
+            let order = Order::from((OrderType::Market(candle.close()), 3.0, OrderSide::Buy));
+            bt.place_parent_order(ParentOrder::new(order, ExecutionAlgo::Twap(3))?)?;
+        }
+        Ok(())
+    };
+
+    // the third slice breaks the limit of open positions
+    let risk = RiskManager::new().with_max_open_positions(2);
+    let mut bt = Backtest::new(data.clone(), 1000.0, None)
+        .unwrap()
+        .with_risk_manager(risk);
+    bt.run(place_twap).unwrap();
+
+    let parent = bt.parent_orders().next().unwrap();
+    assert_eq!(parent.status(), ParentStatus::Cancelled);
+    assert_eq!(parent.filled_quantity(), 2.0);
+    assert_eq!(bt.positions().count(), 2);
+    assert_eq!(bt.free_balance().unwrap(), 790.0);
+    #[cfg(feature = "metrics")]
+    assert_eq!(
+        bt.events().filter(|e| matches!(e, Event::RejectOrder { .. })).count(),
+        1
+    );
+
+    // the third slice costs 120 with 40 left
+    let mut bt = Backtest::new(data, 250.0, None).unwrap();
+    bt.run(place_twap).unwrap();
+
+    let parent = bt.parent_orders().next().unwrap();
+    assert_eq!(parent.status(), ParentStatus::Cancelled);
+    assert_eq!(parent.filled_quantity(), 2.0);
+    assert_eq!(bt.free_balance().unwrap(), 40.0);
+    #[cfg(feature = "metrics")]
+    assert!(
+        bt.events()
+            .any(|e| matches!(e, Event::RejectOrder { reason, .. } if reason.starts_with("Insufficient funds")))
+    );
+}
+
+#[test]
+fn scenario_iceberg_parent_order_cancelled() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+
+    bt.run(|bt, _| {
+        if bt.index == 0 {
+            let order = Order::from((OrderType::Limit(105.0), 5.0, OrderSide::Buy));
+            bt.place_parent_order(ParentOrder::new(order, ExecutionAlgo::Iceberg(2.0))?)?;
+        }
+        if bt.index == 2 {
+            let parent = bt.parent_orders().next().unwrap().clone();
+            bt.cancel_parent_order(&parent)?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // 2 filled on each of the first candles, the last child is cancelled
+    let parent = bt.parent_orders().next().unwrap();
+    assert_eq!(parent.status(), ParentStatus::Cancelled);
+    assert_eq!(parent.filled_quantity(), 4.0);
+    assert_eq!(parent.remaining_quantity(), 1.0);
+    assert!(bt.orders().next().is_none());
+    assert_eq!(bt.locked(), 0.0);
+    assert_eq!(bt.balance(), 580.0);
+    assert!(bt.cancel_parent_order(&parent.clone()).is_err());
+}
+
+#[test]
+fn scenario_scheduled_and_on_demand_cashflows() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_cashflow(Schedule::EveryCandles(2), 100.0);
+
+    bt.run(|bt, _| {
+        if bt.index == 1 {
+            bt.withdraw(50.0)?;
+            assert!(bt.withdraw(2000.0).is_err());
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // deposits on the first and third candles
+    assert_eq!(bt.balance(), 1150.0);
+    assert_eq!(bt.net_deposits(), 150.0);
+
+    #[cfg(feature = "metrics")]
+    {
+        let cashflows = bt
+            .events()
+            .filter(|e| matches!(e, Event::Deposit { .. } | Event::Withdrawal { .. }))
+            .count();
+        assert_eq!(cashflows, 3);
+        assert_eq!(Metrics::from(&bt).time_weighted_return(), 0.0);
+    }
+}
+
+#[test]
+fn scenario_instrument_multiplier() {
+    let data = get_long_data();
+    let instrument = Instrument::new()
+        .with_tick_size(0.5)
+        .with_lot_step(1.0)
+        .with_multiplier(5.0);
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap().with_instrument(instrument);
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            let take_profit = OrderType::TakeProfitAndStopLoss(110.0, 0.0);
+            // rounded to 1 contract
+            let order = Order::from((OrderType::Market(candle.close()), take_profit, 1.7, OrderSide::Buy));
+            bt.place_order(order)?;
+            assert_eq!(bt.locked(), 500.0);
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // enter at 100, exit at 110: 10 points * 5
+    assert!(bt.positions.is_empty());
+    assert_eq!(bt.balance(), 1050.0);
+}
+
+fn get_symbol_data(closes: &[(i64, f64)]) -> Vec<Candle> {
+    closes
+        .iter()
+        .map(|(hours, close)| testing::get_flat_candle(testing::hour(*hours), *close))
+        .collect()
+}
+
+#[test]
+fn scenario_portfolio_shared_wallet() {
+    let series = vec![
+        ("A", get_symbol_data(&[(0, 10.0), (1, 11.0), (2, 12.0)])),
+        // no candle at the second step
+        ("B", get_symbol_data(&[(0, 20.0), (2, 22.0)])),
+    ];
+    let mut bt = Backtest::portfolio(series, 1000.0, None).unwrap();
+    assert_eq!(bt.symbols().collect::<Vec<_>>(), vec!["A", "B"]);
+
+    bt.run_portfolio(|bt, candles| {
+        match bt.index {
+            0 => {
+                assert_eq!(candles.len(), 2);
+                for (symbol, candle) in candles {
+                    let order = Order::from((
+                        OrderType::Market(candle.close()),
+                        100.0 / candle.close(),
+                        OrderSide::Buy,
+                    ))
+                    .with_symbol(symbol.as_str());
+                    bt.place_order(order)?;
+                }
+                let order = Order::from((OrderType::Market(10.0), 1.0, OrderSide::Buy));
+                assert!(matches!(bt.place_order(order.clone()), Err(Error::MissingSymbol)));
+                let order = order.with_symbol("C");
+                assert!(matches!(bt.place_order(order), Err(Error::UnknownSymbol(_))));
+            }
+            1 => {
+                assert_eq!(candles.keys().collect::<Vec<_>>(), vec!["A"]);
+                assert!(bt.candle_of("B").is_none());
+                assert_eq!(bt.last_candle_of("B").unwrap().close(), 20.0);
+                // B is valued at its last known close
+                assert_eq!(bt.unrealized_pnl(), 0.0);
+            }
+            _ => assert_eq!(bt.unrealized_pnl(), 10.0),
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // A: 10 * (12 - 10), B: 5 * (22 - 20)
+    assert_eq!(bt.positions().count(), 2);
+    assert_eq!(bt.unrealized_pnl(), 30.0);
+    assert_eq!(bt.balance(), 800.0);
+}
+
+#[test]
+fn scenario_portfolio_strategy_context() {
+    let series = vec![
+        ("A", get_symbol_data(&[(0, 10.0), (1, 11.0), (2, 12.0)])),
+        ("B", get_symbol_data(&[(0, 20.0), (2, 22.0)])),
+    ];
+    let mut bt = Backtest::portfolio(series, 1000.0, None).unwrap();
+    let mut lengths = Vec::new();
+    bt.run_portfolio_with_context(|ctx, candles| {
+        // the context ends at the current step for every symbol, and has no single series
+        assert!(ctx.candles().is_empty() && ctx.history(2).is_empty());
+        assert_eq!(ctx.candle(), None);
+        assert_eq!(ctx.time(), Some(testing::hour(ctx.index() as i64)));
+        assert_eq!(ctx.candles_of("A").last(), candles.get("A"));
+        assert!(ctx.candles_of("C").is_empty());
+        lengths.push((ctx.candles_of("A").len(), ctx.candles_of("B").len()));
+        // the debug output shows the visible candles only
+        let debug = format!("{ctx:?}");
+        assert!(debug.starts_with("StrategyContext { candles: {\"A\": ["));
+        let (a, b) = (ctx.candles_of("A").len(), ctx.candles_of("B").len());
+        assert_eq!(debug.matches("open_time").count(), a + b);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(lengths, [(1, 1), (2, 1), (3, 2)]);
+    assert_eq!(bt.candles().count(), 0);
+}
+
+#[test]
+fn scenario_portfolio_time_exit_counts_symbol_candles() {
+    let series = vec![
+        ("A", get_symbol_data(&[(0, 10.0), (1, 11.0), (2, 12.0), (3, 13.0)])),
+        // no candle at the second step
+        ("B", get_symbol_data(&[(0, 20.0), (2, 22.0), (3, 23.0)])),
+    ];
+    let mut bt = Backtest::portfolio(series, 1000.0, None).unwrap();
+    bt.run_portfolio(|bt, _| {
+        if bt.index == 0 {
+            let order = Order::from((OrderType::Market(20.0), 1.0, OrderSide::Buy))
+                .with_symbol("B")
+                .with_time_exit(TimeExit::Candles(2));
+            bt.place_order(order)?;
+        }
+        // the step without candle for B is not counted: still open at the fourth step
+        assert_eq!(bt.positions().count(), usize::from(bt.index > 0));
+        Ok(())
+    })
+    .unwrap();
+
+    // closed at the close of the second candle of B after the fill
+    assert_eq!(bt.positions().count(), 0);
+    assert_eq!(bt.balance(), 1003.0);
+}
+
+#[test]
+fn scenario_rebalance_with_band() {
+    let series = vec![
+        ("A", get_symbol_data(&[(0, 10.0), (1, 20.0), (2, 20.0)])),
+        ("B", get_symbol_data(&[(0, 10.0), (1, 10.0), (2, 10.0)])),
+    ];
+    let mut bt = Backtest::portfolio(series, 1000.0, None)
+        .unwrap()
+        .with_symbol_instrument("A", Instrument::new().with_lot_step(1.0))
+        .with_rebalance(Rebalance::new().with_band(5.0));
+    let quantity = |bt: &Backtest, symbol: &str| {
+        bt.positions()
+            .filter(|p| p.symbol() == Some(symbol))
+            .map(|p| p.quantity)
+            .sum::<f64>()
+    };
+
+    bt.run_portfolio(|bt, _| {
+        let weights = [("A", 0.5), ("B", 0.5)];
+        let orders = bt.rebalance_to(&weights)?;
+        match bt.index {
+            0 => assert_eq!(orders.len(), 2),
+            1 => {
+                // A drifted to 1000 / 1500: 12.5 A are sold, rounded down to the lot step
+                assert_eq!(quantity(bt, "A"), 38.0);
+                assert_eq!(orders.len(), 1);
+                assert_eq!(orders[0].quantity, 24.0);
+            }
+            // 760 / 1500 is inside the band
+            _ => assert!(orders.is_empty()),
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(quantity(&bt, "A"), 38.0);
+    assert_eq!(quantity(&bt, "B"), 74.0);
+    assert_eq!(bt.balance(), 0.0);
+    assert!(matches!(
+        bt.rebalance_to(&[("A", 0.7), ("B", 0.7)]),
+        Err(Error::InvalidWeights)
+    ));
+    assert!(matches!(bt.rebalance_to(&[("C", 0.5)]), Err(Error::UnknownSymbol(_))));
+}
+
+#[test]
+fn scenario_rebalance_on_schedule() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_rebalance(Rebalance::new().with_schedule(Schedule::EveryCandles(2)));
+
+    bt.run(|bt, _| {
+        let orders = bt.rebalance_to(&[("BTC", 0.5)])?;
+        match bt.index {
+            0 => assert_eq!(orders[0].quantity, 5.0),
+            1 => assert!(orders.is_empty()),
+            _ => {
+                // equity of 500 + 5 * 120, the excess of 50 is sold
+                let quantity = bt.positions().map(|p| p.quantity).sum::<f64>();
+                assert!((quantity - (5.0 - 50.0 / 120.0)).abs() < 1e-9);
+                assert_eq!(bt.balance(), 550.0);
+            }
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // the holdings of a single-asset backtest can't be split between symbols
+    assert!(matches!(
+        bt.rebalance_to(&[("BTC", 0.3), ("ETH", 0.3)]),
+        Err(Error::InvalidWeights)
+    ));
+}
+
+#[test]
+fn scenario_futures_roll() {
+    let base = DateTime::from_timestamp_secs(1735689600).unwrap();
+    let series = vec![
+        ("F1", get_symbol_data(&[(0, 100.0), (1, 102.0), (2, 104.0), (3, 106.0)])),
+        ("F2", get_symbol_data(&[(0, 110.0), (1, 112.0), (2, 115.0), (3, 117.0)])),
+    ];
+    // F1 is rolled on the third candle
+    let chain = ContractChain::new(vec![
+        Contract::new("F2", base + chrono::Duration::days(30)),
+        Contract::new("F1", base + chrono::Duration::days(1) + chrono::Duration::hours(2)),
+    ])
+    .unwrap()
+    .with_roll_days(1)
+    .with_roll_cost(1.0);
+    let mut bt = Backtest::portfolio(series, 1000.0, None)
+        .unwrap()
+        .with_contract_chain(chain);
+    assert_eq!(bt.contract_chain().unwrap().active(base).unwrap().symbol(), "F1");
+
+    let back_adjusted = bt.continuous_series(Adjustment::BackAdjusted).unwrap();
+    assert_eq!(
+        back_adjusted.iter().map(|c| c.close()).collect::<Vec<_>>(),
+        vec![111.0, 113.0, 115.0, 117.0]
+    );
+    let ratio_adjusted = bt.continuous_series(Adjustment::RatioAdjusted).unwrap();
+    assert_eq!(ratio_adjusted[0].close(), 100.0 * 115.0 / 104.0);
+    assert_eq!(ratio_adjusted[3].close(), 117.0);
+
+    bt.run_portfolio(|bt, _| {
+        match bt.index {
+            0 => {
+                let order = Order::from((
+                    OrderType::Market(100.0),
+                    OrderType::TakeProfitAndStopLoss(108.0, 99.0),
+                    1.0,
+                    OrderSide::Buy,
+                ))
+                .with_exit_rule(OrderType::TrailingStop(100.0, 10.0))
+                .with_symbol("F1");
+                bt.place_order(order)?;
+            }
+            3 => {
+                // the rules are shifted by the spread of 11 (115 - 104), the trailing stop from its high of 104
+                let position = bt.positions().next().unwrap();
+                assert_eq!(
+                    position.exit_rules(),
+                    [
+                        OrderType::TakeProfitAndStopLoss(119.0, 110.0),
+                        OrderType::TrailingStop(115.0, 10.0)
+                    ]
+                );
+                assert_eq!(position.initial_stop(), Some(110.0));
+            }
+            _ => {}
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    let position = bt.positions().next().unwrap();
+    assert_eq!(position.symbol(), Some("F2"));
+    assert_eq!(position.entry_price().unwrap(), 115.0);
+    assert_eq!(position.entry_index(), Some(0));
+    assert_eq!(position.entry_time(), Some(base));
+    // 1000 - 100 + 104 - 115 - 1 (roll cost)
+    assert_eq!(bt.balance(), 888.0);
+    assert_eq!(bt.unrealized_pnl(), 2.0);
+    assert_eq!(bt.locked(), 0.0);
+    bt.reconcile().unwrap();
+    #[cfg(feature = "metrics")]
+    {
+        assert_eq!(exit_reasons(&bt), vec![ExitReason::Roll]);
+        assert!(
+            bt.events()
+                .any(|e| matches!(e, Event::Roll { cost, .. } if *cost == 1.0))
+        );
+    }
+}
+
+#[test]
+fn scenario_portfolio_strategy_roll() {
+    /// Buys the front contract and records the fills and the closes.
+    #[derive(Default)]
+    struct Roller(Vec<String>);
+
+    impl Strategy for Roller {
+        fn on_candle(&mut self, _ctx: &mut StrategyContext, _candle: &Candle) -> Result<()> {
+            Ok(())
+        }
+
+        fn on_portfolio_candles(
+            &mut self,
+            ctx: &mut StrategyContext,
+            candles: &BTreeMap<String, Candle>,
+        ) -> Result<()> {
+            if ctx.index() == 0 {
+                let close = candles["F1"].close();
+                ctx.place_order(Order::from((OrderType::Market(close), 1.0, OrderSide::Buy)).with_symbol("F1"))?;
+            }
+            Ok(())
+        }
+
+        fn on_order_filled(&mut self, _ctx: &mut StrategyContext, position: &Position) -> Result<()> {
+            self.0.push(format!("filled {}", position.symbol().unwrap_or_default()));
+            Ok(())
+        }
+
+        fn on_position_closed(
+            &mut self,
+            _ctx: &mut StrategyContext,
+            position: &Position,
+            reason: &ExitReason,
+            _pnl: f64,
+        ) -> Result<()> {
+            self.0
+                .push(format!("closed {} {reason:?}", position.symbol().unwrap_or_default()));
+            Ok(())
+        }
+    }
+
+    let base = DateTime::from_timestamp_secs(1735689600).unwrap();
+    let series = vec![
+        ("F1", get_symbol_data(&[(0, 100.0), (1, 102.0), (2, 104.0)])),
+        ("F2", get_symbol_data(&[(0, 110.0), (1, 112.0), (2, 115.0)])),
+    ];
+    let chain = ContractChain::new(vec![
+        Contract::new("F1", base + chrono::Duration::days(1) + chrono::Duration::hours(2)),
+        Contract::new("F2", base + chrono::Duration::days(30)),
+    ])
+    .unwrap()
+    .with_roll_days(1);
+    let mut bt = Backtest::portfolio(series, 1000.0, None)
+        .unwrap()
+        .with_contract_chain(chain);
+    let mut strategy = Roller::default();
+    bt.run_portfolio_strategy(&mut strategy).unwrap();
+
+    // the roll closes the position in F1, but goes on in F2 without a new fill
+    assert_eq!(strategy.0, ["filled F1", "closed F1 Roll"]);
+    assert_eq!(bt.positions().next().unwrap().symbol(), Some("F2"));
+    assert!(bt.notifications.is_none());
+}
+
+#[test]
+fn scenario_covered_call() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 105.0), (2, 110.0)]);
+    let expiry = data[2].close_time();
+    let call = OptionContract::new("C100", "S", OptionKind::Call, 100.0, expiry);
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_option(call.clone())
+        .with_volatility("S", vec![0.2]);
+    let mut premium = 0.0;
+
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            bt.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+            premium = bt.option_value("C100")?;
+            assert_eq!(premium, call.price(100.0, 0.2, candle.close_time()));
+            bt.open_option("C100", 1.0, OrderSide::Sell)?;
+
+            let position = bt.positions().find(|p| p.symbol() == Some("C100")).unwrap();
+            let greeks = bt.position_greeks(position).unwrap();
+            assert!(greeks.delta < 0.0 && greeks.theta > 0.0);
+            assert_eq!(bt.greeks(), greeks);
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // the call settles at its intrinsic value of 10
+    assert_eq!(bt.positions().count(), 1);
+    assert!((bt.balance() - (890.0 + premium)).abs() < 1e-9);
+    assert_eq!(bt.unrealized_pnl(), 10.0);
+    assert!(bt.locked().abs() < 1e-9);
+    bt.reconcile().unwrap();
+    #[cfg(feature = "metrics")]
+    assert_eq!(exit_reasons(&bt), vec![ExitReason::Expiry]);
+    assert!(matches!(
+        bt.open_option("P", 1.0, OrderSide::Buy),
+        Err(Error::UnknownSymbol(_))
+    ));
+}
+
+#[test]
+fn scenario_option_trading_window() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 105.0), (2, 110.0)]);
+    let call = OptionContract::new("C100", "S", OptionKind::Call, 100.0, data[2].close_time());
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_option(call)
+        .with_volatility("S", vec![0.2])
+        .with_window(1..3);
+
+    bt.run(|bt, _| {
+        match bt.index {
+            // the warm-up candle can't write the option
+            0 => assert!(matches!(
+                bt.open_option("C100", 1.0, OrderSide::Sell),
+                Err(Error::OutsideWindow)
+            )),
+            1 => bt.open_option("C100", 1.0, OrderSide::Sell)?,
+            _ => {}
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    #[cfg(feature = "metrics")]
+    assert_eq!(exit_reasons(&bt), vec![ExitReason::Expiry]);
+    assert!(bt.locked().abs() < 1e-9);
+    bt.reconcile().unwrap();
+}
+
+#[test]
+fn scenario_split_and_dividend() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 100.0), (2, 50.0), (3, 52.0)]);
+    let split = CorporateAction::split("S", data[2].open_time(), 2.0);
+    let dividend = CorporateAction::dividend("S", data[3].open_time(), 1.0);
+    let adjusted = adjust_candles(&data, &[split.clone(), dividend.clone()]).unwrap();
+    assert_eq!(
+        adjusted.iter().map(|c| c.close()).collect::<Vec<_>>(),
+        vec![49.0, 49.0, 49.0, 52.0]
+    );
+    assert_eq!(adjusted[0].volume(), 2.0);
+
+    // dated before the data, the old split is already in the prices
+    let old_split = CorporateAction::split("S", data[0].open_time() - chrono::Duration::days(1), 3.0);
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_corporate_actions(vec![dividend, split, old_split]);
+    bt.run(|bt, candle| {
+        match bt.index {
+            0 => bt.place_order(Order::from((OrderType::Market(candle.close()), 2.0, OrderSide::Buy)))?,
+            1 => {
+                // the split is not known yet
+                let closes = bt.adjusted_candles("S")?.iter().map(|c| c.close()).collect::<Vec<_>>();
+                assert_eq!(closes, vec![100.0, 100.0]);
+            }
+            2 => {
+                let position = bt.positions().next().unwrap();
+                assert_eq!(position.quantity, 4.0);
+                assert_eq!(position.entry_price()?, 50.0);
+                assert_eq!(bt.adjusted_candles("S")?[0].close(), 50.0);
+            }
+            _ => assert_eq!(bt.balance(), 804.0),
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(bt.unrealized_pnl(), 8.0);
+    #[cfg(feature = "metrics")]
+    {
+        assert_eq!(bt.events().filter(|e| matches!(e, Event::Split { .. })).count(), 1);
+        assert!(
+            bt.events()
+                .any(|e| matches!(e, Event::Split { ratio, .. } if *ratio == 2.0))
+        );
+        assert!(
+            bt.events()
+                .any(|e| matches!(e, Event::Dividend { amount, .. } if *amount == 4.0))
+        );
+    }
+}
+
+#[test]
+fn scenario_account_currency_conversion() {
+    let data = get_long_data();
+    let mut bt = Backtest::new(data, 1000.0, Some((0.01, 0.01)))
+        .unwrap()
+        .with_instrument(Instrument::new().with_currency("USD"))
+        .with_account_currency("EUR")
+        .with_fx_rate("USD", vec![0.9, 0.9, 1.0]);
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+    bt.run(|bt, candle| {
+        match bt.index {
+            0 => {
+                let order = Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy));
+                bt.place_order(order)?;
+                assert!(close(bt.locked(), 90.0));
+            }
+            // valued at the close of the previous candle: (100 + 10) * 0.9 - 90
+            2 => assert!(close(bt.unrealized_pnl(), 9.0)),
+            _ => {}
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    // 120 * 1.0 - 90, the rate gain included
+    assert!(close(bt.unrealized_pnl(), 30.0));
+    let position = bt.positions().next().cloned().unwrap();
+    assert_eq!(position.fx_rate(), 0.9);
+    assert!(close(bt.close_position(&position, 120.0, true).unwrap(), 30.0));
+    // 1000 - 90 - 0.9 (fees) + 120 - 1.0 (fees on the cost of 100 USD at 1.0)
+    assert!(close(bt.balance(), 1028.1));
+    assert!(close(bt.fees_paid(), 1.9));
+
+    let mut bt = Backtest::new(get_long_data(), 1000.0, None)
+        .unwrap()
+        .with_instrument(Instrument::new().with_currency("JPY"))
+        .with_account_currency("EUR");
+    let order = Order::from((OrderType::Market(100.0), 1.0, OrderSide::Buy));
+    assert!(matches!(bt.place_order(order), Err(Error::MissingFxRate(_))));
+}
+
+#[test]
+fn scenario_ledger_reconciliation() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 110.0), (2, 120.0)]);
+    let mut bt = Backtest::new(data, 1000.0, Some((0.01, 0.01))).unwrap();
+
+    bt.run(|bt, candle| {
+        match bt.index {
+            0 => {
+                bt.place_order(Order::from((OrderType::Limit(50.0), 1.0, OrderSide::Buy)))?;
+                bt.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+            }
+            1 => {
+                let order = bt.orders().next().cloned().unwrap();
+                bt.delete_order(&order, true)?;
+            }
+            _ => {}
+        }
+        bt.reconcile()
+    })
+    .unwrap();
+    let position = bt.positions().next().cloned().unwrap();
+    assert!((bt.close_position(&position, 120.0, true).unwrap() - 20.0).abs() < 1e-9);
+    bt.reconcile().unwrap();
+    assert!((bt.balance() - 1018.0).abs() < 1e-9);
+
+    let ledger = bt.ledger();
+    assert_eq!(ledger[0].reason(), EntryReason::Deposit);
+    assert_eq!(ledger[0].amount(), 1000.0);
+    let unlock = ledger
+        .iter()
+        .find(|entry| entry.reason() == EntryReason::Unlock)
+        .unwrap();
+    let lock = ledger
+        .iter()
+        .find(|entry| entry.reason() == EntryReason::Lock && entry.reference() == unlock.reference())
+        .unwrap();
+    assert_eq!(lock.amount(), 50.0);
+    assert_eq!(
+        unlock.time(),
+        Some(DateTime::from_timestamp_secs(1735689600).unwrap() + chrono::Duration::hours(1))
+    );
+
+    let of_position = |reason| {
+        ledger
+            .iter()
+            .filter(|entry| entry.reference() == Reference::Position(position.id()) && entry.reason() == reason)
+            .map(|entry| entry.amount())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(of_position(EntryReason::FillCost), [100.0, 100.0]);
+    assert_eq!(of_position(EntryReason::Fee), [1.0, 1.0]);
+    assert_eq!(of_position(EntryReason::RealizedPnl), [20.0]);
+    assert_eq!(ledger[ledger.len() - 1].debit(), LedgerAccount::Fees);
+}
+
+#[test]
+fn scenario_equity_curve() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 80.0), (2, 120.0)]);
+    let mut bt = Backtest::new(data.clone(), 1000.0, None).unwrap();
+    bt.run(|bt, candle| {
+        if bt.index == 0 {
+            bt.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    let equity = bt.equity_curve().map(|s| s.equity()).collect::<Vec<_>>();
+    assert_eq!(equity, [1000.0, 980.0, 1020.0]);
+    let snapshot = bt.equity_curve().nth(1).unwrap();
+    assert_eq!(snapshot.balance(), 900.0);
+    assert_eq!(snapshot.unrealized_pnl(), -20.0);
+    assert_eq!(snapshot.exposure(), 80.0);
+    assert_eq!(snapshot.time(), data[1].close_time());
+    // the dip of the open position is a drawdown
+    #[cfg(feature = "metrics")]
+    assert!((Metrics::from(&bt).max_drawdown() - 2.0).abs() < 1e-9);
+
+    // sampled every 2 candles, the last one included
+    let mut bt = Backtest::new(data.clone(), 1000.0, None)
+        .unwrap()
+        .with_equity_sampling(2);
+    bt.run(|_, _| Ok(())).unwrap();
+    assert_eq!(bt.equity_curve().count(), 2);
+
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap().with_equity_sampling(0);
+    bt.run(|_, _| Ok(())).unwrap();
+    assert_eq!(bt.equity_curve().count(), 0);
+}
+
+#[test]
+fn scenario_risk_manager_limits() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 100.0), (2, 100.0)]);
+    let buy = |price: f64, quantity: f64| Order::from((OrderType::Limit(price), quantity, OrderSide::Buy));
+    let sell = |price: f64, quantity: f64| Order::from((OrderType::Limit(price), quantity, OrderSide::Sell));
+
+    let risk = RiskManager::new()
+        .with_max_pending_orders(2)
+        .with_max_position_notional(150.0);
+    let mut bt = Backtest::new(data.clone(), 10000.0, None)
+        .unwrap()
+        .with_risk_manager(risk);
+    bt.place_order(buy(100.0, 1.0)).unwrap();
+    assert!(matches!(
+        bt.place_order(buy(100.0, 1.0)),
+        Err(Error::MaxPositionNotional(200.0, 150.0))
+    ));
+    // the sell order reduces the net notional of the symbol
+    bt.place_order(sell(100.0, 1.0)).unwrap();
+    assert!(matches!(
+        bt.place_order(sell(100.0, 1.0)),
+        Err(Error::MaxPendingOrders(2))
+    ));
+    assert_eq!(bt.locked(), 200.0);
+    #[cfg(feature = "metrics")]
+    assert_eq!(
+        bt.events().filter(|e| matches!(e, Event::RejectOrder { .. })).count(),
+        2
+    );
+
+    let risk = RiskManager::new()
+        .with_max_gross_exposure(250.0)
+        .with_max_net_exposure(150.0)
+        .with_max_order_percent(5.0);
+    let mut bt = Backtest::new(data.clone(), 10000.0, None)
+        .unwrap()
+        .with_risk_manager(risk);
+    assert!(matches!(bt.place_order(buy(100.0, 6.0)), Err(Error::MaxOrderSize(..))));
+    bt.place_order(buy(100.0, 1.0)).unwrap();
+    assert!(matches!(
+        bt.place_order(buy(100.0, 1.0)),
+        Err(Error::MaxNetExposure(..))
+    ));
+    bt.place_order(sell(100.0, 1.0)).unwrap();
+    assert!(matches!(
+        bt.place_order(sell(100.0, 1.0)),
+        Err(Error::MaxGrossExposure(..))
+    ));
+
+    let risk = RiskManager::new()
+        .with_max_open_positions(1)
+        .with_min_trade_interval(chrono::Duration::hours(2));
+    let mut bt = Backtest::new(data, 10000.0, None).unwrap().with_risk_manager(risk);
+    let mut results = Vec::new();
+    bt.run(|bt, candle| {
+        let order = Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy));
+        results.push(bt.place_order(order));
+        Ok(())
+    })
+    .unwrap();
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::MinTradeInterval(3600))));
+    assert!(matches!(results[2], Err(Error::MaxOpenPositions(1))));
+}
+
+#[test]
+fn scenario_risk_manager_option() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 105.0)]);
+    let call = OptionContract::new("C100", "S", OptionKind::Call, 100.0, data[1].close_time());
+    let risk = RiskManager::new().with_min_trade_interval(chrono::Duration::hours(1));
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_option(call)
+        .with_volatility("S", vec![0.2])
+        .with_risk_manager(risk);
+
+    bt.open_option("C100", 1.0, OrderSide::Buy).unwrap();
+    // the option trade counts for the minimum time between trades
+    assert!(matches!(
+        bt.open_option("C100", 1.0, OrderSide::Buy),
+        Err(Error::MinTradeInterval(3600))
+    ));
+    assert!(matches!(
+        bt.place_order(Order::from((OrderType::Market(100.0), 1.0, OrderSide::Buy))),
+        Err(Error::MinTradeInterval(3600))
+    ));
+    assert_eq!(bt.positions().count(), 1);
+}
+
+#[test]
+fn scenario_risk_manager_daily_loss_limit() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 50.0), (24, 50.0)]);
+    let risk = RiskManager::new().with_daily_loss_limit(300.0);
+    let mut bt = Backtest::new(data, 10000.0, None).unwrap().with_risk_manager(risk);
+    let mut results = Vec::new();
+    bt.run(|bt, candle| {
+        let quantity = if bt.index == 0 { 10.0 } else { 1.0 };
+        let order = Order::from((OrderType::Market(candle.close()), quantity, OrderSide::Buy));
+        results.push(bt.place_order(order).map_err(|e| e.to_string()));
+        Ok(())
+    })
+    .unwrap();
+
+    assert!(results[0].is_ok());
+    // 500 lost on the first day, the limit is reset on the next day
+    assert_eq!(results[1], Err("Daily loss 500 reached the limit 300".to_string()));
+    assert!(results[2].is_ok());
+
+    // a withdrawal is not a loss
+    let data = get_symbol_data(&[(0, 100.0), (1, 100.0)]);
+    let risk = RiskManager::new().with_daily_loss_limit(100.0);
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap().with_risk_manager(risk);
+    bt.run(|bt, candle| {
+        if bt.index == 1 {
+            bt.withdraw(200.0)?;
+            bt.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+        }
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(bt.positions().count(), 1);
+}
+
+#[test]
+fn scenario_position_sizing() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 102.0), (2, 98.0), (3, 100.0)]);
+    let mut bt = Backtest::new(data, 10000.0, None)
+        .unwrap()
+        .with_instrument(Instrument::new().with_lot_step(1.0).with_min_quantity(1.0));
+    bt.index = 3;
+
+    let order = Order::from((OrderType::Market(100.0), 1.0, OrderSide::Buy));
+    let stopped = Order::from((
+        OrderType::Market(100.0),
+        OrderType::TakeProfitAndStopLoss(0.0, 95.0),
+        1.0,
+        OrderSide::Buy,
+    ));
+    let size = |bt: &Backtest, sizing: Sizing, order: &Order| bt.position_size(&sizing, order);
+
+    assert_eq!(size(&bt, Sizing::FixedFractional(10.0), &order).unwrap(), 10.0);
+    // below the minimum quantity
+    assert_eq!(size(&bt, Sizing::FixedFractional(0.5), &order).unwrap(), 0.0);
+    // 100 at risk over a stop distance of 5
+    assert_eq!(size(&bt, Sizing::FixedRisk(1.0), &stopped).unwrap(), 20.0);
+    assert!(matches!(
+        size(&bt, Sizing::FixedRisk(1.0), &order),
+        Err(Error::MissingStopLoss)
+    ));
+    assert_eq!(size(&bt, Sizing::EqualRisk(2.0), &stopped).unwrap(), 40.0);
+
+    // ATR(3) = (2 + 4 + 2) / 3, 100 at risk over 2 ATR
+    let volatility = |period| Sizing::Volatility {
+        percent: 1.0,
+        period,
+        multiplier: 2.0,
+    };
+    assert_eq!(size(&bt, volatility(3), &order).unwrap(), 18.0);
+    assert!(matches!(
+        size(&bt, volatility(10), &order),
+        Err(Error::NotEnoughCandles(11))
+    ));
+
+    let kelly = Sizing::Kelly {
+        fraction: 0.5,
+        fallback: 2.0,
+    };
+    assert_eq!(size(&bt, kelly.clone(), &order).unwrap(), 2.0);
+    // W = 0.6, R = 2: a Kelly fraction of 40%, halved
+    bt.trade_pnls = vec![20.0, 20.0, 20.0, -10.0, -10.0];
+    assert_eq!(size(&bt, kelly, &order).unwrap(), 20.0);
+
+    // the open positions share the risk budget
+    bt.place_order(stopped.clone()).unwrap();
+    bt.execute_orders(&bt.data[3].clone()).unwrap();
+    assert_eq!(bt.positions().count(), 1);
+    assert_eq!(size(&bt, Sizing::EqualRisk(2.0), &stopped).unwrap(), 20.0);
+}
+
+#[test]
+fn scenario_circuit_breaker_drawdown() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 90.0), (2, 80.0), (3, 85.0), (4, 84.0), (5, 86.0)]);
+    let breaker = CircuitBreaker::new()
+        .with_max_drawdown(15.0)
+        .with_flatten(true)
+        .with_resume(Resume::Cooldown(2));
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap().with_circuit_breaker(breaker);
+
+    let mut results = Vec::new();
+    bt.run(|bt, candle| {
+        let quantity = if bt.index == 0 { 10.0 } else { 1.0 };
+        let order = Order::from((OrderType::Market(candle.close()), quantity, OrderSide::Buy));
+        results.push(bt.place_order(order).is_ok());
+        Ok(())
+    })
+    .unwrap();
+
+    // the second order lacks funds, then trading is halted at the close of the third candle
+    // on a 20% drawdown, and resumed two candles later
+    assert_eq!(results, [true, false, false, false, false, true]);
+    assert!(!bt.is_halted());
+    assert_eq!(bt.positions().count(), 1);
+    // 1000 - 1000 + 800 (flatten) - 86
+    assert_eq!(bt.balance(), 714.0);
+    #[cfg(feature = "metrics")]
+    {
+        assert_eq!(exit_reasons(&bt), vec![ExitReason::CircuitBreaker]);
+        let transitions = bt
+            .events()
+            .filter_map(|e| match e {
+                Event::Halt { reason, .. } => Some(Some(*reason)),
+                Event::Resume { .. } => Some(None),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(transitions, [Some(HaltReason::Drawdown(20.0)), None]);
+    }
+
+    // a withdrawal is not a drawdown, a deposit is not a new high
+    let data = get_symbol_data(&[(0, 100.0), (1, 100.0), (2, 90.0)]);
+    let breaker = CircuitBreaker::new().with_max_drawdown(15.0);
+    let mut bt = Backtest::new(data, 1000.0, None)
+        .unwrap()
+        .with_circuit_breaker(breaker)
+        .with_cashflow(Schedule::EveryCandles(2), -200.0);
+    bt.run(|bt, candle| {
+        match bt.index {
+            0 => bt.deposit(500.0)?,
+            1 => bt.place_order(Order::from((OrderType::Market(candle.close()), 10.0, OrderSide::Buy)))?,
+            _ => {}
+        }
+        Ok(())
+    })
+    .unwrap();
+    // the peak goes 1000, 1300 (-200 + 500), 1100 (-200): the equity of 1000 is 9% below
+    assert!(!bt.is_halted());
+}
+
+#[test]
+fn scenario_circuit_breaker_new_high() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 80.0), (2, 90.0), (3, 110.0), (4, 110.0)]);
+    let run = |flatten: bool| {
+        let breaker = CircuitBreaker::new()
+            .with_max_drawdown(15.0)
+            .with_flatten(flatten)
+            .with_resume(Resume::NewHigh);
+        let mut bt = Backtest::new(data.clone(), 1000.0, None)
+            .unwrap()
+            .with_circuit_breaker(breaker);
+        let mut halted = Vec::new();
+        bt.run(|bt, candle| {
+            if bt.index == 0 {
+                bt.place_order(Order::from((OrderType::Market(candle.close()), 10.0, OrderSide::Buy)))?;
+            }
+            halted.push(bt.is_halted());
+            Ok(())
+        })
+        .unwrap();
+        (bt, halted)
+    };
+
+    // halted on a 20% drawdown, resumed when the position makes the equity go above 1000
+    let (bt, halted) = run(false);
+    assert_eq!(halted, [false, false, true, true, false]);
+    assert_eq!(bt.positions().count(), 1);
+
+    // once flattened, the equity stays at 800 and trading never resumes
+    let (bt, halted) = run(true);
+    assert_eq!(halted, [false, false, true, true, true]);
+    assert!(bt.is_halted());
+    assert_eq!(bt.balance(), 800.0);
+}
+
+#[test]
+fn scenario_circuit_breaker_consecutive_losses() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 99.0), (2, 98.0), (3, 97.0)]);
+    let breaker = CircuitBreaker::new().with_max_consecutive_losses(2);
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap().with_circuit_breaker(breaker);
+
+    let mut results = Vec::new();
+    bt.run(|bt, candle| {
+        if let Some(position) = bt.positions().next().cloned() {
+            bt.close_position(&position, candle.close(), true)?;
+        }
+        let order = Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy));
+        results.push(bt.place_order(order).map_err(|e| e.to_string()));
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(bt.halt_reason(), Some(HaltReason::ConsecutiveLosses(2)));
+    assert!(results[2].is_ok());
+    assert_eq!(results[3], Err("Trading is halted".to_string()));
+}
+
+/// Records the calls of its hooks.
+#[derive(Default)]
+struct RecordingStrategy {
+    calls: Vec<String>,
+}
+
+impl Strategy for RecordingStrategy {
+    fn on_start(&mut self, _ctx: &mut StrategyContext) -> Result<()> {
+        self.calls.push("start".to_string());
+        Ok(())
+    }
+
+    fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> Result<()> {
+        match ctx.index() {
+            0 => {
+                let take_profit = OrderType::TakeProfitAndStopLoss(110.0, 0.0);
+                ctx.place_order(Order::from((
+                    OrderType::Market(candle.close()),
+                    take_profit,
+                    1.0,
+                    OrderSide::Buy,
+                )))?;
+                ctx.place_order(Order::from((OrderType::Limit(50.0), 1.0, OrderSide::Buy)))?;
+            }
+            1 => {
+                let order = ctx.orders().next().cloned().ok_or(Error::OrderNotFound)?;
+                ctx.delete_order(&order, true)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_order_filled(&mut self, _ctx: &mut StrategyContext, position: &Position) -> Result<()> {
+        self.calls.push(format!("filled {}", position.entry_price()?));
+        Ok(())
+    }
+
+    fn on_order_cancelled(&mut self, _ctx: &mut StrategyContext, order: &Order) -> Result<()> {
+        self.calls.push(format!("cancelled {}", order.entry_price()?));
+        Ok(())
+    }
+
+    fn on_position_closed(
+        &mut self,
+        ctx: &mut StrategyContext,
+        _: &Position,
+        reason: &ExitReason,
+        pnl: f64,
+    ) -> Result<()> {
+        self.calls.push(format!("closed {reason:?} {pnl}"));
+        // the hooks can trade: buy again at the next candle
+        let close = ctx.candle().ok_or(Error::CandleNotFound)?.close();
+        ctx.place_order(Order::from((OrderType::Market(close), 1.0, OrderSide::Buy)))
+    }
+
+    fn on_finish(&mut self, _ctx: &mut StrategyContext) -> Result<()> {
+        self.calls.push("finish".to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn scenario_strategy_hooks() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 100.0), (2, 110.0), (3, 110.0)]);
+    let expected = [
+        "start",
+        "filled 100",
+        "cancelled 50",
+        "closed TakeProfit 10",
+        "filled 110",
+        "finish",
+    ];
+
+    let mut bt = Backtest::new(data.clone(), 1000.0, None).unwrap();
+    let mut strategy = RecordingStrategy::default();
+    bt.run_strategy(&mut strategy).unwrap();
+    assert_eq!(strategy.calls, expected);
+    assert_eq!(bt.positions().count(), 1);
+    assert_eq!(bt.balance(), 900.0);
+
+    // the same strategy runs with an aggregator
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+    let mut strategy = RecordingStrategy::default();
+    bt.run_strategy_with_aggregator(&TestAggregator, &mut strategy).unwrap();
+    assert_eq!(strategy.calls, expected);
+
+    // the notifications are only recorded while a strategy runs
+    assert!(bt.notifications.is_none());
+}
+
+#[test]
+fn scenario_strategy_context() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 101.0), (2, 102.0), (3, 103.0)]);
+    let mut bt = Backtest::new(data.clone(), 1000.0, None).unwrap();
+    bt.run_with_context(|ctx, candle| {
+        // the context ends at the current candle
+        assert_eq!(ctx.candles().len(), ctx.index() + 1);
+        assert_eq!(ctx.candle(), Some(candle));
+        assert_eq!(ctx.time(), Some(candle.open_time()));
+        let history = ctx.history(2);
+        assert_eq!(history.len(), ctx.index().min(1) + 1);
+        assert_eq!(history.last(), Some(candle));
+        assert_eq!(ctx.history(10).len(), ctx.index() + 1);
+        if ctx.index() == 0 {
+            ctx.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+        } else {
+            // the position bought at 100 is marked at the current close
+            assert_eq!(ctx.equity()?, 900.0 + candle.close());
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    struct Bounds(Vec<usize>);
+
+    impl Strategy for Bounds {
+        fn on_start(&mut self, ctx: &mut StrategyContext) -> Result<()> {
+            assert_eq!(ctx.candle(), None);
+            assert_eq!(ctx.equity()?, 1000.0);
+            self.0.push(ctx.candles().len());
+            Ok(())
+        }
+
+        fn on_candle(&mut self, _ctx: &mut StrategyContext, _candle: &Candle) -> Result<()> {
+            Ok(())
+        }
+
+        fn on_finish(&mut self, ctx: &mut StrategyContext) -> Result<()> {
+            self.0.push(ctx.candles().len());
+            Ok(())
+        }
+    }
+
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+    let mut bounds = Bounds(Vec::new());
+    bt.run_strategy(&mut bounds).unwrap();
+    assert_eq!(bounds.0, [0, 4]);
+}
+
+#[test]
+fn scenario_trading_window() {
+    let data = get_symbol_data(&[(0, 100.0), (1, 101.0), (2, 102.0), (3, 103.0), (4, 104.0), (5, 105.0)]);
+    let start = data[2].open_time();
+    let strategy = |ctx: &mut StrategyContext, candle: &Candle| {
+        let order = Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy));
+        match ctx.index() {
+            // the warm-up candles can't trade
+            0 | 1 => {
+                assert!(!ctx.in_window());
+                assert!(matches!(ctx.place_order(order), Err(Error::OutsideWindow)));
+            }
+            2 => {
+                assert!(ctx.in_window());
+                assert_eq!(ctx.candles().len(), 3);
+                ctx.place_order(order)?;
+            }
+            3 | 4 => assert!(ctx.in_window()),
+            // the run stops at the end of the window
+            index => panic!("candle {index} is after the window"),
+        }
+        Ok(())
+    };
+
+    let mut bt = Backtest::new(data.clone(), 1000.0, None)
+        .unwrap()
+        .with_time_window(start, data[5].open_time());
+    assert_eq!(bt.window(), 2..5);
+    bt.run_with_context(strategy).unwrap();
+    assert_eq!(bt.index, 5);
+    assert!(!bt.in_window());
+
+    // the equity curve starts at the first candle of the window and ends at its last candle
+    let times = bt.equity_curve().map(|snapshot| snapshot.time()).collect::<Vec<_>>();
+    assert_eq!(
+        times,
+        [data[2].close_time(), data[3].close_time(), data[4].close_time()]
+    );
+    // bought at 102, marked at 104
+    assert_eq!(bt.equity_curve().last().unwrap().equity(), 1002.0);
+
+    #[cfg(feature = "metrics")]
+    {
+        // the metrics match a backtest of the window alone
+        let mut alone = Backtest::new(data[2..5].to_vec(), 1000.0, None).unwrap();
+        alone
+            .run_with_context(|ctx, candle| {
+                if ctx.index() == 0 {
+                    ctx.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+                }
+                Ok(())
+            })
+            .unwrap();
+        let (metrics, expected) = (Metrics::from(&bt), Metrics::from(&alone));
+        assert_eq!(metrics.time_weighted_return(), expected.time_weighted_return());
+        assert_eq!(metrics.max_drawdown(), expected.max_drawdown());
+        assert_eq!(metrics.money_weighted_return(), expected.money_weighted_return());
+    }
+
+    // the window is bounded by the data and kept by a reset
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap().with_window(4..10);
+    assert_eq!(bt.window(), 4..6);
+    bt.reset();
+    assert_eq!(bt.window(), 4..6);
+}
+
+#[test]
+fn scenario_time_aggregation() {
+    use chrono::FixedOffset;
+
+    // hourly candles from 02:00 UTC, missing 08:00 and 09:00
+    let hours = [2, 3, 4, 5, 6, 7, 10, 11, 12];
+    let data = get_symbol_data(&hours.map(|hour| (hour, 100.0 + hour as f64)));
+
+    // the open hour, the close hour and the close of the candles given at each step
+    fn bars_of<A: Aggregation>(data: &[Candle], aggregator: &A) -> Vec<Vec<(i64, i64, f64)>> {
+        let hour_of = |time: DateTime<chrono::Utc>| (time.timestamp() - 1735689600) / 3600;
+        let mut bars = Vec::new();
+        let mut bt = Backtest::new(data.to_vec(), 1000.0, None).unwrap();
+        bt.run_with_aggregator(aggregator, |_, candles| {
+            bars.push(
+                candles
+                    .iter()
+                    .map(|c| (hour_of(c.open_time()), hour_of(c.close_time()), c.close()))
+                    .collect(),
+            );
+            Ok(())
+        })
+        .unwrap();
+        bars
+    }
+
+    // 4-hour bars aligned on midnight, the first one and the one across the gap partly covered
+    let aggregator = TimeAggregator::new(chrono::Utc).timeframe(Timeframe::Hours(4));
+    let (first, second, third) = ((2, 4, 103.0), (4, 8, 107.0), (10, 12, 111.0));
+    assert_eq!(
+        bars_of(&data, &aggregator),
+        [
+            vec![],
+            vec![first],
+            vec![first],
+            vec![first],
+            vec![first],
+            vec![second],
+            vec![second],
+            vec![third],
+            vec![third],
+        ]
+    );
+
+    // the incomplete periods are skipped
+    let bars = bars_of(&data, &aggregator.complete_only());
+    assert_eq!(bars[..5], [vec![], vec![], vec![], vec![], vec![]]);
+    assert_eq!(bars[5..], [vec![second], vec![second], vec![second], vec![second]]);
+
+    // the days of UTC-5 end at 05:00 UTC, the candles following the order of the timeframes
+    let aggregator = TimeAggregator::new(FixedOffset::west_opt(5 * 3600).unwrap())
+        .timeframe(Timeframe::Hours(1))
+        .timeframe(Timeframe::Day);
+    let bars = bars_of(&data, &aggregator);
+    assert_eq!(bars[1], [(3, 4, 103.0)]);
+    assert_eq!(bars[2], [(4, 5, 104.0), (2, 5, 104.0)]);
+    assert_eq!(bars[8], [(12, 13, 112.0), (2, 5, 104.0)]);
+
+    // an aggregator without factors nor timeframes
+    let mut bt = Backtest::new(data, 1000.0, None).unwrap();
+    let aggregator = TimeAggregator::new(chrono::Utc);
+    assert!(matches!(
+        bt.run_with_aggregator(&aggregator, |_, _| Ok(())),
+        Err(Error::InvalidFactor)
+    ));
+}
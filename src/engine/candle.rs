@@ -78,6 +78,35 @@ impl Candle {
     pub fn is_bearish(&self) -> bool {
         self.close < self.open
     }
+
+    /// Returns the assumed price path inside the candle.
+    ///
+    /// A bullish candle goes open → low → high → close,
+    /// any other candle goes open → high → low → close.
+    pub fn intrabar_path(&self) -> [f64; 4] {
+        if self.is_bullish() {
+            [self.open, self.low, self.high, self.close]
+        } else {
+            [self.open, self.high, self.low, self.close]
+        }
+    }
+
+    /// Returns when the price is first reached on the intrabar path,
+    /// from 0.0 (at the open) to 3.0 (at the close).
+    ///
+    /// Returns `None` if the price is never reached inside the candle.
+    pub fn intrabar_position(&self, price: f64) -> Option<f64> {
+        self.intrabar_path().windows(2).enumerate().find_map(|(i, segment)| {
+            let (from, to) = (segment[0], segment[1]);
+            if price < from.min(to) || price > from.max(to) {
+                return None;
+            }
+            if from == to {
+                return Some(i as f64);
+            }
+            Some(i as f64 + (price - from) / (to - from))
+        })
+    }
 }
 
 /// Builder for creating validated `Candle` instances.
@@ -515,3 +544,40 @@ fn candle_ask_calculation() {
         .unwrap();
     assert_eq!(candle.ask(), 1000.0 - 0.0);
 }
+
+#[cfg(test)]
+#[test]
+fn candle_intrabar_path() {
+    let round = |p: f64| (p * 100.0).round() / 100.0;
+
+    let bullish = CandleBuilder::builder()
+        .open(100.0)
+        .high(110.0)
+        .low(90.0)
+        .close(105.0)
+        .volume(1000.0)
+        .open_time(DateTime::from_timestamp_secs(1515151515).unwrap())
+        .close_time(DateTime::from_timestamp_secs(1515151516).unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(bullish.intrabar_path(), [100.0, 90.0, 110.0, 105.0]);
+    assert_eq!(bullish.intrabar_position(100.0).map(round), Some(0.0));
+    assert_eq!(bullish.intrabar_position(95.0).map(round), Some(0.5));
+    assert_eq!(bullish.intrabar_position(108.0).map(round), Some(1.9));
+    assert_eq!(bullish.intrabar_position(105.0).map(round), Some(1.75));
+    assert_eq!(bullish.intrabar_position(111.0).map(round), None);
+
+    let bearish = CandleBuilder::builder()
+        .open(100.0)
+        .high(110.0)
+        .low(90.0)
+        .close(95.0)
+        .volume(1000.0)
+        .open_time(DateTime::from_timestamp_secs(1515151515).unwrap())
+        .close_time(DateTime::from_timestamp_secs(1515151516).unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(bearish.intrabar_path(), [100.0, 110.0, 90.0, 95.0]);
+    assert_eq!(bearish.intrabar_position(108.0).map(round), Some(0.8));
+    assert_eq!(bearish.intrabar_position(92.0).map(round), Some(1.9));
+}
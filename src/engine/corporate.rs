@@ -0,0 +1,282 @@
+use chrono::{DateTime, Utc};
+
+use super::{Backtest, Candle, CandleBuilder, EntryReason, PositionSide, Reference};
+use crate::errors::{Error, Result};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::Event;
+
+/// Kind of a corporate action.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActionKind {
+    /// A stock split.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of new shares for one old share (e.g., 2.0 for a 2-for-1 split, 0.1 for a 1-for-10 reverse split).
+    Split(f64),
+    /// A cash dividend.
+    ///
+    /// ### Arguments
+    /// * `0` - The amount paid per share.
+    Dividend(f64),
+}
+
+/// A corporate action of a symbol, effective on its ex-date.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorporateAction {
+    symbol: String,
+    time: DateTime<Utc>,
+    kind: ActionKind,
+}
+
+impl CorporateAction {
+    /// Creates a stock split.
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of the series. In a single-asset backtest, any symbol refers to the series of the backtest.
+    /// * `time` - The ex-date: the first candle opening at or after it trades at the split prices.
+    /// * `ratio` - The number of new shares for one old share (e.g., 2.0 for a 2-for-1 split).
+    pub fn split(symbol: impl Into<String>, time: DateTime<Utc>, ratio: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            time,
+            kind: ActionKind::Split(ratio),
+        }
+    }
+
+    /// Creates a cash dividend.
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of the series. In a single-asset backtest, any symbol refers to the series of the backtest.
+    /// * `time` - The ex-date: the positions open before the first candle opening at or after it receive the dividend.
+    /// * `amount` - The amount paid per share.
+    pub fn dividend(symbol: impl Into<String>, time: DateTime<Utc>, amount: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            time,
+            kind: ActionKind::Dividend(amount),
+        }
+    }
+
+    /// Returns the symbol of the series.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Returns the ex-date.
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// Returns the kind of the action.
+    pub fn kind(&self) -> ActionKind {
+        self.kind
+    }
+
+    /// Returns true if the action is effective on the candle, given the previous candle of the symbol.
+    ///
+    /// An action is only effective between two candles: the ones dated before the data never are.
+    fn is_due(&self, candle: &Candle, previous: Option<&Candle>) -> bool {
+        candle.open_time() >= self.time && previous.is_some_and(|previous| previous.open_time() < self.time)
+    }
+}
+
+/// Back-adjusts the candles of a series for its splits and dividends, for signal generation.
+///
+/// The prices before each ex-date are divided by the split ratio, and multiplied by
+/// `1 - dividend / close` for a dividend, the close being the one of the last candle before the ex-date.
+/// The volumes before a split are multiplied by its ratio. The last candles keep their actual prices.
+///
+/// ### Arguments
+/// * `candles` - The raw candles of the series, sorted by open time.
+/// * `actions` - The corporate actions of the series, whatever their symbol.
+///
+/// ### Returns
+/// The adjusted candles, or an error if a ratio or a dividend is invalid.
+pub fn adjust_candles(candles: &[Candle], actions: &[CorporateAction]) -> Result<Vec<Candle>> {
+    // price and volume factors of the candles, accumulated from the last action
+    let mut factors = vec![(1.0, 1.0); candles.len()];
+    let mut actions = actions.iter().collect::<Vec<_>>();
+    actions.sort_by_key(|action| action.time);
+    for action in actions.into_iter().rev() {
+        let end = candles.partition_point(|candle| candle.open_time() < action.time);
+        let (price, volume) = match action.kind {
+            ActionKind::Split(ratio) if ratio > 0.0 && ratio.is_finite() => (1.0 / ratio, ratio),
+            ActionKind::Dividend(amount) if amount >= 0.0 => match end.checked_sub(1) {
+                Some(last) if amount < candles[last].close() => (1.0 - amount / candles[last].close(), 1.0),
+                Some(_) => return Err(Error::InvalidCorporateAction),
+                None => (1.0, 1.0),
+            },
+            _ => return Err(Error::InvalidCorporateAction),
+        };
+        for factor in factors[..end].iter_mut() {
+            factor.0 *= price;
+            factor.1 *= volume;
+        }
+    }
+
+    candles
+        .iter()
+        .zip(factors)
+        .map(|(candle, (price, volume))| {
+            CandleBuilder::builder()
+                .open(candle.open() * price)
+                .high(candle.high() * price)
+                .low(candle.low() * price)
+                .close(candle.close() * price)
+                .volume(candle.volume() * volume)
+                .bid(candle.bid() * volume)
+                .open_time(candle.open_time())
+                .close_time(candle.close_time())
+                .build()
+        })
+        .collect()
+}
+
+impl Backtest {
+    /// Sets the corporate actions applied to the raw prices of the backtest.
+    ///
+    /// On the ex-date of a split, the open positions and the pending orders of the symbol have their quantity
+    /// multiplied and their prices divided by the ratio. On the ex-date of a dividend, the long positions
+    /// of the symbol are credited and the short positions debited of the dividend. The actions are applied
+    /// at the start of the candle, before the strategy is called.
+    ///
+    /// For signals on adjusted prices, see [`Backtest::adjusted_candles`].
+    pub fn with_corporate_actions(mut self, actions: Vec<CorporateAction>) -> Self {
+        self.corporate_actions.extend(actions);
+        self.corporate_actions.sort_by_key(|action| action.time);
+        self
+    }
+
+    /// Returns the corporate actions of the backtest.
+    pub fn corporate_actions(&self) -> std::slice::Iter<'_, CorporateAction> {
+        self.corporate_actions.iter()
+    }
+
+    /// Returns the candles of a symbol up to the current step (included), back-adjusted for its corporate actions.
+    ///
+    /// Only the actions effective so far are applied, so the adjusted history never looks ahead.
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of the series. In a single-asset backtest, any symbol refers to the series of the backtest.
+    pub fn adjusted_candles(&self, symbol: &str) -> Result<Vec<Candle>> {
+        let candles = self.market_history(self.market_symbol(symbol)?);
+        let last = candles.last().map(|candle| candle.open_time());
+        let actions = self
+            .corporate_actions
+            .iter()
+            .filter(|action| self.is_action_of(action, symbol) && last.is_some_and(|last| action.time <= last))
+            .cloned()
+            .collect::<Vec<_>>();
+        adjust_candles(candles, &actions)
+    }
+
+    /// Returns the symbol of a series checked against the multi-asset backtest, or `None` in a single-asset backtest.
+    fn market_symbol<'a>(&self, symbol: &'a str) -> Result<Option<&'a str>> {
+        if !self.is_portfolio() {
+            return Ok(None);
+        }
+        self.asset(symbol)
+            .map(|_| Some(symbol))
+            .ok_or_else(|| Error::UnknownSymbol(symbol.to_string()))
+    }
+
+    /// Returns true if the action applies to the symbol.
+    fn is_action_of(&self, action: &CorporateAction, symbol: &str) -> bool {
+        !self.is_portfolio() || action.symbol == symbol
+    }
+
+    /// Applies the corporate actions effective on the candle to the positions and the pending orders.
+    pub(crate) fn apply_corporate_actions(&mut self, candle: &Candle) -> Result<()> {
+        let mut due = Vec::new();
+        for action in &self.corporate_actions {
+            let symbol = self.market_symbol(&action.symbol)?;
+            let Some(candle) = self.market_candle(symbol, candle) else {
+                continue;
+            };
+            let history = self.market_history(symbol);
+            let previous = history.len().checked_sub(2).map(|index| &history[index]);
+            if action.is_due(&candle, previous) {
+                due.push(action.clone());
+            }
+        }
+
+        for action in due {
+            match action.kind {
+                ActionKind::Split(ratio) => self.apply_split(&action, ratio)?,
+                ActionKind::Dividend(amount) => self.apply_dividend(&action, amount)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Adjusts the positions and the pending orders of a symbol for a split.
+    fn apply_split(&mut self, action: &CorporateAction, ratio: f64) -> Result<()> {
+        if ratio <= 0.0 || !ratio.is_finite() {
+            return Err(Error::InvalidCorporateAction);
+        }
+        let is_portfolio = self.is_portfolio();
+        let options = &self.options;
+        let of_symbol = |symbol: Option<&str>| {
+            (!is_portfolio || symbol == Some(action.symbol.as_str()))
+                && !symbol.is_some_and(|symbol| options.contains_key(symbol))
+        };
+        for position in self.positions.iter_mut().filter(|p| of_symbol(p.symbol())) {
+            position.split(ratio);
+        }
+        let time = self.current_time().ok();
+        for order in self.orders.iter_mut().filter(|o| of_symbol(o.symbol())) {
+            //? the cost is locked again, avoiding floating point drifts of the locked funds
+            let wallet = self.wallet.on(Reference::Order(order.id()), time);
+            wallet.unlock(order.account_cost_amount()?)?;
+            order.split(ratio);
+            wallet.lock(order.account_cost_amount()?)?;
+        }
+        #[cfg(feature = "metrics")]
+        self.events.push(Event::Split {
+            symbol: action.symbol.clone(),
+            ratio,
+            time: action.time,
+        });
+        Ok(())
+    }
+
+    /// Credits the long positions and debits the short positions of a symbol for a dividend, in the account currency.
+    fn apply_dividend(&mut self, action: &CorporateAction, amount: f64) -> Result<()> {
+        if amount < 0.0 || !amount.is_finite() {
+            return Err(Error::InvalidCorporateAction);
+        }
+        let payments = self
+            .positions
+            .iter()
+            .filter(|position| self.is_action_of(action, position.symbol().unwrap_or_default()))
+            .filter(|position| !self.is_option(position.symbol()))
+            .map(|position| {
+                let cash = amount * position.quantity * position.multiplier() * self.fx_rate_of(position.symbol())?;
+                Ok(match position.side {
+                    PositionSide::Long => (position.id(), cash),
+                    PositionSide::Short => (position.id(), -cash),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let cash = payments.iter().map(|(_, cash)| cash).sum::<f64>();
+        if cash == 0.0 {
+            return Ok(());
+        }
+        for (id, cash) in payments {
+            self.posting(Reference::Position(id)).add(cash, EntryReason::Funding)?;
+        }
+        #[cfg(feature = "metrics")]
+        {
+            self.events.push(Event::Dividend {
+                symbol: action.symbol.clone(),
+                amount: cash,
+                time: action.time,
+            });
+            self.events.push(Event::from(&self.wallet));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+
+use super::{Backtest, Candle, to_f64};
+use crate::errors::Result;
+
+#[cfg(feature = "metrics")]
+use crate::metrics::Event;
+
+/// The mark-to-market state of the account at the close of a candle.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquitySnapshot {
+    time: DateTime<Utc>,
+    balance: f64,
+    unrealized_pnl: f64,
+    equity: f64,
+    exposure: f64,
+}
+
+impl EquitySnapshot {
+    /// Returns the close time of the candle.
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// Returns the balance of the wallet, without the cost of the open positions.
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
+
+    /// Returns the unrealized P&L of the open positions.
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.unrealized_pnl
+    }
+
+    /// Returns the equity: the balance plus the value of the open positions at the close.
+    pub fn equity(&self) -> f64 {
+        self.equity
+    }
+
+    /// Returns the gross exposure: the notional value of the open positions, long and short, at the close.
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+}
+
+impl Backtest {
+    /// Sets the sampling interval of the equity curve, in candles.
+    ///
+    /// Defaults to 1: a snapshot is recorded at the close of every candle. With a larger interval,
+    /// the last candle is always recorded. An interval of 0 disables the equity curve, and the
+    /// metrics fall back to the wallet updates.
+    pub fn with_equity_sampling(mut self, every: usize) -> Self {
+        self.equity_sampling = every;
+        self
+    }
+
+    /// Returns the sampling interval of the equity curve, in candles.
+    pub fn equity_sampling(&self) -> usize {
+        self.equity_sampling
+    }
+
+    /// Returns the snapshots of the equity curve, oldest first.
+    pub fn equity_curve(&self) -> std::slice::Iter<'_, EquitySnapshot> {
+        self.equity_curve.iter()
+    }
+
+    /// Returns the mark-to-market state of the account at the close of a candle.
+    ///
+    /// In a multi-asset backtest, each position is valued at the last known close of its symbol.
+    pub fn equity_snapshot(&self, candle: &Candle) -> Result<EquitySnapshot> {
+        let mut value = 0.0;
+        let mut exposure = 0.0;
+        for position in &self.positions {
+            let price = self.mark_price(position.symbol(), candle);
+            let fx_rate = self.fx_rate_of(position.symbol())?;
+            value += to_f64(position.settlement_amount(price, fx_rate)?);
+            exposure += position.quantity * price * position.multiplier() * fx_rate;
+        }
+        Ok(EquitySnapshot {
+            time: candle.close_time(),
+            balance: self.wallet.balance(),
+            unrealized_pnl: self.wallet.unrealized_pnl(),
+            equity: self.wallet.balance() + value,
+            exposure,
+        })
+    }
+
+    /// Records the snapshot of the candle if it is sampled.
+    pub(crate) fn record_equity(&mut self, candle: &Candle) -> Result<()> {
+        let Some(step) = self.index.checked_sub(self.window.start) else {
+            return Ok(());
+        };
+        let every = self.equity_sampling;
+        let last = self.index + 1 >= self.window.end;
+        if every == 0 || (!step.is_multiple_of(every) && !last) {
+            return Ok(());
+        }
+        let snapshot = self.equity_snapshot(candle)?;
+        #[cfg(feature = "metrics")]
+        self.events.push(Event::Equity(snapshot.clone()));
+        self.equity_curve.push(snapshot);
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+
+use super::{Candle, Execution, OrderType, Position, PositionSide, Wallet};
+use crate::{
+    PercentCalculus,
+    errors::{Error, Result},
+};
+
+/// Reason why a position has been closed.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitReason {
+    /// The take-profit price has been reached.
+    TakeProfit,
+    /// The stop-loss price has been reached.
+    StopLoss,
+    /// The trailing stop price has been reached.
+    TrailingStop,
+    /// A custom exit rule has been triggered.
+    Custom,
+    /// The time-based exit rule has been triggered.
+    Time,
+    /// The session has closed.
+    SessionClose,
+    /// The position has been closed by the strategy.
+    Manual,
+    /// The position has been reduced or closed by a rebalancing.
+    Rebalance,
+    /// The position has been rolled into the next contract of a futures chain.
+    Roll,
+    /// The futures contract has expired.
+    Expiry,
+    /// The position has been closed by a halt of the circuit breaker.
+    CircuitBreaker,
+}
+
+impl ExitReason {
+    /// Returns the reason of an exit rule triggered at the given price.
+    pub(crate) fn of(exit_type: &OrderType, exit_price: f64) -> Self {
+        match exit_type {
+            OrderType::TakeProfitAndStopLoss(take_profit, _) if *take_profit == exit_price => Self::TakeProfit,
+            OrderType::TakeProfitAndStopLoss(..) => Self::StopLoss,
+            OrderType::TrailingStop(..) => Self::TrailingStop,
+            _ => Self::Custom,
+        }
+    }
+}
+
+/// State of the engine given to the exit rules.
+#[derive(Debug)]
+pub struct ExitContext<'a> {
+    index: usize,
+    candles: &'a [Candle],
+    execution: Execution,
+    wallet: &'a Wallet,
+}
+
+impl<'a> ExitContext<'a> {
+    /// Creates a new exit context.
+    pub(crate) fn new(index: usize, candles: &'a [Candle], execution: Execution, wallet: &'a Wallet) -> Self {
+        Self {
+            index,
+            candles,
+            execution,
+            wallet,
+        }
+    }
+
+    /// Returns the index of the current candle.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the candles up to the current one (included).
+    pub fn candles(&self) -> &'a [Candle] {
+        self.candles
+    }
+
+    /// Returns the execution timing of the backtest.
+    pub fn execution(&self) -> Execution {
+        self.execution
+    }
+
+    /// Returns the wallet of the backtest.
+    pub fn wallet(&self) -> &'a Wallet {
+        self.wallet
+    }
+}
+
+/// Trait for the rules closing a position.
+///
+/// The engine checks the exit rules of each open position on every candle.
+/// When several rules trigger on the same candle, the first one reached on the
+/// intrabar path of the candle closes the position (see [`Candle::intrabar_path`]).
+/// The built-in rules are the [`OrderType::TakeProfitAndStopLoss`] and [`OrderType::TrailingStop`] variants,
+/// and custom rules are given with [`OrderType::Custom`].
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// /// Closes a long position when a candle closes below the lowest low of the previous candles.
+/// #[derive(Debug, Clone)]
+/// struct LowestLowExit(usize);
+///
+/// impl ExitRule for LowestLowExit {
+///     fn exit_price(&mut self, _position: &Position, candle: &Candle, context: &ExitContext) -> Result<Option<f64>> {
+///         let candles = context.candles();
+///         let previous = &candles[candles.len().saturating_sub(self.0 + 1)..candles.len() - 1];
+///         let lowest_low = previous.iter().map(|c| c.low()).fold(f64::INFINITY, f64::min);
+///         Ok((candle.close() < lowest_low).then_some(candle.close()))
+///     }
+/// }
+///
+/// let exit_rule = OrderType::custom(LowestLowExit(20));
+/// let order: Order = (OrderType::Market(100.0), exit_rule, 1.0, OrderSide::Buy).into();
+/// ```
+pub trait ExitRule: ExitRuleClone + fmt::Debug + Send + Sync {
+    /// Returns the exit price if the rule closes the position on the candle.
+    ///
+    /// ### Arguments
+    /// * `position` - The open position.
+    /// * `candle` - The current candle.
+    /// * `context` - The state of the engine.
+    fn exit_price(&mut self, position: &Position, candle: &Candle, context: &ExitContext) -> Result<Option<f64>>;
+}
+
+/// Helper trait to clone boxed exit rules, implemented for every `ExitRule + Clone`.
+pub trait ExitRuleClone {
+    /// Clones the exit rule into a box.
+    fn clone_box(&self) -> Box<dyn ExitRule>;
+}
+
+impl<T: ExitRule + Clone + 'static> ExitRuleClone for T {
+    fn clone_box(&self) -> Box<dyn ExitRule> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn ExitRule> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl ExitRule for OrderType {
+    fn exit_price(&mut self, position: &Position, candle: &Candle, context: &ExitContext) -> Result<Option<f64>> {
+        match self {
+            OrderType::TakeProfitAndStopLoss(take_profit, stop_loss) => {
+                if *take_profit < 0.0 || *stop_loss < 0.0 {
+                    return Err(Error::NegTakeProfitAndStopLoss);
+                }
+
+                let take_profit_hit = *take_profit > 0.0
+                    && match position.side {
+                        PositionSide::Long => *take_profit <= candle.high(),
+                        PositionSide::Short => *take_profit >= candle.low(),
+                    };
+                let stop_loss_hit = *stop_loss > 0.0
+                    && match position.side {
+                        PositionSide::Long => *stop_loss >= candle.low(),
+                        PositionSide::Short => *stop_loss <= candle.high(),
+                    };
+
+                let exit_price = match (take_profit_hit, stop_loss_hit) {
+                    (true, true) => {
+                        // both are reached, the first one on the intrabar path wins
+                        let take_profit_at = candle.intrabar_position(*take_profit).unwrap_or(0.0);
+                        let stop_loss_at = candle.intrabar_position(*stop_loss).unwrap_or(0.0);
+                        if stop_loss_at < take_profit_at {
+                            Some(*stop_loss)
+                        } else {
+                            Some(*take_profit)
+                        }
+                    }
+                    (true, false) => Some(*take_profit),
+                    (false, true) => Some(*stop_loss),
+                    (false, false) => None,
+                };
+                Ok(exit_price)
+            }
+            OrderType::TrailingStop(price, percent) => {
+                if *price <= 0.0 || *percent <= 0.0 {
+                    return Err(Error::NegZeroTrailingStop);
+                }
+
+                let exit_price = match position.side {
+                    PositionSide::Long => {
+                        let execute_price = price.subpercent(*percent);
+                        if execute_price >= candle.low() {
+                            Some(execute_price)
+                        } else {
+                            if candle.high() > *price {
+                                *price = candle.high();
+                            }
+                            None
+                        }
+                    }
+                    PositionSide::Short => {
+                        let execute_price = price.addpercent(*percent);
+                        if execute_price <= candle.high() {
+                            Some(execute_price)
+                        } else {
+                            if candle.low() < *price {
+                                *price = candle.low();
+                            }
+                            None
+                        }
+                    }
+                };
+                Ok(exit_price)
+            }
+            OrderType::Custom(_, rule) => rule.exit_price(position, candle, context),
+            OrderType::Market(_) | OrderType::Limit(_) => Err(Error::MismatchedOrderType),
+        }
+    }
+}
+
+#[cfg(test)]
+use super::{
+    Order, OrderSide,
+    testing::{get_candle, hour},
+};
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct CloseBelow(f64);
+
+#[cfg(test)]
+impl ExitRule for CloseBelow {
+    fn exit_price(&mut self, _position: &Position, candle: &Candle, _context: &ExitContext) -> Result<Option<f64>> {
+        Ok((candle.close() < self.0).then_some(candle.close()))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn built_in_exit_rules() {
+    let wallet = Wallet::new(1000.0).unwrap();
+    let candles = vec![get_candle(hour(0), 95.0, 125.0, 110.0, 1.0)];
+    let context = ExitContext::new(0, &candles, Execution::OnClose, &wallet);
+    let order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Buy).into();
+    let position = Position::from(order);
+
+    let mut rule = OrderType::TakeProfitAndStopLoss(120.0, 90.0);
+    assert_eq!(rule.exit_price(&position, &candles[0], &context).unwrap(), Some(120.0));
+
+    let mut rule = OrderType::TrailingStop(100.0, 10.0);
+    assert_eq!(rule.exit_price(&position, &candles[0], &context).unwrap(), None);
+    assert_eq!(rule, OrderType::TrailingStop(125.0, 10.0));
+
+    let mut rule = OrderType::Market(100.0);
+    assert!(matches!(
+        rule.exit_price(&position, &candles[0], &context),
+        Err(Error::MismatchedOrderType)
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn custom_exit_rule() {
+    let wallet = Wallet::new(1000.0).unwrap();
+    let candles = vec![get_candle(hour(0), 95.0, 125.0, 110.0, 1.0)];
+    let context = ExitContext::new(0, &candles, Execution::OnClose, &wallet);
+    let order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Buy).into();
+    let position = Position::from(order);
+
+    let mut rule = OrderType::custom(CloseBelow(100.0));
+    assert_eq!(rule.exit_price(&position, &candles[0], &context).unwrap(), None);
+
+    let mut rule = OrderType::custom(CloseBelow(115.0));
+    assert_eq!(rule.exit_price(&position, &candles[0], &context).unwrap(), Some(110.0));
+    assert_eq!(rule.clone().inner().ok(), None);
+    // a custom rule is equal to its clones only
+    assert_eq!(rule.clone(), rule);
+    assert_ne!(OrderType::custom(CloseBelow(115.0)), rule);
+}
+
+#[cfg(all(test, feature = "serde"))]
+#[test]
+fn custom_exit_rule_is_not_deserialized() {
+    use serde::{Deserialize, de::IntoDeserializer, de::value::Error as ValueError};
+
+    let deserializer = IntoDeserializer::<ValueError>::into_deserializer("Custom");
+    let error = OrderType::deserialize(deserializer).unwrap_err();
+    assert!(error.to_string().contains("unknown variant `Custom`"));
+}
@@ -0,0 +1,317 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::{Backtest, Candle, CandleBuilder, ExitReason, IntoAmount, Position, Reference};
+use crate::errors::{Error, Result};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::Event;
+
+/// A dated futures contract, traded under the symbol of one of the series of a multi-asset backtest.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    symbol: String,
+    expiry: DateTime<Utc>,
+}
+
+impl Contract {
+    /// Creates a new contract.
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of the series of the contract.
+    /// * `expiry` - The expiry date of the contract.
+    pub fn new(symbol: impl Into<String>, expiry: DateTime<Utc>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            expiry,
+        }
+    }
+
+    /// Returns the symbol of the contract.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Returns the expiry date of the contract.
+    pub fn expiry(&self) -> DateTime<Utc> {
+        self.expiry
+    }
+}
+
+/// Adjustment of the continuous series built from a contract chain.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Adjustment {
+    /// Shifts the prices before each roll by the price gap between the contracts.
+    #[default]
+    BackAdjusted,
+    /// Scales the prices before each roll by the price ratio between the contracts.
+    RatioAdjusted,
+}
+
+/// A chain of dated futures contracts, rolled automatically by the backtest.
+///
+/// The open positions of a contract are rolled into the next contract `roll_days` before its expiry,
+/// at the close of the first candle opening at or after the roll date. The positions of the last
+/// contract are closed at expiry.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::{TimeZone, Utc};
+///
+/// let chain = ContractChain::new(vec![
+///     Contract::new("ESH5", Utc.with_ymd_and_hms(2025, 3, 21, 13, 30, 0).unwrap()),
+///     Contract::new("ESM5", Utc.with_ymd_and_hms(2025, 6, 20, 13, 30, 0).unwrap()),
+/// ])
+/// .unwrap()
+/// .with_roll_days(8)
+/// .with_roll_cost(12.5);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractChain {
+    contracts: Vec<Contract>,
+    roll_days: i64,
+    roll_cost: f64,
+}
+
+impl ContractChain {
+    /// Creates a new chain, the contracts being sorted by expiry.
+    ///
+    /// ### Returns
+    /// The new chain, or an error if it is empty or a symbol is given to several contracts.
+    pub fn new(mut contracts: Vec<Contract>) -> Result<Self> {
+        let mut symbols = BTreeSet::new();
+        if contracts.is_empty()
+            || !contracts
+                .iter()
+                .all(|contract| symbols.insert(contract.symbol.as_str()))
+        {
+            return Err(Error::InvalidContractChain);
+        }
+        contracts.sort_by_key(|contract| contract.expiry);
+        Ok(Self {
+            contracts,
+            roll_days: 0,
+            roll_cost: 0.0,
+        })
+    }
+
+    /// Sets the number of days before expiry at which the positions are rolled.
+    pub fn with_roll_days(mut self, days: i64) -> Self {
+        self.roll_days = days.max(0);
+        self
+    }
+
+    /// Sets an additional cost per contract charged on each roll (e.g., the bid-ask spread of the calendar spread).
+    ///
+    /// The fees of both legs of the roll are charged as well.
+    pub fn with_roll_cost(mut self, cost: f64) -> Self {
+        self.roll_cost = cost.max(0.0);
+        self
+    }
+
+    /// Returns the contracts sorted by expiry.
+    pub fn contracts(&self) -> &[Contract] {
+        &self.contracts
+    }
+
+    /// Returns the number of days before expiry at which the positions are rolled.
+    pub fn roll_days(&self) -> i64 {
+        self.roll_days
+    }
+
+    /// Returns the cost per contract charged on each roll.
+    pub fn roll_cost(&self) -> f64 {
+        self.roll_cost
+    }
+
+    /// Returns the roll date of a contract.
+    pub fn roll_time(&self, contract: &Contract) -> DateTime<Utc> {
+        contract.expiry - Duration::days(self.roll_days)
+    }
+
+    /// Returns the contract to trade at the given time: the first one not yet rolled.
+    pub fn active(&self, time: DateTime<Utc>) -> Option<&Contract> {
+        self.contracts.iter().find(|contract| time < self.roll_time(contract))
+    }
+
+    /// Returns the contract of a symbol.
+    fn contract(&self, symbol: &str) -> Option<&Contract> {
+        self.contracts.iter().find(|contract| contract.symbol == symbol)
+    }
+
+    /// Returns the contract following a contract.
+    fn next(&self, contract: &Contract) -> Option<&Contract> {
+        let index = self.contracts.iter().position(|c| c == contract)?;
+        self.contracts.get(index + 1)
+    }
+}
+
+/// Returns a copy of the candle with adjusted prices.
+fn adjust(candle: &Candle, f: impl Fn(f64) -> f64) -> Result<Candle> {
+    CandleBuilder::builder()
+        .open(f(candle.open()))
+        .high(f(candle.high()))
+        .low(f(candle.low()))
+        .close(f(candle.close()))
+        .volume(candle.volume())
+        .bid(candle.bid())
+        .open_time(candle.open_time())
+        .close_time(candle.close_time())
+        .build()
+}
+
+impl Backtest {
+    /// Sets the chain of futures contracts rolled by the backtest.
+    ///
+    /// The contracts must be symbols of the multi-asset backtest, see [`Backtest::portfolio`].
+    pub fn with_contract_chain(mut self, chain: ContractChain) -> Self {
+        self.contract_chain = Some(chain);
+        self
+    }
+
+    /// Returns the chain of futures contracts, if any.
+    pub fn contract_chain(&self) -> Option<&ContractChain> {
+        self.contract_chain.as_ref()
+    }
+
+    /// Builds a continuous series from the contract chain, for signal generation.
+    ///
+    /// Each contract provides the candles until its roll date. The prices before each roll are
+    /// adjusted by the gap or the ratio between the closes of both contracts on the roll candle,
+    /// so the last contract keeps its actual prices. Orders must still be placed on the contracts.
+    ///
+    /// ### Returns
+    /// The continuous series, or an error if there is no chain or an adjusted price is negative.
+    pub fn continuous_series(&self, adjustment: Adjustment) -> Result<Vec<Candle>> {
+        let chain = self.contract_chain.as_ref().ok_or(Error::InvalidContractChain)?;
+        let mut segments = Vec::with_capacity(chain.contracts.len());
+        let mut start = None;
+        for (i, contract) in chain.contracts.iter().enumerate() {
+            let asset = self
+                .asset(&contract.symbol)
+                .ok_or_else(|| Error::UnknownSymbol(contract.symbol.clone()))?;
+            let end = (i + 1 < chain.contracts.len()).then(|| chain.roll_time(contract));
+            let segment = asset
+                .candles()
+                .iter()
+                .filter(|candle| start.is_none_or(|start| candle.open_time() >= start))
+                .filter(|candle| end.is_none_or(|end| candle.open_time() < end))
+                .collect::<Vec<_>>();
+            segments.push((asset, segment));
+            start = end;
+        }
+
+        // adjustment of the prices before each roll, accumulated from the last contract
+        let mut series = Vec::new();
+        let (mut gap, mut ratio) = (0.0, 1.0);
+        for i in (0..segments.len()).rev() {
+            if let Some((_, next)) = segments.get(i + 1)
+                && let Some(roll_candle) = next.first()
+                && let Some(old) = segments[i]
+                    .0
+                    .candles()
+                    .iter()
+                    .rfind(|candle| candle.open_time() <= roll_candle.open_time())
+            {
+                gap += roll_candle.close() - old.close();
+                ratio *= roll_candle.close() / old.close();
+            }
+            let mut candles = segments[i]
+                .1
+                .iter()
+                .map(|candle| match adjustment {
+                    Adjustment::BackAdjusted => adjust(candle, |price| price + gap),
+                    Adjustment::RatioAdjusted => adjust(candle, |price| price * ratio),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            candles.append(&mut series);
+            series = candles;
+        }
+        Ok(series)
+    }
+
+    /// Rolls the positions of the contracts reaching their roll date, and closes the positions of the expired contracts.
+    pub(crate) fn roll_contracts(&mut self, candle: &Candle) -> Result<()> {
+        let Some(chain) = self.contract_chain.clone() else {
+            return Ok(());
+        };
+        let time = candle.open_time();
+        let mut positions = VecDeque::with_capacity(self.positions.len());
+        while let Some(position) = self.positions.pop_front() {
+            let Some(contract) = position.symbol().and_then(|symbol| chain.contract(symbol)) else {
+                positions.push_back(position);
+                continue;
+            };
+            if time < chain.roll_time(contract) {
+                positions.push_back(position);
+                continue;
+            }
+            let exit_price = self.mark_price(position.symbol(), candle);
+            let next = chain
+                .next(contract)
+                .and_then(|next| Some((next, self.market_candle(Some(&next.symbol), candle)?)));
+            match next {
+                //? the rolled position is opened at the back of the queue, and checked again
+                Some((next, next_candle)) => {
+                    self.roll_position(&position, exit_price, next, &next_candle, chain.roll_cost)?;
+                }
+                None if time >= contract.expiry => {
+                    self.close_position_with(&position, exit_price, false, ExitReason::Expiry)?;
+                }
+                //? the next contract has no candle yet, the roll waits
+                None => positions.push_back(position),
+            }
+        }
+        self.positions.append(&mut positions);
+        Ok(())
+    }
+
+    /// Closes a position and opens the equivalent one in the next contract at its close.
+    ///
+    /// The rolled position keeps the entry time and the price-based exit rules, shifted by the spread
+    /// between the contracts. The strategy hooks see the close with [`ExitReason::Roll`], but no new fill.
+    fn roll_position(
+        &mut self,
+        position: &Position,
+        exit_price: f64,
+        next: &Contract,
+        next_candle: &Candle,
+        roll_cost: f64,
+    ) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        let fees = self.wallet.fees_paid();
+        self.close_position_with(position, exit_price, false, ExitReason::Roll)?;
+        let spread = next_candle.close() - exit_price;
+        let order = position.rolled(&next.symbol, next_candle.close(), spread);
+        let mut order = self.instrument_of(Some(&next.symbol)).normalize(order)?;
+        let fx_rate = self.fx_rate_of(Some(&next.symbol))?;
+        order.set_fx_rate(fx_rate);
+        self.posting(Reference::Order(order.id()))
+            .lock(order.account_cost_amount()?)?;
+        let mut rolled = Position::from(order);
+        rolled.roll_from(position, spread);
+        #[cfg(feature = "metrics")]
+        let to = rolled.clone();
+        // the position goes on in the next contract, no new fill is reported
+        self.add_position(rolled)?;
+        if roll_cost > 0.0 {
+            self.posting(Reference::Position(position.id()))
+                .sub_fees((roll_cost * position.quantity * fx_rate).into_amount()?)?;
+        }
+        #[cfg(feature = "metrics")]
+        {
+            self.events.push(Event::from(&self.wallet));
+            self.events.push(Event::Roll {
+                from: position.clone(),
+                to,
+                cost: self.wallet.fees_paid() - fees,
+            });
+        }
+        Ok(())
+    }
+}
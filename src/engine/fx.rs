@@ -0,0 +1,62 @@
+use super::Backtest;
+use crate::errors::{Error, Result};
+
+impl Backtest {
+    /// Sets the currency of the wallet (e.g., "EUR").
+    ///
+    /// The instruments quoted in another currency, see [`Instrument::with_currency`](super::Instrument::with_currency),
+    /// have their P&L, fees and margin converted with the rates of [`Backtest::with_fx_rate`].
+    /// The margin of an order and the cost of its position are converted at the rate of the candle on which
+    /// the order is placed, and the position is settled at the rate of its exit candle, so the realized
+    /// and unrealized P&L include the gain or loss on the rate.
+    pub fn with_account_currency(mut self, currency: impl Into<String>) -> Self {
+        self.account_currency = Some(currency.into());
+        self
+    }
+
+    /// Returns the currency of the wallet, if any.
+    pub fn account_currency(&self) -> Option<&str> {
+        self.account_currency.as_deref()
+    }
+
+    /// Sets the rate series of a currency, one value per candle of the backtest.
+    /// The last value is used beyond the end of the series.
+    ///
+    /// ### Arguments
+    /// * `currency` - The quote currency of the instruments (e.g., "USD").
+    /// * `rates` - The value of one unit of the currency in the account currency (e.g., 0.92 for USD in a EUR account).
+    pub fn with_fx_rate(mut self, currency: impl Into<String>, rates: Vec<f64>) -> Self {
+        self.fx_rates.insert(currency.into(), rates);
+        self
+    }
+
+    /// Returns the value of one unit of a currency in the account currency at the current candle.
+    ///
+    /// ### Returns
+    /// The rate, 1 for the account currency or without account currency, or an error if the rate series is missing.
+    pub fn fx_rate(&self, currency: &str) -> Result<f64> {
+        match self.account_currency.as_deref() {
+            None => return Ok(1.0),
+            Some(account) if account == currency => return Ok(1.0),
+            _ => {}
+        }
+        let rate = self
+            .fx_rates
+            .get(currency)
+            .and_then(|rates| rates.get(self.index).or(rates.last()))
+            .copied()
+            .ok_or_else(|| Error::MissingFxRate(currency.to_string()))?;
+        if rate <= 0.0 || !rate.is_finite() {
+            return Err(Error::MissingFxRate(currency.to_string()));
+        }
+        Ok(rate)
+    }
+
+    /// Returns the rate converting the quote currency of the instrument of a symbol into the account currency.
+    pub(crate) fn fx_rate_of(&self, symbol: Option<&str>) -> Result<f64> {
+        match self.instrument_of(symbol).currency() {
+            Some(currency) => self.fx_rate(currency),
+            None => Ok(1.0),
+        }
+    }
+}
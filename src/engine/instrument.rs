@@ -0,0 +1,291 @@
+use super::{Order, OrderSide};
+use crate::errors::{Error, Result};
+
+/// What to do with an order that does not match the instrument constraints.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Rounding {
+    /// Rounds the price to the tick size and the quantity down to the lot step.
+    ///
+    /// Limit prices are rounded in the favorable direction: down for buy orders, up for sell orders.
+    #[default]
+    Round,
+    /// Rejects the order with [`Error::InvalidTickSize`] or [`Error::InvalidLotSize`].
+    Reject,
+}
+
+/// Specification of a traded instrument.
+///
+/// A zero value disables the constraint. The minimum quantity and notional are enforced with both [`Rounding`] modes.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// // E-mini S&P 500 futures
+/// let es = Instrument::new()
+///     .with_tick_size(0.25)
+///     .with_lot_step(1.0)
+///     .with_min_quantity(1.0)
+///     .with_multiplier(50.0);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    tick_size: f64,
+    lot_step: f64,
+    min_quantity: f64,
+    min_notional: f64,
+    multiplier: f64,
+    price_precision: Option<u32>,
+    quantity_precision: Option<u32>,
+    rounding: Rounding,
+    currency: Option<String>,
+}
+
+impl Default for Instrument {
+    fn default() -> Self {
+        Self {
+            tick_size: 0.0,
+            lot_step: 0.0,
+            min_quantity: 0.0,
+            min_notional: 0.0,
+            multiplier: 1.0,
+            price_precision: None,
+            quantity_precision: None,
+            rounding: Rounding::default(),
+            currency: None,
+        }
+    }
+}
+
+impl Instrument {
+    /// Creates a new instrument without constraints and a multiplier of 1.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum price increment.
+    pub fn with_tick_size(mut self, tick_size: f64) -> Self {
+        self.tick_size = tick_size.max(0.0);
+        self
+    }
+
+    /// Sets the quantity increment.
+    pub fn with_lot_step(mut self, lot_step: f64) -> Self {
+        self.lot_step = lot_step.max(0.0);
+        self
+    }
+
+    /// Sets the minimum quantity of an order.
+    pub fn with_min_quantity(mut self, min_quantity: f64) -> Self {
+        self.min_quantity = min_quantity.max(0.0);
+        self
+    }
+
+    /// Sets the minimum notional of an order (price * quantity * multiplier).
+    pub fn with_min_notional(mut self, min_notional: f64) -> Self {
+        self.min_notional = min_notional.max(0.0);
+        self
+    }
+
+    /// Sets the contract multiplier (e.g., 50 for the E-mini S&P 500 futures).
+    ///
+    /// The multiplier applies to the cost of the orders, the fees and the P&L of the positions.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        if multiplier > 0.0 {
+            self.multiplier = multiplier;
+        }
+        self
+    }
+
+    /// Sets the number of decimals of the prices.
+    pub fn with_price_precision(mut self, decimals: u32) -> Self {
+        self.price_precision = Some(decimals);
+        self
+    }
+
+    /// Sets the number of decimals of the quantities.
+    pub fn with_quantity_precision(mut self, decimals: u32) -> Self {
+        self.quantity_precision = Some(decimals);
+        self
+    }
+
+    /// Sets what to do with orders that do not match the tick size or the lot step.
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Sets the currency in which the instrument is quoted (e.g., "USD").
+    ///
+    /// The P&L, fees and margin are converted into the account currency, see [`Backtest::with_account_currency`](super::Backtest::with_account_currency).
+    pub fn with_currency(mut self, currency: impl Into<String>) -> Self {
+        self.currency = Some(currency.into());
+        self
+    }
+
+    /// Returns the minimum price increment.
+    pub fn tick_size(&self) -> f64 {
+        self.tick_size
+    }
+
+    /// Returns the quantity increment.
+    pub fn lot_step(&self) -> f64 {
+        self.lot_step
+    }
+
+    /// Returns the minimum quantity of an order.
+    pub fn min_quantity(&self) -> f64 {
+        self.min_quantity
+    }
+
+    /// Returns the minimum notional of an order.
+    pub fn min_notional(&self) -> f64 {
+        self.min_notional
+    }
+
+    /// Returns the contract multiplier.
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Returns the quote currency, if any.
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+
+    /// Rounds a quantity down to the lot step and the quantity precision.
+    pub fn round_quantity(&self, quantity: f64) -> f64 {
+        let quantity = if self.lot_step > 0.0 {
+            // the epsilon avoids flooring 0.3 / 0.1 = 2.9999999999999996 to 2
+            (quantity / self.lot_step + 1e-9).floor() * self.lot_step
+        } else {
+            quantity
+        };
+        round_to(quantity, self.quantity_precision)
+    }
+
+    /// Rounds a price to the tick size and the price precision.
+    ///
+    /// ### Arguments
+    /// * `price` - The price to round.
+    /// * `side` - Rounds down for buy orders and up for sell orders, or to the nearest tick if `None`.
+    pub fn round_price(&self, price: f64, side: Option<&OrderSide>) -> f64 {
+        let price = if self.tick_size > 0.0 {
+            let ticks = price / self.tick_size;
+            let ticks = match side {
+                Some(OrderSide::Buy) => (ticks + 1e-9).floor(),
+                Some(OrderSide::Sell) => (ticks - 1e-9).ceil(),
+                None => ticks.round(),
+            };
+            ticks * self.tick_size
+        } else {
+            price
+        };
+        round_to(price, self.price_precision)
+    }
+
+    /// Rounds or rejects an order against the specification, and sets its multiplier.
+    ///
+    /// ### Returns
+    /// The normalized order, or an error if the order does not match the specification.
+    pub fn normalize(&self, mut order: Order) -> Result<Order> {
+        let price = order.entry_price()?;
+        let side = (!order.is_market_type()).then_some(&order.side);
+        let rounded_price = self.round_price(price, side);
+        let rounded_quantity = self.round_quantity(order.quantity);
+
+        if self.rounding == Rounding::Reject {
+            if !is_close(price, rounded_price) {
+                return Err(Error::InvalidTickSize(price, self.tick_size));
+            }
+            if !is_close(order.quantity, rounded_quantity) {
+                return Err(Error::InvalidLotSize(order.quantity, self.lot_step));
+            }
+        }
+
+        if rounded_quantity < self.min_quantity || (rounded_quantity <= 0.0 && order.quantity > 0.0) {
+            return Err(Error::MinQuantity(rounded_quantity, self.min_quantity));
+        }
+        let notional = rounded_price * rounded_quantity * self.multiplier;
+        if notional < self.min_notional {
+            return Err(Error::MinNotional(notional, self.min_notional));
+        }
+
+        order.set_entry_price(rounded_price)?;
+        order.quantity = rounded_quantity;
+        order.set_multiplier(self.multiplier);
+        Ok(order)
+    }
+}
+
+/// Rounds a value to a number of decimals.
+fn round_to(value: f64, decimals: Option<u32>) -> f64 {
+    match decimals {
+        Some(decimals) => {
+            let factor = 10f64.powi(decimals as i32);
+            (value * factor).round() / factor
+        }
+        None => value,
+    }
+}
+
+/// Returns true if both values are equal up to floating point errors.
+fn is_close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(1.0)
+}
+
+#[cfg(test)]
+use super::OrderType;
+
+#[cfg(test)]
+#[test]
+fn instrument_round_order() {
+    let instrument = Instrument::new()
+        .with_tick_size(0.25)
+        .with_lot_step(0.1)
+        .with_quantity_precision(1)
+        .with_multiplier(50.0);
+
+    let order: Order = (OrderType::Limit(100.3), 1.27, OrderSide::Buy).into();
+    let order = instrument.normalize(order).unwrap();
+    assert_eq!(order.entry_price().unwrap(), 100.25);
+    assert_eq!(order.quantity, 1.2);
+    assert_eq!(order.multiplier(), 50.0);
+    assert_eq!(order.cost().unwrap(), 100.25 * 1.2 * 50.0);
+
+    let order: Order = (OrderType::Limit(100.1), 0.3, OrderSide::Sell).into();
+    let order = instrument.normalize(order).unwrap();
+    assert_eq!(order.entry_price().unwrap(), 100.25);
+    assert_eq!(order.quantity, 0.3);
+
+    let order: Order = (OrderType::Market(100.1), 1.0, OrderSide::Sell).into();
+    assert_eq!(instrument.normalize(order).unwrap().entry_price().unwrap(), 100.0);
+}
+
+#[cfg(test)]
+#[test]
+fn instrument_reject_order() {
+    let instrument = Instrument::new()
+        .with_tick_size(0.5)
+        .with_lot_step(1.0)
+        .with_min_quantity(2.0)
+        .with_min_notional(500.0)
+        .with_rounding(Rounding::Reject);
+
+    let order: Order = (OrderType::Limit(100.2), 2.0, OrderSide::Buy).into();
+    assert!(matches!(instrument.normalize(order), Err(Error::InvalidTickSize(_, _))));
+
+    let order: Order = (OrderType::Limit(100.5), 2.5, OrderSide::Buy).into();
+    assert!(matches!(instrument.normalize(order), Err(Error::InvalidLotSize(_, _))));
+
+    let order: Order = (OrderType::Limit(100.5), 1.0, OrderSide::Buy).into();
+    assert!(matches!(instrument.normalize(order), Err(Error::MinQuantity(_, _))));
+
+    let order: Order = (OrderType::Limit(100.5), 3.0, OrderSide::Buy).into();
+    assert!(matches!(instrument.normalize(order), Err(Error::MinNotional(_, _))));
+
+    let order: Order = (OrderType::Limit(100.5), 5.0, OrderSide::Buy).into();
+    assert!(instrument.normalize(order).is_ok());
+}
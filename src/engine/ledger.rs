@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+
+use super::{Amount, ZERO, to_f64};
+
+/// Reason of a wallet mutation.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryReason {
+    /// The initial balance or a deposit.
+    Deposit,
+    /// A withdrawal.
+    Withdrawal,
+    /// Funds locked by a pending order.
+    Lock,
+    /// Funds unlocked by a cancelled or split order.
+    Unlock,
+    /// The cost of a position, paid on entry and returned on exit.
+    FillCost,
+    /// The realized profit or loss of a closed position.
+    RealizedPnl,
+    /// A fee paid to the market, or the cost of a futures roll.
+    Fee,
+    /// A cashflow paid or received on a position (e.g., a dividend).
+    Funding,
+    /// A change of the unrealized profit or loss of the open positions.
+    Revaluation,
+}
+
+/// Account of the ledger debited or credited by an entry.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerAccount {
+    /// The free funds of the wallet.
+    Cash,
+    /// The funds locked by the pending orders.
+    Locked,
+    /// The cost of the open positions.
+    Positions,
+    /// The deposits minus the withdrawals, including the initial balance.
+    Capital,
+    /// The realized profit or loss.
+    RealizedPnl,
+    /// The fees paid.
+    Fees,
+    /// The cashflows of the positions.
+    Funding,
+    /// The unrealized profit or loss of the open positions.
+    UnrealizedPnl,
+    /// The counterpart of the unrealized profit or loss.
+    Valuation,
+}
+
+/// The order or the position an entry belongs to.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Reference {
+    /// The entry belongs to the whole account (e.g., a deposit or a revaluation).
+    #[default]
+    None,
+    /// The id of an order.
+    Order(u32),
+    /// The id of a position.
+    Position(u32),
+}
+
+/// A double-entry record of a wallet mutation: the amount moves from the credited account to the debited one.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    time: Option<DateTime<Utc>>,
+    reason: EntryReason,
+    debit: LedgerAccount,
+    credit: LedgerAccount,
+    amount: Amount,
+    reference: Reference,
+}
+
+impl LedgerEntry {
+    /// Creates a new entry.
+    pub(crate) fn new(
+        time: Option<DateTime<Utc>>,
+        reason: EntryReason,
+        debit: LedgerAccount,
+        credit: LedgerAccount,
+        amount: Amount,
+        reference: Reference,
+    ) -> Self {
+        Self {
+            time,
+            reason,
+            debit,
+            credit,
+            amount,
+            reference,
+        }
+    }
+
+    /// Returns the open time of the candle of the entry, or `None` before the first candle.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        self.time
+    }
+
+    /// Returns the reason of the entry.
+    pub fn reason(&self) -> EntryReason {
+        self.reason
+    }
+
+    /// Returns the debited account.
+    pub fn debit(&self) -> LedgerAccount {
+        self.debit
+    }
+
+    /// Returns the credited account.
+    pub fn credit(&self) -> LedgerAccount {
+        self.credit
+    }
+
+    /// Returns the amount of the entry, negative for a loss.
+    pub fn amount(&self) -> f64 {
+        to_f64(self.amount)
+    }
+
+    /// Returns the order or the position of the entry.
+    pub fn reference(&self) -> Reference {
+        self.reference
+    }
+
+    /// Returns the signed amount of the entry on an account: positive when debited, negative when credited.
+    pub(crate) fn net(&self, account: LedgerAccount) -> Amount {
+        let mut net = ZERO;
+        if self.debit == account {
+            net += self.amount;
+        }
+        if self.credit == account {
+            net -= self.amount;
+        }
+        net
+    }
+}
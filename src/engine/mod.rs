@@ -2,32 +2,90 @@
 //!
 //! This module provides the fundamental types for backtesting:
 //! - `Order`: Market, limit, and conditional orders.
+//! - `ParentOrder`: Large orders sliced by TWAP, VWAP or iceberg execution algorithms.
 //! - `Position`: Open trades with exit rules.
 //! - `Wallet`: Tracks balance, fees, and P&L.
+//! - `LedgerEntry`: Double-entry record of a wallet mutation.
+//! - `EquitySnapshot`: Mark-to-market state of the account at a candle close.
 //! - `Candle`: OHLCV data for backtesting.
+//! - `Calendar`: Trading sessions of an exchange.
+//! - `Instrument`: Tick size, lot size, minimums and contract multiplier.
+//! - `Rebalance`: Rules of the rebalancings to target portfolio weights.
+//! - `RiskManager`: Pre-trade limits checked when an order is placed.
+//! - `CircuitBreaker`: Trading halts on drawdown or consecutive losses.
+//! - `Sizing`: Position sizing methods, from fixed fractional to Kelly.
+//! - `Strategy`: Trading strategy with hooks on the lifecycle of the backtest.
+//! - `StrategyContext`: State of the backtest given to a strategy, without the future candles.
+//! - `ContractChain`: Dated futures contracts, rolled before expiry.
+//! - `OptionContract`: Calls and puts valued with Black-Scholes or Black-76.
+//! - `CorporateAction`: Splits and dividends of equity series.
+//! - `TimeAggregator`: Candles grouped by calendar periods (e.g., 4 hours, days, weeks, months).
+//!
+//! The trading and the metrics can be restricted to a window of the candles with [`Backtest::with_window`],
+//! the candles before it warming the strategy up.
+//!
+//! A backtest runs on a single series with [`Backtest::new`], or on several symbols
+//! sharing one wallet with [`Backtest::portfolio`].
 
+mod algo;
+mod breaker;
 mod candle;
+mod corporate;
+mod equity;
+mod exit;
+mod futures;
+mod fx;
+mod instrument;
+mod ledger;
+mod options;
 mod order;
+mod portfolio;
 mod position;
+mod rebalance;
+mod risk;
+mod schedule;
+mod session;
+mod sizing;
+mod strategy;
+mod timeframe;
 mod wallet;
+mod window;
 
-use std::collections::{VecDeque, vec_deque::Iter};
+use std::collections::{BTreeMap, VecDeque, vec_deque::Iter};
+use std::ops::Range;
 
-use crate::{
-    PercentCalculus,
-    errors::{Error, Result},
-};
+use crate::errors::{Error, Result};
 
 #[cfg(feature = "metrics")]
 use crate::metrics::*;
 
+pub use algo::*;
+pub use breaker::*;
 pub use candle::*;
+pub use corporate::*;
+pub use equity::*;
+pub use exit::*;
+pub use futures::*;
+pub use instrument::*;
+pub use ledger::*;
+pub use options::*;
 pub use order::*;
 pub use position::*;
+pub use rebalance::*;
+pub use risk::*;
+pub use schedule::*;
+pub use session::*;
+pub use sizing::*;
+pub use strategy::*;
+pub use timeframe::*;
 pub(crate) use wallet::*;
 
+use portfolio::Asset;
+
 #[cfg(test)]
 mod bts;
+#[cfg(test)]
+mod testing;
 
 #[cfg(test)]
 impl Iterator for Backtest {
@@ -80,6 +138,44 @@ pub trait Aggregation {
     fn should_aggregate(&self, factor: usize, candles: &[Candle]) -> bool {
         candles.len() == factor
     }
+
+    /// Returns the timeframes of a time-based aggregation, none by default.
+    ///
+    /// With timeframes, the candles are grouped by the calendar periods of their open time
+    /// instead of the factors, see [`TimeAggregator`].
+    fn timeframes(&self) -> &[Timeframe] {
+        &[]
+    }
+
+    /// Returns the start and the end of the period of a timeframe containing `time`, aligned in UTC by default.
+    fn period(
+        &self,
+        timeframe: &Timeframe,
+        time: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
+        timeframe.period(time, &chrono::Utc)
+    }
+
+    /// Determines if the candles of a closed period should be aggregated, even partly covering it by default.
+    fn should_aggregate_period(
+        &self,
+        _timeframe: &Timeframe,
+        _candles: &[Candle],
+        _period: (chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>),
+    ) -> bool {
+        true
+    }
+}
+
+/// Execution timing of the exits decided by the engine at the close of a candle (e.g., time-based exits).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Execution {
+    /// Fills at the close of the candle.
+    #[default]
+    OnClose,
+    /// Fills at the open of the next candle.
+    NextOpen,
 }
 
 /// Backtesting engine for trading strategies.
@@ -89,11 +185,44 @@ pub struct Backtest {
     index: usize,
     wallet: Wallet,
     data: Vec<Candle>,
+    // open times of the steps of the timeline
+    clock: Vec<chrono::DateTime<chrono::Utc>>,
     #[cfg(feature = "metrics")]
     events: Vec<Event>,
     orders: VecDeque<Order>,
+    parent_orders: Vec<ParentOrder>,
     positions: VecDeque<Position>,
     market_fees: Option<(f64, f64)>,
+    execution: Execution,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    calendar: Option<Box<dyn TradingCalendar>>,
+    flatten_at_close: bool,
+    cashflows: Vec<(Schedule, f64)>,
+    instrument: Instrument,
+    instruments: BTreeMap<String, Instrument>,
+    assets: Vec<Asset>,
+    rebalance: Rebalance,
+    contract_chain: Option<ContractChain>,
+    options: BTreeMap<String, OptionContract>,
+    volatilities: BTreeMap<String, Vec<f64>>,
+    corporate_actions: Vec<CorporateAction>,
+    account_currency: Option<String>,
+    fx_rates: BTreeMap<String, Vec<f64>>,
+    equity_sampling: usize,
+    equity_curve: Vec<EquitySnapshot>,
+    risk: RiskManager,
+    risk_day: Option<(chrono::NaiveDate, f64)>,
+    last_trade: Option<chrono::DateTime<chrono::Utc>>,
+    trade_pnls: Vec<f64>,
+    breaker: CircuitBreaker,
+    halt: Option<(HaltReason, usize)>,
+    equity_peak: f64,
+    losses_from: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    notifications: Option<Vec<Notification>>,
+    window: Range<usize>,
+    #[cfg(feature = "metrics")]
+    window_opening: (usize, f64),
 }
 
 impl std::ops::Deref for Backtest {
@@ -123,29 +252,249 @@ impl Backtest {
     /// ### Returns
     /// The new backtest instance or an error.
     pub fn new(data: Vec<Candle>, initial_balance: f64, market_fees: Option<(f64, f64)>) -> Result<Self> {
-        if data.is_empty() {
+        let clock = data.iter().map(|candle| candle.open_time()).collect();
+        Self::with_clock(data, clock, initial_balance, market_fees)
+    }
+
+    /// Creates a new backtest instance stepping through a timeline.
+    ///
+    /// ### Arguments
+    /// * `data` - Vector of candle data, empty in a multi-asset backtest.
+    /// * `clock` - The open times of the steps.
+    /// * `initial_balance` - Initial wallet balance.
+    /// * `market_fees` - Market *(market and limit)* fee percentage, see [`Backtest::new`].
+    fn with_clock(
+        data: Vec<Candle>,
+        clock: Vec<chrono::DateTime<chrono::Utc>>,
+        initial_balance: f64,
+        market_fees: Option<(f64, f64)>,
+    ) -> Result<Self> {
+        if clock.is_empty() {
             return Err(Error::CandleDataEmpty);
         }
 
-        if let Some((market_fee, limit_fee)) = market_fees {
-            if market_fee <= 0.0 || limit_fee <= 0.0 {
-                return Err(Error::NegZeroFees);
-            }
+        if let Some((market_fee, limit_fee)) = market_fees
+            && (market_fee <= 0.0 || limit_fee <= 0.0)
+        {
+            return Err(Error::NegZeroFees);
         }
 
+        let len = clock.len();
         Ok(Self {
             data,
+            clock,
             index: 0,
             market_fees,
+            execution: Execution::default(),
+            calendar: None,
+            flatten_at_close: false,
+            cashflows: Vec::new(),
+            instrument: Instrument::default(),
+            instruments: BTreeMap::new(),
+            assets: Vec::new(),
+            rebalance: Rebalance::default(),
+            contract_chain: None,
+            options: BTreeMap::new(),
+            volatilities: BTreeMap::new(),
+            corporate_actions: Vec::new(),
+            account_currency: None,
+            fx_rates: BTreeMap::new(),
+            equity_sampling: 1,
+            equity_curve: Vec::new(),
+            risk: RiskManager::default(),
+            risk_day: None,
+            last_trade: None,
+            trade_pnls: Vec::new(),
+            breaker: CircuitBreaker::default(),
+            halt: None,
+            equity_peak: initial_balance,
+            losses_from: 0,
+            notifications: None,
+            window: 0..len,
+            #[cfg(feature = "metrics")]
+            window_opening: (0, initial_balance),
             #[cfg(feature = "metrics")]
             events: Vec::new(),
             orders: VecDeque::new(),
+            parent_orders: Vec::new(),
             positions: VecDeque::new(),
             wallet: Wallet::new(initial_balance)?,
         })
     }
 
-    /// Returns an iterator over the data.
+    /// Sets the execution timing of the exits decided at the close of a candle.
+    ///
+    /// Defaults to [`Execution::OnClose`].
+    pub fn with_execution(mut self, execution: Execution) -> Self {
+        self.execution = execution;
+        self
+    }
+
+    /// Returns the execution timing of the backtest.
+    pub fn execution(&self) -> Execution {
+        self.execution
+    }
+
+    /// Sets the trading calendar of the backtest.
+    ///
+    /// Orders are not executed and exit rules are not checked on candles opening outside a session,
+    /// so the data may contain pre-market and post-market candles: the strategy still receives them.
+    pub fn with_calendar<C: TradingCalendar + 'static>(mut self, calendar: C) -> Self {
+        self.calendar = Some(Box::new(calendar));
+        self
+    }
+
+    /// Closes all open positions at the close of the last candle of each session.
+    ///
+    /// Requires a trading calendar, see [`Backtest::with_calendar`].
+    pub fn with_flatten_at_close(mut self, flatten: bool) -> Self {
+        self.flatten_at_close = flatten;
+        self
+    }
+
+    /// Sets the specification of the traded instrument.
+    ///
+    /// Orders are rounded or rejected against the specification when they are placed,
+    /// and the contract multiplier applies to their cost and P&L.
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instrument = instrument;
+        self
+    }
+
+    /// Returns the specification of the traded instrument.
+    pub fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+
+    /// Adds a scheduled cashflow, applied at the start of the candle before the strategy is called.
+    ///
+    /// ### Arguments
+    /// * `schedule` - When the cashflow is applied (e.g., [`Schedule::Monthly`]).
+    /// * `amount` - A positive amount for a deposit, a negative amount for a withdrawal.
+    pub fn with_cashflow(mut self, schedule: Schedule, amount: f64) -> Self {
+        self.cashflows.push((schedule, amount));
+        self
+    }
+
+    /// Deposits funds into the wallet.
+    ///
+    /// A deposit is not a profit: the metrics compute time-weighted and money-weighted returns.
+    ///
+    /// ### Arguments
+    /// * `amount` - The amount to deposit.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn deposit(&mut self, amount: f64) -> Result<()> {
+        self.posting(Reference::None).deposit(amount)?;
+        self.shift_risk_day(amount);
+        self.shift_equity_peak(amount);
+        #[cfg(feature = "metrics")]
+        {
+            let time = self.current_time()?;
+            self.events.push(Event::Deposit { amount, time });
+            self.events.push(Event::from(&self.wallet));
+        }
+        Ok(())
+    }
+
+    /// Withdraws funds from the free balance of the wallet.
+    ///
+    /// ### Arguments
+    /// * `amount` - The amount to withdraw.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error if the free balance is insufficient.
+    pub fn withdraw(&mut self, amount: f64) -> Result<()> {
+        self.posting(Reference::None).withdraw(amount)?;
+        self.shift_risk_day(-amount);
+        self.shift_equity_peak(-amount);
+        #[cfg(feature = "metrics")]
+        {
+            let time = self.current_time()?;
+            self.events.push(Event::Withdrawal { amount, time });
+            self.events.push(Event::from(&self.wallet));
+        }
+        Ok(())
+    }
+
+    /// Returns the open time of the current candle.
+    pub(crate) fn current_time(&self) -> Result<chrono::DateTime<chrono::Utc>> {
+        self.current_candle()
+            .map(|candle| candle.open_time())
+            .ok_or(Error::CandleNotFound)
+    }
+
+    /// Returns the wallet, its next ledger entries referencing the order or the position at the current candle.
+    pub(crate) fn posting(&mut self, reference: Reference) -> &mut Wallet {
+        let time = self.current_time().ok();
+        self.wallet.on(reference, time)
+    }
+
+    /// Applies the scheduled cashflows due on the candle.
+    fn apply_cashflows(&mut self, candle: &Candle) -> Result<()> {
+        let previous = self.index.checked_sub(1).and_then(|index| self.clock_candle(index));
+        let amounts = self
+            .cashflows
+            .iter()
+            .filter(|(schedule, _)| schedule.is_due(self.index, candle, previous.as_ref()))
+            .map(|(_, amount)| *amount)
+            .collect::<Vec<_>>();
+        for amount in amounts {
+            if amount >= 0.0 {
+                self.deposit(amount)?;
+            } else {
+                self.withdraw(-amount)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the trading calendar of the backtest, if any.
+    pub fn calendar(&self) -> Option<&dyn TradingCalendar> {
+        self.calendar.as_deref()
+    }
+
+    /// Returns true if the current candle opens inside a session.
+    ///
+    /// Always true without a trading calendar.
+    pub fn is_session_open(&self) -> bool {
+        self.clock_candle(self.index)
+            .is_some_and(|candle| self.in_session(&candle))
+    }
+
+    /// Returns the number of minutes between the close of the current candle and the close of the session.
+    ///
+    /// Returns `None` without a trading calendar or outside a session.
+    pub fn minutes_to_close(&self) -> Option<i64> {
+        let candle = self.clock_candle(self.index)?;
+        let (_, close) = self.calendar.as_ref()?.session(candle.open_time())?;
+        Some((close - candle.close_time()).num_minutes().max(0))
+    }
+
+    /// Returns true if the candle opens inside a session.
+    fn in_session(&self, candle: &Candle) -> bool {
+        self.calendar
+            .as_ref()
+            .is_none_or(|calendar| calendar.is_open(candle.open_time()))
+    }
+
+    /// Returns true if the candle is the last one of its session.
+    fn is_session_close(&self, candle: &Candle) -> bool {
+        let Some((_, close)) = self
+            .calendar
+            .as_ref()
+            .and_then(|calendar| calendar.session(candle.open_time()))
+        else {
+            return false;
+        };
+        candle.close_time() >= close || self.clock.get(self.index + 1).is_some_and(|next| *next >= close)
+    }
+
+    /// Returns an iterator over the data, the candles after the current one included.
+    ///
+    /// A strategy should read the candles through its [`StrategyContext`], which cannot look ahead.
+    /// The iterator is empty in a multi-asset backtest, see [`Backtest::candle_of`] and [`StrategyContext::candles_of`].
     pub fn candles(&self) -> std::slice::Iter<'_, Candle> {
         self.data.iter()
     }
@@ -155,11 +504,21 @@ impl Backtest {
         self.orders.iter()
     }
 
+    /// Returns an iterator over the parent orders, including the completed and cancelled ones.
+    pub fn parent_orders(&self) -> std::slice::Iter<'_, ParentOrder> {
+        self.parent_orders.iter()
+    }
+
     /// Returns an iterator over the open positions.
     pub fn positions(&self) -> Iter<'_, Position> {
         self.positions.iter()
     }
 
+    /// Returns the realized P&L of the closed positions in the account currency, oldest first.
+    pub fn trade_pnls(&self) -> &[f64] {
+        &self.trade_pnls
+    }
+
     /// Returns an iterator over the recorded events.
     #[cfg(feature = "metrics")]
     pub fn events(&self) -> std::slice::Iter<'_, Event> {
@@ -168,13 +527,24 @@ impl Backtest {
 
     /// Places a new order.
     ///
+    /// The order is rounded or rejected against the instrument specification, see [`Backtest::with_instrument`].
+    /// In a multi-asset backtest, the order must have the symbol of one of the series.
+    /// The order is then checked against the pre-trade limits, see [`Backtest::with_risk_manager`].
+    ///
     /// ### Arguments
     /// * `order` - The order to place.
     ///
     /// ### Returns
     /// Ok if successful, or an error.
     pub fn place_order(&mut self, order: Order) -> Result<()> {
-        self.wallet.lock(order.cost()?)?;
+        self.check_window()?;
+        self.check_symbol(&order)?;
+        let mut order = self.instrument_of(order.symbol()).normalize(order)?;
+        order.set_fx_rate(self.fx_rate_of(order.symbol())?);
+        self.check_risk(&order)?;
+        self.posting(Reference::Order(order.id()))
+            .lock(order.account_cost_amount()?)?;
+        self.record_trade()?;
         self.orders.push_back(order.clone());
         #[cfg(feature = "metrics")]
         {
@@ -184,6 +554,126 @@ impl Backtest {
         Ok(())
     }
 
+    /// Places a parent order, sliced into child orders by its execution algorithm.
+    ///
+    /// The first child is placed on the current candle. The funds are locked by each child order.
+    /// A child order rejected when it is placed (e.g., insufficient funds, a pre-trade limit) cancels the parent order.
+    ///
+    /// ### Arguments
+    /// * `parent` - The parent order to place.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn place_parent_order(&mut self, parent: ParentOrder) -> Result<()> {
+        self.check_window()?;
+        #[cfg(feature = "metrics")]
+        self.events.push(Event::AddParentOrder(parent.clone()));
+        self.parent_orders.push(parent);
+        Ok(())
+    }
+
+    /// Cancels an active parent order and its pending child order.
+    ///
+    /// The filled quantity stays open as positions.
+    ///
+    /// ### Arguments
+    /// * `parent` - Reference to the parent order to cancel.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn cancel_parent_order(&mut self, parent: &ParentOrder) -> Result<()> {
+        let parent = self
+            .parent_orders
+            .iter_mut()
+            .find(|p| *p == parent && p.is_active())
+            .ok_or(Error::ParentOrderNotFound)?;
+        let child = parent.cancel();
+        #[cfg(feature = "metrics")]
+        let parent = parent.clone();
+        if let Some(child) = child.filter(|child| self.orders.contains(child)) {
+            self.delete_order(&child, true)?;
+        }
+        #[cfg(feature = "metrics")]
+        self.events.push(Event::DelParentOrder(parent));
+        Ok(())
+    }
+
+    /// Places the next child orders of the active parent orders.
+    fn slice_parent_orders(&mut self, candle: &Candle) -> Result<()> {
+        let mut parents = std::mem::take(&mut self.parent_orders);
+        let mut children = Vec::new();
+        for parent in parents.iter_mut() {
+            let symbol = parent.order().symbol();
+            let Some(candle) = self.market_candle(symbol, candle) else {
+                continue;
+            };
+            let history = self.market_history(symbol);
+            match parent.next_child(&candle, &history[..history.len().saturating_sub(1)]) {
+                Ok(child) => children.extend(child),
+                Err(e) => {
+                    self.parent_orders = parents;
+                    return Err(e);
+                }
+            }
+        }
+        self.parent_orders = parents;
+        for child in children {
+            match self.place_order(child.clone()) {
+                Ok(()) => {}
+                // a slice too small for the instrument, or placed during a halt, is skipped
+                Err(Error::MinQuantity(..) | Error::MinNotional(..) | Error::TradingHalted) => {}
+                Err(e) => self.reject_child_order(child, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancels the parent order of a rejected child order (e.g., insufficient funds, a pre-trade limit).
+    ///
+    /// The rejection is recorded as an [`Event::RejectOrder`](crate::metrics::Event) with the `metrics` feature,
+    /// and the backtest goes on.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn reject_child_order(&mut self, child: Order, error: Error) {
+        #[cfg(feature = "metrics")]
+        {
+            let recorded = matches!(self.events.last(), Some(Event::RejectOrder { order, .. }) if *order == child);
+            if !recorded {
+                self.events.push(Event::RejectOrder {
+                    order: child.clone(),
+                    reason: error.to_string(),
+                });
+            }
+        }
+        let Some(parent) = self
+            .parent_orders
+            .iter_mut()
+            .find(|parent| parent.is_active() && parent.child() == Some(&child))
+        else {
+            return;
+        };
+        parent.cancel();
+        #[cfg(feature = "metrics")]
+        self.events.push(Event::DelParentOrder(parent.clone()));
+    }
+
+    /// Updates the parent orders after the execution of their child orders.
+    fn update_parent_orders(&mut self) -> Result<()> {
+        let mut parents = std::mem::take(&mut self.parent_orders);
+        let mut result = Ok(());
+        for parent in parents.iter_mut().filter(|parent| parent.is_active()) {
+            let child_pending = parent.child().is_some_and(|child| self.orders.contains(child));
+            if let Some(child) = parent.update(child_pending) {
+                result = result.and(self.delete_order(&child, true));
+            }
+            #[cfg(feature = "metrics")]
+            if !parent.is_active() {
+                self.events.push(Event::DelParentOrder(parent.clone()));
+            }
+        }
+        self.parent_orders = parents;
+        result
+    }
+
     /// Deletes a pending order.
     ///
     /// ### Arguments
@@ -200,7 +690,9 @@ impl Backtest {
                 .ok_or(Error::OrderNotFound)?;
             self.orders.remove(order_idx).ok_or(Error::RemoveOrder)?;
         }
-        self.wallet.unlock(order.cost()?)?;
+        self.posting(Reference::Order(order.id()))
+            .unlock(order.account_cost_amount()?)?;
+        self.push_notification(Notification::Cancelled(order.clone()));
         #[cfg(feature = "metrics")]
         {
             self.events.push(Event::from(&self.wallet));
@@ -211,12 +703,21 @@ impl Backtest {
 
     /// Opens a new position.
     fn open_position(&mut self, position: Position) -> Result<()> {
-        self.wallet.sub(position.cost()?)?;
-        if let Some((market_fee, limit_fee)) = self.market_fees {
+        self.add_position(position.clone())?;
+        self.push_notification(Notification::Filled(position));
+        Ok(())
+    }
+
+    /// Pays the cost and the fees of a position, and adds it to the open positions.
+    fn add_position(&mut self, position: Position) -> Result<()> {
+        let market_fees = self.market_fees;
+        let wallet = self.posting(Reference::Position(position.id()));
+        wallet.sub(position.account_cost_amount()?)?;
+        if let Some((market_fee, limit_fee)) = market_fees {
             if position.is_market_type() {
-                self.wallet.sub_fees(position.cost()? * market_fee)?;
+                wallet.sub_fees(position.account_cost_amount()? * market_fee.into_amount()?)?;
             } else {
-                self.wallet.sub_fees(position.cost()? * limit_fee)?;
+                wallet.sub_fees(position.account_cost_amount()? * limit_fee.into_amount()?)?;
             };
         }
         self.positions.push_back(position.clone());
@@ -236,9 +737,22 @@ impl Backtest {
     /// * `force_remove` - If true, removes the position without checking conditions.
     ///
     /// ### Returns
-    /// The profit/loss from closing the position, or an error.
+    /// The profit/loss from closing the position in the account currency, or an error.
     pub fn close_position(&mut self, position: &Position, exit_price: f64, force_remove: bool) -> Result<f64> {
-        if exit_price <= 0.0 || !exit_price.is_finite() {
+        self.close_position_with(position, exit_price, force_remove, ExitReason::Manual)
+    }
+
+    /// Closes an existing position, recording the reason of the exit.
+    fn close_position_with(
+        &mut self,
+        position: &Position,
+        exit_price: f64,
+        force_remove: bool,
+        reason: ExitReason,
+    ) -> Result<f64> {
+        // an option expiring out of the money settles at zero
+        let worthless = exit_price == 0.0 && reason == ExitReason::Expiry;
+        if (exit_price <= 0.0 && !worthless) || !exit_price.is_finite() {
             return Err(Error::ExitPrice(exit_price));
         }
         if force_remove {
@@ -249,26 +763,35 @@ impl Backtest {
                 .ok_or(Error::PositionNotFound)?;
             self.positions.remove(pos_idx).ok_or(Error::RemovePosition)?;
         }
-        // Calculate profit/loss and update wallet
-        let pnl = position.estimate_pnl(exit_price)?;
-        let total_amount = pnl + position.cost()?;
-        self.wallet.add(total_amount)?;
-        self.wallet.sub_pnl(total_amount);
-        if let Some((market_fee, limit_fee)) = self.market_fees {
+        // Calculate profit/loss in the account currency and update wallet
+        let fx_rate = self.fx_rate_of(position.symbol())?;
+        let total_amount = position.settlement_amount(exit_price, fx_rate)?;
+        let pnl = total_amount - position.account_cost_amount()?;
+        let exit_cost = position.cost_amount()? * fx_rate.into_amount()?;
+        let market_fees = self.market_fees;
+        let wallet = self.posting(Reference::Position(position.id()));
+        wallet.add(position.account_cost_amount()?, EntryReason::FillCost)?;
+        wallet.add(pnl, EntryReason::RealizedPnl)?;
+        wallet.sub_pnl(total_amount);
+        if let Some((market_fee, limit_fee)) = market_fees {
             if position.is_market_type() {
-                self.wallet.sub_fees(position.cost()? * market_fee)?;
+                wallet.sub_fees(exit_cost * market_fee.into_amount()?)?;
             } else {
-                self.wallet.sub_fees(position.cost()? * limit_fee)?;
+                wallet.sub_fees(exit_cost * limit_fee.into_amount()?)?;
             };
         }
+        self.push_notification(Notification::Closed(position.clone(), reason.clone(), to_f64(pnl)));
         #[cfg(feature = "metrics")]
         {
             let mut position = position.clone();
             position.set_exit_price(exit_price)?;
+            position.set_exit_fx_rate(fx_rate);
+            position.set_exit_reason(reason);
             self.events.push(Event::from(&self.wallet));
             self.events.push(Event::DelPosition(position));
         }
-        Ok(pnl)
+        self.trade_pnls.push(to_f64(pnl));
+        Ok(to_f64(pnl))
     }
 
     /// Closes all open positions at the given exit price.
@@ -279,19 +802,41 @@ impl Backtest {
     /// ### Returns
     /// Ok if successful, or an error.
     pub fn close_all_positions(&mut self, exit_price: f64) -> Result<()> {
+        self.close_all_positions_with(exit_price, ExitReason::Manual)
+    }
+
+    /// Closes all open positions at the given exit price, recording the reason of the exit.
+    fn close_all_positions_with(&mut self, exit_price: f64, reason: ExitReason) -> Result<()> {
         while let Some(position) = self.positions.pop_front() {
-            self.close_position(&position, exit_price, false)?;
+            self.close_position_with(&position, exit_price, false, reason.clone())?;
         }
         Ok(())
     }
 
     /// Executes pending orders based on current candle data.
     fn execute_orders(&mut self, candle: &Candle) -> Result<()> {
+        if !self.in_session(candle) {
+            return Ok(());
+        }
+
+        self.slice_parent_orders(candle)?;
         let mut orders = VecDeque::with_capacity(self.orders.len());
         while let Some(order) = self.orders.pop_front() {
+            //? the symbol has no candle at this step, the order waits
+            let Some(candle) = self.market_candle(order.symbol(), candle) else {
+                orders.push_back(order);
+                continue;
+            };
             let price = order.entry_price()?;
             if price >= candle.low() && price <= candle.high() {
-                self.open_position(Position::from(order))?;
+                if let Some(parent_id) = order.parent_id()
+                    && let Some(parent) = self.parent_orders.iter_mut().find(|p| p.id() == parent_id)
+                {
+                    parent.fill(price, order.quantity);
+                }
+                let mut position = Position::from(order);
+                position.set_entry(self.index, candle.open_time());
+                self.open_position(position)?;
             } else {
                 //? if order is market type and does not between `high` and `low`, delete
                 if order.is_market_type() {
@@ -302,96 +847,113 @@ impl Backtest {
             }
         }
         self.orders.append(&mut orders);
+        self.update_parent_orders()
+    }
+
+    /// Moves the stop-loss of a position according to its stop adjustments.
+    ///
+    /// The adjustments are checked on the candle close, so the new stop is only
+    /// used from the next candle.
+    fn adjust_stop(&mut self, position: &mut Position, candle: &Candle) -> Result<()> {
+        if let Some(new_stop) = position.adjusted_stop(candle.close())? {
+            #[cfg(feature = "metrics")]
+            let old_stop = position.stop_loss();
+            position.set_stoploss(new_stop);
+            #[cfg(feature = "metrics")]
+            if let Some(stop) = position.stop_loss().filter(|stop| Some(*stop) != old_stop) {
+                self.events.push(Event::StopUpdate {
+                    position: position.clone(),
+                    from: old_stop,
+                    to: stop,
+                });
+            }
+        }
         Ok(())
     }
 
-    /// Executes position management (take-profit, stop-loss, trailing stop).
+    /// Executes position management (take-profit, stop-loss, trailing stop, stop adjustments, time exits).
     fn execute_positions(&mut self, candle: &Candle) -> Result<()> {
+        if !self.in_session(candle) {
+            return self.mark_to_market(candle);
+        }
+
         let mut positions = VecDeque::with_capacity(self.positions.len());
 
         while let Some(mut position) = self.positions.pop_front() {
-            let should_close = match position.exit_rule() {
-                Some(OrderType::TakeProfitAndStopLoss(take_profit, stop_loss)) => {
-                    if *take_profit < 0.0 || *stop_loss < 0.0 {
-                        return Err(Error::NegTakeProfitAndStopLoss);
-                    }
+            //? the symbol has no candle at this step, the position waits
+            let Some(candle) = self.market_candle(position.symbol(), candle) else {
+                positions.push_back(position);
+                continue;
+            };
 
-                    match position.side {
-                        PositionSide::Long => {
-                            if *take_profit > 0.0 && take_profit <= &candle.high() {
-                                Some(*take_profit)
-                            } else if *stop_loss > 0.0 && stop_loss >= &candle.low() {
-                                Some(*stop_loss)
-                            } else {
-                                None
-                            }
-                        }
-                        PositionSide::Short => {
-                            if *take_profit > 0.0 && take_profit >= &candle.low() {
-                                Some(*take_profit)
-                            } else if *stop_loss > 0.0 && stop_loss <= &candle.high() {
-                                Some(*stop_loss)
-                            } else {
-                                None
-                            }
-                        }
-                    }
-                }
-                Some(OrderType::TrailingStop(price, percent)) => {
-                    if *price <= 0.0 || *percent <= 0.0 {
-                        return Err(Error::NegZeroTrailingStop);
-                    }
+            if position.is_pending_exit() {
+                self.close_position_with(&position, candle.open(), false, ExitReason::Time)?;
+                continue;
+            }
 
-                    match position.side {
-                        PositionSide::Long => {
-                            let execute_price = price.subpercent(*percent);
-                            if execute_price >= candle.low() {
-                                Some(execute_price)
-                            } else {
-                                if &candle.high() > price {
-                                    position.set_trailingstop(candle.high());
-                                }
-                                None
-                            }
-                        }
-                        PositionSide::Short => {
-                            let execute_price = price.addpercent(*percent);
-                            if execute_price <= candle.high() {
-                                Some(execute_price)
-                            } else {
-                                if &candle.low() < price {
-                                    position.set_trailingstop(candle.low());
-                                }
-                                None
-                            }
-                        }
-                    }
-                }
-                None => None,
-                _ => {
-                    return Err(Error::MismatchedOrderType);
-                }
+            let should_close = {
+                let candles = self.market_history(position.symbol());
+                let index = candles.len().saturating_sub(1);
+                let context = ExitContext::new(index, candles, self.execution, &self.wallet);
+                position.check_exit(&candle, &context)?
             };
 
+            let entry_index = position.entry_index().unwrap_or(self.index);
+            let candles = self.candles_since(position.symbol(), entry_index);
             match should_close {
-                Some(exit_price) => {
-                    self.close_position(&position, exit_price, false)?;
+                Some((exit_price, reason)) => {
+                    self.close_position_with(&position, exit_price, false, reason)?;
+                }
+                None if position.is_time_exit_due(candles, candle.close_time()) => match self.execution {
+                    Execution::OnClose => {
+                        self.close_position_with(&position, candle.close(), false, ExitReason::Time)?;
+                    }
+                    Execution::NextOpen => {
+                        position.set_pending_exit();
+                        positions.push_back(position);
+                    }
+                },
+                None => {
+                    self.adjust_stop(&mut position, &candle)?;
+                    positions.push_back(position);
                 }
-                None => positions.push_back(position),
             }
         }
 
-        let mut total_unrealized_pnl = 0.0;
-        for position in &positions {
+        self.positions.append(&mut positions);
+        self.roll_contracts(candle)?;
+        self.settle_options(candle)?;
+        if self.flatten_at_close && self.is_session_close(candle) {
+            while let Some(position) = self.positions.pop_front() {
+                let exit_price = self.mark_price(position.symbol(), candle);
+                self.close_position_with(&position, exit_price, false, ExitReason::SessionClose)?;
+            }
+        }
+        self.mark_to_market(candle)
+    }
+
+    /// Values the open positions at the candle close, checks the circuit breaker and records the equity.
+    fn mark_to_market(&mut self, candle: &Candle) -> Result<()> {
+        self.update_unrealized_pnl(candle)?;
+        self.check_circuit_breaker(candle)?;
+        self.record_equity(candle)
+    }
+
+    /// Updates the unrealized P&L of the open positions at the candle close.
+    ///
+    /// In a multi-asset backtest, each position is valued at the last known close of its symbol.
+    /// The P&L is converted into the account currency at the rate of the candle.
+    fn update_unrealized_pnl(&mut self, candle: &Candle) -> Result<()> {
+        let mut total_unrealized_pnl = ZERO;
+        for position in &self.positions {
             // calculate unrealized P&L for this position
-            let current_price = candle.close();
-            let pnl = position.estimate_pnl(current_price)?;
+            let current_price = self.mark_price(position.symbol(), candle);
+            let fx_rate = self.fx_rate_of(position.symbol())?;
+            let pnl = position.account_pnl_amount(current_price, fx_rate)?;
             total_unrealized_pnl += pnl;
         }
 
-        self.positions.append(&mut positions);
-        self.wallet.set_unrealized_pnl(total_unrealized_pnl);
-        //? new event wallet
+        self.posting(Reference::None).set_unrealized_pnl(total_unrealized_pnl)?;
         Ok(())
     }
 
@@ -402,77 +964,31 @@ impl Backtest {
     ///
     /// ### Returns
     /// Ok if successful, or an error.
-    pub fn run<S>(&mut self, mut strategy: S) -> Result<()>
+    pub fn run<S>(&mut self, strategy: S) -> Result<()>
     where
         S: FnMut(&mut Self, &Candle) -> Result<()>,
     {
-        while self.index < self.data.len() {
-            let candle = self.data.get(self.index).ok_or(Error::CandleNotFound)?.clone();
-            strategy(self, &candle)?;
-            self.execute_orders(&candle)?;
-            self.execute_positions(&candle)?;
-            self.index += 1;
-        }
-
-        Ok(())
+        self.run_strategy(&mut FnStrategy(strategy))
     }
 
     /// Runs the backtest with aggregation, executing the provided function for each candle
     /// and its aggregated versions.
     ///
     /// ### Arguments
-    /// * `aggregator` - An aggregator that defines how to group candles (e.g., by timeframe).
+    /// * `aggregator` - An aggregator that defines how to group candles, by count with its factors
+    ///   or by calendar periods with its timeframes (e.g., [`TimeAggregator`]).
     /// * `strategy` - A closure that takes the backtest and a vector of candle references.
-    ///            The vector contains the current candle followed by any aggregated candles.
+    ///   The vector contains the current candle followed by any aggregated candles, or the candles
+    ///   of the last closed period of each timeframe.
     ///
     /// ### Returns
     /// Ok if successful, or an error.
-    pub fn run_with_aggregator<A, S>(&mut self, aggregator: &A, mut strategy: S) -> Result<()>
+    pub fn run_with_aggregator<A, S>(&mut self, aggregator: &A, strategy: S) -> Result<()>
     where
         A: Aggregation,
         S: FnMut(&mut Self, Vec<&Candle>) -> Result<()>,
     {
-        use std::collections::BTreeMap;
-
-        let factors = aggregator.factors();
-        if factors.is_empty() {
-            return Err(Error::InvalidFactor);
-        }
-
-        let mut current_candles = BTreeMap::new();
-        let mut aggregated_candles_map = BTreeMap::new();
-
-        // Initialize the map with empty queues for each factor
-        for &factor in factors {
-            current_candles.insert(factor, VecDeque::with_capacity(factor));
-            aggregated_candles_map.insert(factor, VecDeque::with_capacity(1));
-        }
-
-        while self.index < self.data.len() {
-            let candle = self.data.get(self.index).ok_or(Error::CandleNotFound)?.clone();
-            for (_, deque) in current_candles.iter_mut() {
-                deque.push_back(candle.clone());
-            }
-
-            for (factor, agg) in aggregated_candles_map.iter_mut() {
-                let deque = current_candles.get_mut(factor).ok_or(Error::CandleDataEmpty)?;
-                let zero = deque.make_contiguous();
-                if aggregator.should_aggregate(*factor, zero) {
-                    let candle = aggregator.aggregate(zero)?;
-                    agg.pop_front();
-                    deque.pop_front();
-                    agg.push_back(candle);
-                }
-            }
-
-            let agg_candles = aggregated_candles_map.values().flatten().collect();
-            strategy(self, agg_candles)?;
-            self.execute_orders(&candle)?;
-            self.execute_positions(&candle)?;
-            self.index += 1;
-        }
-
-        Ok(())
+        self.run_strategy_with_aggregator(aggregator, &mut FnAggregatedStrategy(strategy))
     }
 
     /// Resets the backtest to its initial state.
@@ -484,6 +1000,19 @@ impl Backtest {
             self.events = Vec::new();
         }
         self.orders = VecDeque::new();
+        self.parent_orders = Vec::new();
         self.positions = VecDeque::new();
+        self.equity_curve = Vec::new();
+        self.risk_day = None;
+        self.last_trade = None;
+        self.trade_pnls = Vec::new();
+        self.halt = None;
+        self.equity_peak = self.wallet.initial_balance();
+        self.losses_from = 0;
+        self.notifications = None;
+        #[cfg(feature = "metrics")]
+        {
+            self.window_opening = (0, self.wallet.initial_balance());
+        }
     }
 }
@@ -0,0 +1,453 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use super::{Backtest, Candle, ExitReason, Order, OrderSide, OrderType, Position, PositionSide, Reference};
+use crate::errors::{Error, Result};
+
+/// Seconds in a year of 365 days, the time unit of the pricing models.
+const YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/// Type of an option.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionKind {
+    /// The right to buy the underlying at the strike.
+    Call,
+    /// The right to sell the underlying at the strike.
+    Put,
+}
+
+/// Model used to value an option.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PricingModel {
+    /// Black-Scholes, for options on a spot underlying.
+    #[default]
+    BlackScholes,
+    /// Black-76, for options on a futures underlying.
+    Black76,
+}
+
+/// Sensitivities of an option value.
+///
+/// The vega is given for a change of 1% of the volatility, and the theta for one calendar day.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Greeks {
+    /// Change of the value for a change of 1 of the underlying price.
+    pub delta: f64,
+    /// Change of the delta for a change of 1 of the underlying price.
+    pub gamma: f64,
+    /// Change of the value for a change of 1% of the volatility.
+    pub vega: f64,
+    /// Change of the value after one calendar day.
+    pub theta: f64,
+    /// Change of the value for a change of 1% of the interest rate.
+    pub rho: f64,
+}
+
+impl std::ops::Add for Greeks {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            delta: self.delta + rhs.delta,
+            gamma: self.gamma + rhs.gamma,
+            vega: self.vega + rhs.vega,
+            theta: self.theta + rhs.theta,
+            rho: self.rho + rhs.rho,
+        }
+    }
+}
+
+impl std::ops::Mul<f64> for Greeks {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            delta: self.delta * rhs,
+            gamma: self.gamma * rhs,
+            vega: self.vega * rhs,
+            theta: self.theta * rhs,
+            rho: self.rho * rhs,
+        }
+    }
+}
+
+/// A European option on one of the series of the backtest, traded under its own symbol.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::{TimeZone, Utc};
+///
+/// // a call on the underlying `SPY` struck at 600
+/// let call = OptionContract::new(
+///     "SPY-600C",
+///     "SPY",
+///     OptionKind::Call,
+///     600.0,
+///     Utc.with_ymd_and_hms(2025, 3, 21, 21, 0, 0).unwrap(),
+/// )
+/// .with_rate(0.04);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionContract {
+    symbol: String,
+    underlying: String,
+    kind: OptionKind,
+    strike: f64,
+    expiry: DateTime<Utc>,
+    rate: f64,
+    model: PricingModel,
+}
+
+impl OptionContract {
+    /// Creates a new option valued with Black-Scholes and a zero interest rate.
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of the option.
+    /// * `underlying` - The symbol of the underlying series. In a single-asset backtest,
+    ///   any symbol refers to the series of the backtest.
+    /// * `kind` - Call or put.
+    /// * `strike` - The strike price.
+    /// * `expiry` - The expiry date.
+    pub fn new(
+        symbol: impl Into<String>,
+        underlying: impl Into<String>,
+        kind: OptionKind,
+        strike: f64,
+        expiry: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            symbol: symbol.into(),
+            underlying: underlying.into(),
+            kind,
+            strike,
+            expiry,
+            rate: 0.0,
+            model: PricingModel::default(),
+        }
+    }
+
+    /// Sets the annual risk-free interest rate (e.g., 0.04 for 4%).
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets the pricing model.
+    pub fn with_model(mut self, model: PricingModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Returns the symbol of the option.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Returns the symbol of the underlying series.
+    pub fn underlying(&self) -> &str {
+        &self.underlying
+    }
+
+    /// Returns the type of the option.
+    pub fn kind(&self) -> OptionKind {
+        self.kind
+    }
+
+    /// Returns the strike price.
+    pub fn strike(&self) -> f64 {
+        self.strike
+    }
+
+    /// Returns the expiry date.
+    pub fn expiry(&self) -> DateTime<Utc> {
+        self.expiry
+    }
+
+    /// Returns the value of the option at expiry.
+    pub fn intrinsic_value(&self, underlying: f64) -> f64 {
+        match self.kind {
+            OptionKind::Call => (underlying - self.strike).max(0.0),
+            OptionKind::Put => (self.strike - underlying).max(0.0),
+        }
+    }
+
+    /// Returns the time to expiry in years.
+    fn time_to_expiry(&self, time: DateTime<Utc>) -> f64 {
+        ((self.expiry - time).num_seconds() as f64 / YEAR).max(0.0)
+    }
+
+    /// Returns the cost of carry, the discount factor of the underlying and `d1`, `d2`.
+    fn terms(&self, underlying: f64, volatility: f64, t: f64) -> (f64, f64, f64, f64) {
+        let carry = match self.model {
+            PricingModel::BlackScholes => self.rate,
+            PricingModel::Black76 => 0.0,
+        };
+        let sqrt_t = t.sqrt();
+        let d1 =
+            ((underlying / self.strike).ln() + (carry + volatility * volatility / 2.0) * t) / (volatility * sqrt_t);
+        let d2 = d1 - volatility * sqrt_t;
+        (carry, ((carry - self.rate) * t).exp(), d1, d2)
+    }
+
+    /// Returns the model value of the option.
+    ///
+    /// ### Arguments
+    /// * `underlying` - The price of the underlying.
+    /// * `volatility` - The annualized volatility (e.g., 0.2 for 20%).
+    /// * `time` - The valuation time. The intrinsic value is returned at and after expiry.
+    pub fn price(&self, underlying: f64, volatility: f64, time: DateTime<Utc>) -> f64 {
+        let t = self.time_to_expiry(time);
+        if t <= 0.0 || volatility <= 0.0 {
+            let forward = match self.model {
+                PricingModel::BlackScholes => underlying * (self.rate * t).exp(),
+                PricingModel::Black76 => underlying,
+            };
+            return self.intrinsic_value(forward) * (-self.rate * t).exp();
+        }
+        let (_, carry_factor, d1, d2) = self.terms(underlying, volatility, t);
+        let discount = (-self.rate * t).exp();
+        match self.kind {
+            OptionKind::Call => underlying * carry_factor * cdf(d1) - self.strike * discount * cdf(d2),
+            OptionKind::Put => self.strike * discount * cdf(-d2) - underlying * carry_factor * cdf(-d1),
+        }
+    }
+
+    /// Returns the greeks of one option.
+    ///
+    /// ### Arguments
+    /// * `underlying` - The price of the underlying.
+    /// * `volatility` - The annualized volatility (e.g., 0.2 for 20%).
+    /// * `time` - The valuation time. Only the delta of an in-the-money option remains at expiry.
+    pub fn greeks(&self, underlying: f64, volatility: f64, time: DateTime<Utc>) -> Greeks {
+        let t = self.time_to_expiry(time);
+        if t <= 0.0 || volatility <= 0.0 {
+            let delta = match self.kind {
+                OptionKind::Call if underlying > self.strike => 1.0,
+                OptionKind::Put if underlying < self.strike => -1.0,
+                _ => 0.0,
+            };
+            return Greeks {
+                delta,
+                ..Greeks::default()
+            };
+        }
+        let (carry, carry_factor, d1, d2) = self.terms(underlying, volatility, t);
+        let discount = (-self.rate * t).exp();
+        let sqrt_t = t.sqrt();
+        let gamma = carry_factor * pdf(d1) / (underlying * volatility * sqrt_t);
+        let vega = underlying * carry_factor * pdf(d1) * sqrt_t;
+        let decay = -underlying * carry_factor * pdf(d1) * volatility / (2.0 * sqrt_t);
+        let (delta, theta, rho) = match self.kind {
+            OptionKind::Call => (
+                carry_factor * cdf(d1),
+                decay
+                    - (carry - self.rate) * underlying * carry_factor * cdf(d1)
+                    - self.rate * self.strike * discount * cdf(d2),
+                self.strike * t * discount * cdf(d2),
+            ),
+            OptionKind::Put => (
+                carry_factor * (cdf(d1) - 1.0),
+                decay
+                    + (carry - self.rate) * underlying * carry_factor * cdf(-d1)
+                    + self.rate * self.strike * discount * cdf(-d2),
+                -self.strike * t * discount * cdf(-d2),
+            ),
+        };
+        let rho = match self.model {
+            PricingModel::BlackScholes => rho,
+            PricingModel::Black76 => -t * self.price(underlying, volatility, time),
+        };
+        Greeks {
+            delta,
+            gamma,
+            vega: vega / 100.0,
+            theta: theta / 365.0,
+            rho: rho / 100.0,
+        }
+    }
+}
+
+/// Standard normal probability density function.
+fn pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Standard normal cumulative distribution function (Abramowitz and Stegun 26.2.17, error below 7.5e-8).
+fn cdf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.2316419 * x.abs());
+    let poly = t * (0.319381530 + t * (-0.356563782 + t * (1.781477937 + t * (-1.821255978 + t * 1.330274429))));
+    let upper = pdf(x) * poly;
+    if x >= 0.0 { 1.0 - upper } else { upper }
+}
+
+impl Backtest {
+    /// Registers an option, valued each candle with its pricing model.
+    ///
+    /// The options are opened with [`Backtest::open_option`]. Their positions are marked to the model value
+    /// at the close of each candle, and settled at their intrinsic value on the first candle closing at or after expiry.
+    pub fn with_option(mut self, option: OptionContract) -> Self {
+        self.options.insert(option.symbol.clone(), option);
+        self
+    }
+
+    /// Sets the annualized volatility series of an underlying, one value per candle of the backtest
+    /// (e.g., 0.2 for 20%). The last value is used beyond the end of the series.
+    pub fn with_volatility(mut self, underlying: impl Into<String>, volatility: Vec<f64>) -> Self {
+        self.volatilities.insert(underlying.into(), volatility);
+        self
+    }
+
+    /// Returns a registered option.
+    pub fn option(&self, symbol: &str) -> Option<&OptionContract> {
+        self.options.get(symbol)
+    }
+
+    /// Returns the model value of a registered option at the close of the current candle.
+    ///
+    /// ### Returns
+    /// The value of one option, or an error if the option or the volatility of its underlying is missing.
+    pub fn option_value(&self, symbol: &str) -> Result<f64> {
+        let candle = self.current_candle().ok_or(Error::CandleNotFound)?;
+        let (option, underlying, volatility) = self.option_inputs(symbol, &candle)?;
+        Ok(option.price(underlying, volatility, candle.close_time()))
+    }
+
+    /// Opens an option position at its model value, at the close of the current candle.
+    ///
+    /// The contract multiplier of the instrument of the option symbol applies (e.g., 100 shares per contract).
+    /// The trade goes through the trading window and the pre-trade limits of the risk manager,
+    /// like [`Backtest::place_order`].
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of a registered option.
+    /// * `quantity` - The number of contracts.
+    /// * `side` - Buy to open a long option, sell to write the option.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn open_option(&mut self, symbol: &str, quantity: f64, side: OrderSide) -> Result<()> {
+        self.check_window()?;
+        let premium = self.option_value(symbol)?;
+        let candle = self.clock_candle(self.index).ok_or(Error::CandleNotFound)?;
+        if premium <= 0.0 {
+            return Err(Error::OptionPremium(premium));
+        }
+        let order = Order::from((OrderType::Market(premium), quantity, side)).with_symbol(symbol);
+        let mut order = self.instrument_of(Some(symbol)).normalize(order)?;
+        order.set_fx_rate(self.fx_rate_of(Some(symbol))?);
+        self.check_risk(&order)?;
+        self.posting(Reference::Order(order.id()))
+            .lock(order.account_cost_amount()?)?;
+        self.record_trade()?;
+        let mut position = Position::from(order);
+        position.set_entry(self.index, candle.open_time());
+        self.open_position(position)
+    }
+
+    /// Returns the greeks of an option position, scaled by its quantity and multiplier,
+    /// or `None` if the position is not an option.
+    pub fn position_greeks(&self, position: &Position) -> Option<Greeks> {
+        let candle = self.current_candle()?;
+        let (option, underlying, volatility) = self.option_inputs(position.symbol()?, &candle).ok()?;
+        let sign = match position.side {
+            PositionSide::Long => 1.0,
+            PositionSide::Short => -1.0,
+        };
+        let greeks = option.greeks(underlying, volatility, candle.close_time());
+        Some(greeks * (sign * position.quantity * position.multiplier()))
+    }
+
+    /// Returns the sum of the greeks of the open option positions.
+    pub fn greeks(&self) -> Greeks {
+        self.positions
+            .iter()
+            .filter_map(|position| self.position_greeks(position))
+            .fold(Greeks::default(), |total, greeks| total + greeks)
+    }
+
+    /// Returns the option, the underlying price and the volatility used to value an option at a candle.
+    fn option_inputs(&self, symbol: &str, candle: &Candle) -> Result<(&OptionContract, f64, f64)> {
+        let option = self
+            .options
+            .get(symbol)
+            .ok_or_else(|| Error::UnknownSymbol(symbol.to_string()))?;
+        let volatility = self
+            .volatilities
+            .get(&option.underlying)
+            .and_then(|series| series.get(self.index).or(series.last()))
+            .ok_or_else(|| Error::MissingVolatility(option.underlying.clone()))?;
+        let underlying = self.mark_price(Some(&option.underlying), candle);
+        Ok((option, underlying, *volatility))
+    }
+
+    /// Returns true if the symbol is a registered option.
+    pub(crate) fn is_option(&self, symbol: Option<&str>) -> bool {
+        symbol.is_some_and(|symbol| self.options.contains_key(symbol))
+    }
+
+    /// Returns the model value of an option position, or `None` if the symbol is not an option.
+    pub(crate) fn option_mark(&self, symbol: Option<&str>, candle: &Candle) -> Option<f64> {
+        let (option, underlying, volatility) = self.option_inputs(symbol?, candle).ok()?;
+        Some(option.price(underlying, volatility, candle.close_time()))
+    }
+
+    /// Settles the option positions expiring on the candle at their intrinsic value.
+    pub(crate) fn settle_options(&mut self, candle: &Candle) -> Result<()> {
+        if self.options.is_empty() {
+            return Ok(());
+        }
+        let mut positions = VecDeque::with_capacity(self.positions.len());
+        while let Some(position) = self.positions.pop_front() {
+            let expired = position
+                .symbol()
+                .and_then(|symbol| self.options.get(symbol))
+                .filter(|option| candle.close_time() >= option.expiry);
+            match expired {
+                Some(option) => {
+                    let underlying = self.mark_price(Some(&option.underlying), candle);
+                    let value = option.intrinsic_value(underlying);
+                    self.close_position_with(&position, value, false, ExitReason::Expiry)?;
+                }
+                None => positions.push_back(position),
+            }
+        }
+        self.positions.append(&mut positions);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn black_scholes_reference_values() {
+    use chrono::Duration;
+
+    let now = DateTime::from_timestamp_secs(1735689600).unwrap();
+    let expiry = now + Duration::days(365);
+    let call = OptionContract::new("C", "S", OptionKind::Call, 100.0, expiry).with_rate(0.05);
+    let put = OptionContract::new("P", "S", OptionKind::Put, 100.0, expiry).with_rate(0.05);
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-4;
+
+    // S = 100, K = 100, r = 5%, sigma = 20%, T = 1 year
+    assert!(close(call.price(100.0, 0.2, now), 10.4506));
+    assert!(close(put.price(100.0, 0.2, now), 5.5735));
+    let greeks = call.greeks(100.0, 0.2, now);
+    assert!(close(greeks.delta, 0.6368));
+    assert!(close(greeks.gamma, 0.018762));
+    assert!(close(greeks.vega, 0.375240));
+    assert!(close(greeks.theta, -6.414028 / 365.0));
+    assert!(close(greeks.rho, 0.532325));
+    assert!(close(put.greeks(100.0, 0.2, now).delta, -0.3632));
+
+    // Black-76: F = 100, K = 100, r = 5%, sigma = 20%, T = 1 year
+    let call = call.with_model(PricingModel::Black76);
+    assert!(close(call.price(100.0, 0.2, now), 7.5771));
+
+    assert_eq!(put.price(90.0, 0.2, expiry), 10.0);
+    assert_eq!(put.greeks(110.0, 0.2, expiry), Greeks::default());
+}
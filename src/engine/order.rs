@@ -1,4 +1,10 @@
-use crate::{errors::*, utils::random_id};
+use super::{
+    exit::ExitRule,
+    wallet::{Amount, IntoAmount, to_f64},
+};
+use crate::{PercentCalculus, errors::*, utils::random_id};
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
 
 /// Represents the side of an order (buy or sell).
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -15,12 +21,12 @@ pub enum OrderSide {
 ///
 /// This enum is divided into two categories:
 /// 1. **Order types for opening positions** (Market, Limit)
-/// 2. **Exit rules for closing positions** (TakeProfit, StopLoss, TrailingStop)
+/// 2. **Exit rules for closing positions** (TakeProfit, StopLoss, TrailingStop, Custom)
 ///
 /// This separation ensures clarity between order types used to open positions
 /// and rules used to automatically close them.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum OrderType {
     /// Market order to open a position immediately at the current price.
     ///
@@ -53,9 +59,140 @@ pub enum OrderType {
     /// * `0` - The initial stop price
     /// * `1` - The trailing percentage (e.g., 10.0 for 10%)
     TrailingStop(f64, f64),
+
+    /// Custom exit rule for a position (e.g., a close below an EMA, a Chandelier stop).
+    ///
+    /// Custom rules can't be serialized: serializing an order or a position holding one returns an error,
+    /// and the variant is rejected when deserializing. A custom rule is only equal to its clones,
+    /// which share its id.
+    ///
+    /// ### Arguments
+    /// * `0` - The id of the rule, see [`OrderType::custom`].
+    /// * `1` - The exit rule.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(u32, Box<dyn ExitRule>),
+}
+
+impl PartialEq for OrderType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Market(price), Self::Market(other)) | (Self::Limit(price), Self::Limit(other)) => price == other,
+            (Self::TakeProfitAndStopLoss(take_profit, stop_loss), Self::TakeProfitAndStopLoss(other_tp, other_sl)) => {
+                take_profit == other_tp && stop_loss == other_sl
+            }
+            (Self::TrailingStop(price, percent), Self::TrailingStop(other_price, other_percent)) => {
+                price == other_price && percent == other_percent
+            }
+            // the state of a rule can't be compared
+            (Self::Custom(id, _), Self::Custom(other, _)) => id == other,
+            _ => false,
+        }
+    }
+}
+
+/// Condition that triggers a stop adjustment, measured on the unrealized profit of a position.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfitTrigger {
+    /// Profit as a percentage of the entry price.
+    ///
+    /// ### Arguments
+    /// * `0` - The profit percentage (e.g., 2.0 for 2%).
+    Percent(f64),
+
+    /// Profit as a multiple of the initial risk `R`.
+    ///
+    /// `R` is the distance between the entry price and the stop-loss set when the position was opened.
+    /// The trigger is never reached if the position was opened without a stop-loss.
+    ///
+    /// ### Arguments
+    /// * `0` - The multiple of `R` (e.g., 1.5 for 1.5R).
+    RMultiple(f64),
+}
+
+/// Declarative rule that moves the stop-loss of a position as it becomes profitable.
+///
+/// The stop only moves in the favorable direction: up for long positions, down for short positions.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopAdjustment {
+    /// Moves the stop to the entry price plus an offset once the trigger is reached.
+    ///
+    /// ### Arguments
+    /// * `0` - The profit trigger.
+    /// * `1` - The offset percentage from the entry price (0.0 for a strict break-even).
+    BreakEven(ProfitTrigger, f64),
+
+    /// Locks a part of the unrealized gains once the trigger is reached.
+    ///
+    /// The locked part follows the gains as they grow.
+    ///
+    /// ### Arguments
+    /// * `0` - The profit trigger.
+    /// * `1` - The percentage of the gains to lock (e.g., 50.0 for 50%).
+    LockProfit(ProfitTrigger, f64),
+}
+
+/// Time-based exit rule for a position.
+///
+/// The rule is checked at the close of each candle, alongside the price-based exit rules.
+/// The position is then closed at the candle close or at the next candle open,
+/// depending on the [`Execution`](crate::engine::Execution) of the backtest.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeExit {
+    /// Closes the position after a number of candles.
+    ///
+    /// In a multi-asset backtest, the candles are the ones of the position's symbol,
+    /// so the steps of the timeline without candle for the symbol are not counted.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of candles after the fill candle (e.g., 1 to close on the next candle).
+    Candles(usize),
+
+    /// Closes the position once a duration has elapsed since the fill candle's open time.
+    ///
+    /// ### Arguments
+    /// * `0` - The maximum holding period.
+    Duration(Duration),
+
+    /// Closes the position at a wall-clock time (UTC) each day.
+    ///
+    /// The position is closed at the first occurrence of the time after the fill candle's open time.
+    ///
+    /// ### Arguments
+    /// * `0` - The time of day.
+    DailyAt(NaiveTime),
+}
+
+impl TimeExit {
+    /// Returns true if the exit is due on the given candle.
+    ///
+    /// ### Arguments
+    /// * `elapsed` - The number of candles since the fill candle.
+    /// * `entry_time` - The open time of the fill candle.
+    /// * `close_time` - The close time of the current candle.
+    pub fn is_due(&self, elapsed: usize, entry_time: DateTime<Utc>, close_time: DateTime<Utc>) -> bool {
+        match self {
+            Self::Candles(candles) => elapsed >= *candles,
+            Self::Duration(duration) => close_time - entry_time >= *duration,
+            Self::DailyAt(time) => {
+                let mut deadline = entry_time.date_naive().and_time(*time).and_utc();
+                if deadline <= entry_time {
+                    deadline += Duration::days(1);
+                }
+                close_time >= deadline
+            }
+        }
+    }
 }
 
 impl OrderType {
+    /// Creates a custom exit rule with a new id.
+    pub fn custom<R: ExitRule + 'static>(rule: R) -> Self {
+        Self::Custom(random_id(), Box::new(rule))
+    }
+
     /// Returns the price associated with the order type (for Market and Limit orders).
     pub fn inner(&self) -> Result<f64> {
         match self {
@@ -75,7 +212,13 @@ pub struct Order {
     pub quantity: f64,
     /// Represents the buy/sell side of the order.
     pub side: OrderSide,
-    exit_type: Option<OrderType>,
+    exit_types: Vec<OrderType>,
+    stop_adjustments: Vec<StopAdjustment>,
+    time_exit: Option<TimeExit>,
+    parent_id: Option<u32>,
+    multiplier: f64,
+    symbol: Option<String>,
+    fx_rate: f64,
 }
 
 impl PartialEq for Order {
@@ -92,7 +235,13 @@ impl From<O1> for Order {
             entry_type,
             quantity,
             side,
-            exit_type: None,
+            exit_types: Vec::new(),
+            stop_adjustments: Vec::new(),
+            time_exit: None,
+            parent_id: None,
+            multiplier: 1.0,
+            symbol: None,
+            fx_rate: 1.0,
         }
     }
 }
@@ -105,7 +254,13 @@ impl From<O2> for Order {
             entry_type,
             quantity,
             side,
-            exit_type: Some(exit_type),
+            exit_types: vec![exit_type],
+            stop_adjustments: Vec::new(),
+            time_exit: None,
+            parent_id: None,
+            multiplier: 1.0,
+            symbol: None,
+            fx_rate: 1.0,
         }
     }
 }
@@ -116,10 +271,137 @@ impl Order {
         self.entry_type.inner()
     }
 
-    /// Returns the total cost of the order (price * quantity).
-    pub(crate) fn cost(&self) -> Result<f64> {
-        let inner = self.entry_type.inner()?;
-        Ok(inner * self.quantity)
+    /// Returns the total cost of the order (price * quantity * multiplier).
+    pub fn cost(&self) -> Result<f64> {
+        self.cost_amount().map(to_f64)
+    }
+
+    /// Returns the total cost of the order as an accounting amount.
+    pub(crate) fn cost_amount(&self) -> Result<Amount> {
+        let inner = self.entry_type.inner()?.into_amount()?;
+        Ok(inner * self.quantity.into_amount()? * self.multiplier.into_amount()?)
+    }
+
+    /// Returns the contract multiplier of the instrument, set when the order is placed.
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Sets the contract multiplier of the order.
+    pub(crate) fn set_multiplier(&mut self, multiplier: f64) {
+        self.multiplier = multiplier;
+    }
+
+    /// Returns the rate converting the quote currency of the order into the account currency,
+    /// set when the order is placed.
+    pub fn fx_rate(&self) -> f64 {
+        self.fx_rate
+    }
+
+    /// Sets the conversion rate of the order.
+    pub(crate) fn set_fx_rate(&mut self, fx_rate: f64) {
+        self.fx_rate = fx_rate;
+    }
+
+    /// Returns the cost of the order in the account currency, as an accounting amount.
+    pub(crate) fn account_cost_amount(&self) -> Result<Amount> {
+        Ok(self.cost_amount()? * self.fx_rate.into_amount()?)
+    }
+
+    /// Sets the entry price of the order, keeping its entry type.
+    pub(crate) fn set_entry_price(&mut self, price: f64) -> Result<()> {
+        match &mut self.entry_type {
+            OrderType::Market(inner) | OrderType::Limit(inner) => {
+                *inner = price;
+                Ok(())
+            }
+            _ => Err(Error::MismatchedOrderType),
+        }
+    }
+
+    /// Returns the symbol of the order, if any.
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
+    }
+
+    /// Sets the symbol traded by the order, required by a multi-asset backtest.
+    pub fn with_symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Returns the id of the order.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the id of the parent order if it is a child order of an execution algorithm.
+    pub fn parent_id(&self) -> Option<u32> {
+        self.parent_id
+    }
+
+    /// Creates a child order with the same rules, for a slice of a parent order.
+    pub(crate) fn child(&self, parent_id: u32, entry_type: OrderType, quantity: f64) -> Self {
+        Self {
+            id: random_id(),
+            entry_type,
+            quantity,
+            parent_id: Some(parent_id),
+            ..self.clone()
+        }
+    }
+
+    /// Adjusts the quantity and the prices of the order for a stock split, keeping its cost.
+    ///
+    /// ### Arguments
+    /// * `ratio` - The number of new shares for one old share (e.g., 2.0 for a 2-for-1 split).
+    pub(crate) fn split(&mut self, ratio: f64) {
+        self.quantity *= ratio;
+        if let OrderType::Market(price) | OrderType::Limit(price) = &mut self.entry_type {
+            *price /= ratio;
+        }
+        for exit_type in self.exit_types.iter_mut() {
+            match exit_type {
+                OrderType::TakeProfitAndStopLoss(take_profit, stop_loss) => {
+                    *take_profit /= ratio;
+                    *stop_loss /= ratio;
+                }
+                OrderType::TrailingStop(stop, _) => *stop /= ratio,
+                _ => {}
+            }
+        }
+    }
+
+    /// Creates a market order with the same rules on another contract, for the roll of a position.
+    ///
+    /// ### Arguments
+    /// * `symbol` - The symbol of the next contract.
+    /// * `price` - The price of the next contract.
+    /// * `spread` - The price of the next contract minus the price of the rolled one, shifting the price-based exit rules.
+    pub(crate) fn rolled(&self, symbol: &str, price: f64, spread: f64) -> Self {
+        let mut order = Self {
+            id: random_id(),
+            entry_type: OrderType::Market(price),
+            parent_id: None,
+            symbol: Some(symbol.to_string()),
+            ..self.clone()
+        };
+        for exit_type in order.exit_types.iter_mut() {
+            match exit_type {
+                OrderType::TakeProfitAndStopLoss(take_profit, stop_loss) => {
+                    // a price of 0.0 disables the rule
+                    if *take_profit > 0.0 {
+                        *take_profit += spread;
+                    }
+                    if *stop_loss > 0.0 {
+                        *stop_loss += spread;
+                    }
+                }
+                OrderType::TrailingStop(price, _) => *price += spread,
+                _ => {}
+            }
+        }
+        order
     }
 
     /// Returns the entry type of the order.
@@ -127,9 +409,87 @@ impl Order {
         &self.entry_type
     }
 
-    /// Returns the exit rule of the order, if any.
-    pub fn exit_rule(&self) -> &Option<OrderType> {
-        &self.exit_type
+    /// Returns the first exit rule of the order, if any.
+    #[deprecated(note = "an order may have several exit rules, use `exit_rules` instead")]
+    pub fn exit_rule(&self) -> Option<&OrderType> {
+        self.exit_types.first()
+    }
+
+    /// Returns the exit rules of the order, in the order they were added.
+    pub fn exit_rules(&self) -> &[OrderType] {
+        &self.exit_types
+    }
+
+    /// Adds an exit rule to the order.
+    ///
+    /// The exit rules are combined: the first one to trigger on the intrabar path of a candle
+    /// closes the position (see [`Candle::intrabar_path`](crate::engine::Candle::intrabar_path)).
+    pub fn with_exit_rule(mut self, exit_type: OrderType) -> Self {
+        self.add_exit_rule(exit_type);
+        self
+    }
+
+    /// Adds an exit rule after the existing ones.
+    fn add_exit_rule(&mut self, exit_type: OrderType) {
+        self.exit_types.push(exit_type);
+    }
+
+    /// Replaces an exit rule of the order, after it has been checked.
+    pub(crate) fn set_exit_rule(&mut self, index: usize, exit_type: OrderType) {
+        if let Some(rule) = self.exit_types.get_mut(index) {
+            *rule = exit_type;
+        }
+    }
+
+    /// Returns the stop adjustments of the order.
+    pub fn stop_adjustments(&self) -> &[StopAdjustment] {
+        &self.stop_adjustments
+    }
+
+    /// Adds a stop adjustment to the order.
+    ///
+    /// Once the order is executed, the engine checks the adjustments on every candle
+    /// and moves the stop-loss of the position when one of them is triggered.
+    pub fn with_stop_adjustment(mut self, adjustment: StopAdjustment) -> Self {
+        self.stop_adjustments.push(adjustment);
+        self
+    }
+
+    /// Returns the time-based exit rule of the order, if any.
+    pub fn time_exit(&self) -> Option<&TimeExit> {
+        self.time_exit.as_ref()
+    }
+
+    /// Sets a time-based exit rule on the order.
+    ///
+    /// The time exit works alongside the price-based exit rule: the first one to trigger closes the position.
+    pub fn with_time_exit(mut self, time_exit: TimeExit) -> Self {
+        self.time_exit = Some(time_exit);
+        self
+    }
+
+    /// Returns the current stop-loss price of the exit rules, if any.
+    ///
+    /// For a trailing stop, this is the price at which the position would be closed.
+    /// With several stops, this is the tightest one.
+    pub fn stop_loss(&self) -> Option<f64> {
+        self.exit_types
+            .iter()
+            .filter_map(|exit_type| self.stop_of(exit_type))
+            .reduce(|a, b| match self.side {
+                OrderSide::Buy => a.max(b),
+                OrderSide::Sell => a.min(b),
+            })
+    }
+
+    /// Returns the stop-loss price of an exit rule, if any.
+    fn stop_of(&self, exit_type: &OrderType) -> Option<f64> {
+        match (exit_type, &self.side) {
+            (OrderType::TakeProfitAndStopLoss(_, stop_loss), _) if *stop_loss > 0.0 => Some(*stop_loss),
+            (OrderType::TrailingStop(price, percent), OrderSide::Buy) => Some(price.subpercent(*percent)),
+            (OrderType::TrailingStop(price, percent), OrderSide::Sell) => Some(price.addpercent(*percent)),
+            _ => None,
+        }
     }
 
     /// Returns true if it is a market order, and false if it is a limit order.
@@ -139,8 +499,12 @@ impl Order {
 
     /// Updates the trailing stop price for the order.
     pub fn set_trailingstop(&mut self, new_price: f64) {
-        if let Some(OrderType::TrailingStop(current_price, _)) = &mut self.exit_type {
-            match self.side {
+        let side = self.side.clone();
+        for exit_type in self.exit_types.iter_mut() {
+            let OrderType::TrailingStop(current_price, _) = exit_type else {
+                continue;
+            };
+            match side {
                 OrderSide::Buy => {
                     if new_price > *current_price {
                         *current_price = new_price;
@@ -154,10 +518,60 @@ impl Order {
             }
         }
     }
+
+    /// Updates the stop-loss price of the exit rules.
+    ///
+    /// The stops only move in the favorable direction. The trailing stop price is moved so that
+    /// the trailing stop closes the position at `new_stop`. Without any stop, the stop-loss of the
+    /// first take-profit and stop-loss rule is set, or a new rule is added.
+    pub fn set_stoploss(&mut self, new_stop: f64) {
+        if new_stop <= 0.0 {
+            return;
+        }
+        if self.stop_loss().is_none() {
+            let take_profit_and_stop_loss = self
+                .exit_types
+                .iter_mut()
+                .find(|exit_type| matches!(exit_type, OrderType::TakeProfitAndStopLoss(..)));
+            match take_profit_and_stop_loss {
+                Some(OrderType::TakeProfitAndStopLoss(_, stop_loss)) => *stop_loss = new_stop,
+                _ => self.add_exit_rule(OrderType::TakeProfitAndStopLoss(0.0, new_stop)),
+            }
+            return;
+        }
+
+        let side = self.side.clone();
+        let is_better = |stop: f64| match side {
+            OrderSide::Buy => new_stop > stop,
+            OrderSide::Sell => new_stop < stop,
+        };
+        for exit_type in self.exit_types.iter_mut() {
+            match exit_type {
+                OrderType::TakeProfitAndStopLoss(_, stop_loss) if *stop_loss > 0.0 && is_better(*stop_loss) => {
+                    *stop_loss = new_stop;
+                }
+                OrderType::TrailingStop(price, percent) => {
+                    let new_price = match side {
+                        OrderSide::Buy => new_stop / (1.0 - *percent / 100.0),
+                        OrderSide::Sell => new_stop / (1.0 + *percent / 100.0),
+                    };
+                    let current_stop = match side {
+                        OrderSide::Buy => price.subpercent(*percent),
+                        OrderSide::Sell => price.addpercent(*percent),
+                    };
+                    if is_better(current_stop) {
+                        *price = new_price;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn create_simple_order() {
     let entry_type = OrderType::Market(100.0);
     let quantity = 2.0;
@@ -173,6 +587,7 @@ fn create_simple_order() {
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn create_order_with_exit_rule() {
     let entry_type = OrderType::Limit(100.0);
     let exit_type = OrderType::TakeProfitAndStopLoss(120.0, 90.0);
@@ -221,6 +636,7 @@ fn order_cost() {
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn set_trailingstop_buy() {
     let mut order: Order = (
         OrderType::Market(100.0),
@@ -247,6 +663,7 @@ fn set_trailingstop_buy() {
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn set_trailingstop_sell() {
     let mut order: Order = (
         OrderType::Market(100.0),
@@ -273,6 +690,7 @@ fn set_trailingstop_sell() {
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn set_trailingstop_no_exit_rule() {
     let mut order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Buy).into();
     order.set_trailingstop(150.0);
@@ -296,3 +714,135 @@ fn order_type_inner_panics() {
     let take_profit_order = OrderType::TakeProfitAndStopLoss(120.0, 90.0);
     take_profit_order.inner().unwrap();
 }
+
+#[cfg(test)]
+#[test]
+fn stop_loss_of_exit_rules() {
+    let order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Buy).into();
+    assert_eq!(order.stop_loss(), None);
+
+    let order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TakeProfitAndStopLoss(120.0, 0.0),
+        1.0,
+        OrderSide::Buy,
+    )
+        .into();
+    assert_eq!(order.stop_loss(), None);
+
+    let order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TakeProfitAndStopLoss(120.0, 90.0),
+        1.0,
+        OrderSide::Buy,
+    )
+        .into();
+    assert_eq!(order.stop_loss(), Some(90.0));
+
+    let order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TrailingStop(100.0, 10.0),
+        1.0,
+        OrderSide::Sell,
+    )
+        .into();
+    assert_eq!(order.stop_loss(), Some(110.0));
+}
+
+#[cfg(test)]
+#[test]
+fn set_stoploss_buy() {
+    let mut order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TakeProfitAndStopLoss(120.0, 90.0),
+        1.0,
+        OrderSide::Buy,
+    )
+        .into();
+
+    order.set_stoploss(85.0);
+    assert_eq!(order.stop_loss(), Some(90.0));
+
+    order.set_stoploss(100.0);
+    assert!(matches!(
+        order.exit_rules().first(),
+        Some(OrderType::TakeProfitAndStopLoss(120.0, 100.0))
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn set_stoploss_sell_trailing_stop() {
+    let mut order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TrailingStop(100.0, 10.0),
+        1.0,
+        OrderSide::Sell,
+    )
+        .into();
+
+    order.set_stoploss(115.0);
+    assert_eq!(order.stop_loss(), Some(110.0));
+
+    order.set_stoploss(99.0);
+    assert!((order.stop_loss().unwrap() - 99.0).abs() < 1e-9);
+}
+
+#[cfg(test)]
+#[test]
+fn set_stoploss_no_exit_rule() {
+    let mut order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Buy).into();
+    order.set_stoploss(95.0);
+    assert!(matches!(
+        order.exit_rules().first(),
+        Some(OrderType::TakeProfitAndStopLoss(0.0, 95.0))
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn time_exit_candles() {
+    let time = DateTime::from_timestamp_secs(1515151515).unwrap();
+    let time_exit = TimeExit::Candles(2);
+
+    assert!(!time_exit.is_due(0, time, time));
+    assert!(!time_exit.is_due(1, time, time));
+    assert!(time_exit.is_due(2, time, time));
+}
+
+#[cfg(test)]
+#[test]
+fn time_exit_duration() {
+    let open_time = DateTime::from_timestamp_secs(1515151515).unwrap();
+    let time_exit = TimeExit::Duration(Duration::hours(4));
+
+    assert!(!time_exit.is_due(3, open_time, open_time + Duration::hours(3)));
+    assert!(time_exit.is_due(4, open_time, open_time + Duration::hours(4)));
+}
+
+#[cfg(test)]
+#[test]
+fn time_exit_daily_at() {
+    // 2018-01-05 11:25:15 UTC
+    let open_time = DateTime::from_timestamp_secs(1515151515).unwrap();
+    let time_exit = TimeExit::DailyAt(NaiveTime::from_hms_opt(16, 0, 0).unwrap());
+
+    assert!(!time_exit.is_due(1, open_time, open_time + Duration::hours(4)));
+    assert!(time_exit.is_due(5, open_time, open_time + Duration::hours(5)));
+
+    // filled after the wall-clock time, exit on the next day
+    let open_time = open_time + Duration::hours(6);
+    assert!(!time_exit.is_due(1, open_time, open_time + Duration::hours(1)));
+    assert!(time_exit.is_due(24, open_time, open_time + Duration::hours(24)));
+}
+
+#[cfg(test)]
+#[test]
+fn order_with_time_exit() {
+    let order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Buy).into();
+    assert!(order.time_exit().is_none());
+
+    let order = order.with_time_exit(TimeExit::Candles(3));
+    assert_eq!(order.time_exit(), Some(&TimeExit::Candles(3)));
+    assert!(order.exit_rules().is_empty());
+}
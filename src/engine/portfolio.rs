@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+
+use super::{
+    Backtest, Candle, CandleBuilder, Instrument, Order, Strategy, StrategyContext,
+    strategy::{FnPortfolioContextStrategy, FnPortfolioStrategy},
+};
+use crate::errors::{Error, Result};
+
+/// Candles of a symbol aligned on the timeline of a multi-asset backtest.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub(crate) struct Asset {
+    symbol: String,
+    candles: Vec<Candle>,
+    // index of the last known candle at each step of the timeline
+    last: Vec<Option<usize>>,
+}
+
+impl Asset {
+    /// Returns the candle of the step opening at `time`, if the symbol has one.
+    fn candle(&self, index: usize, time: DateTime<Utc>) -> Option<&Candle> {
+        self.last_candle(index).filter(|candle| candle.open_time() == time)
+    }
+
+    /// Returns all the candles of the symbol.
+    pub(crate) fn candles(&self) -> &[Candle] {
+        &self.candles
+    }
+
+    /// Returns the index of the last known candle at the step, or at the last step once the run is over.
+    fn last_index(&self, index: usize) -> Option<usize> {
+        self.last.get(index).or(self.last.last()).copied().flatten()
+    }
+
+    /// Returns the last known candle at the step.
+    fn last_candle(&self, index: usize) -> Option<&Candle> {
+        self.last_index(index).map(|i| &self.candles[i])
+    }
+
+    /// Returns the candles up to the last known one at the step (included).
+    fn history(&self, index: usize) -> &[Candle] {
+        match self.last_index(index) {
+            Some(i) => &self.candles[..=i],
+            None => &[],
+        }
+    }
+}
+
+/// Aligns the series on the union of their open times.
+///
+/// ### Returns
+/// The open times of the steps, and the assets.
+fn align(series: Vec<(String, Vec<Candle>)>) -> Result<(Vec<DateTime<Utc>>, Vec<Asset>)> {
+    let mut symbols = BTreeSet::new();
+    let mut assets = Vec::with_capacity(series.len());
+    for (symbol, mut candles) in series {
+        if !symbols.insert(symbol.clone()) {
+            return Err(Error::DuplicateSymbol(symbol));
+        }
+        candles.sort_by_key(|candle| candle.open_time());
+        assets.push(Asset {
+            symbol,
+            candles,
+            last: Vec::new(),
+        });
+    }
+
+    let timeline = assets
+        .iter()
+        .flat_map(|asset| asset.candles.iter().map(|candle| candle.open_time()))
+        .collect::<BTreeSet<_>>();
+    let mut cursors = vec![0; assets.len()];
+    for time in &timeline {
+        for (asset, cursor) in assets.iter_mut().zip(cursors.iter_mut()) {
+            while asset
+                .candles
+                .get(*cursor)
+                .is_some_and(|candle| candle.open_time() <= *time)
+            {
+                *cursor += 1;
+            }
+            asset.last.push(cursor.checked_sub(1));
+        }
+    }
+
+    Ok((timeline.into_iter().collect(), assets))
+}
+
+impl Backtest {
+    /// Creates a new multi-asset backtest with a shared wallet.
+    ///
+    /// The series are aligned on the union of their open times. At each step, a symbol without
+    /// a candle (e.g., a market holiday or a late listing) has no order executed and no exit rule checked,
+    /// and its positions are valued at the last known close.
+    ///
+    /// Orders must be given a symbol with [`Order::with_symbol`]. The backtest has no single series of candles:
+    /// the candles of each symbol are read with [`Backtest::candle_of`] or [`StrategyContext::candles_of`].
+    ///
+    /// ### Arguments
+    /// * `series` - The candles of each symbol.
+    /// * `initial_balance` - Initial wallet balance.
+    /// * `market_fees` - Market *(market and limit)* fee percentage, see [`Backtest::new`].
+    ///
+    /// ### Returns
+    /// The new backtest instance or an error.
+    pub fn portfolio<S: Into<String>>(
+        series: Vec<(S, Vec<Candle>)>,
+        initial_balance: f64,
+        market_fees: Option<(f64, f64)>,
+    ) -> Result<Self> {
+        let series = series
+            .into_iter()
+            .map(|(symbol, candles)| (symbol.into(), candles))
+            .collect();
+        let (clock, assets) = align(series)?;
+        let mut backtest = Self::with_clock(Vec::new(), clock, initial_balance, market_fees)?;
+        backtest.assets = assets;
+        Ok(backtest)
+    }
+
+    /// Sets the specification of the instrument traded under a symbol.
+    ///
+    /// The symbols without specification use the instrument of [`Backtest::with_instrument`].
+    pub fn with_symbol_instrument(mut self, symbol: impl Into<String>, instrument: Instrument) -> Self {
+        self.instruments.insert(symbol.into(), instrument);
+        self
+    }
+
+    /// Returns true if it is a multi-asset backtest.
+    pub fn is_portfolio(&self) -> bool {
+        !self.assets.is_empty()
+    }
+
+    /// Returns an iterator over the symbols of a multi-asset backtest.
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.assets.iter().map(|asset| asset.symbol.as_str())
+    }
+
+    /// Returns the current candle of a symbol, or `None` if the symbol has no candle at this step.
+    pub fn candle_of(&self, symbol: &str) -> Option<&Candle> {
+        let time = *self.clock.get(self.index)?;
+        self.asset(symbol)?.candle(self.index, time)
+    }
+
+    /// Returns the last known candle of a symbol at the current step.
+    pub fn last_candle_of(&self, symbol: &str) -> Option<&Candle> {
+        self.asset(symbol)?.last_candle(self.index)
+    }
+
+    /// Returns the current candle of every symbol having one at this step.
+    pub fn current_candles(&self) -> BTreeMap<String, Candle> {
+        self.symbols()
+            .filter_map(|symbol| Some((symbol.to_string(), self.candle_of(symbol)?.clone())))
+            .collect()
+    }
+
+    /// Returns the candle of a step of the timeline: the candle of a single-asset backtest or,
+    /// in a multi-asset backtest, a candle without prices spanning the candles opening at the step.
+    pub(crate) fn clock_candle(&self, index: usize) -> Option<Candle> {
+        if !self.is_portfolio() {
+            return self.data.get(index).cloned();
+        }
+        let open_time = *self.clock.get(index)?;
+        let close_time = self
+            .assets
+            .iter()
+            .filter_map(|asset| asset.candle(index, open_time))
+            .map(|candle| candle.close_time())
+            .max()?;
+        CandleBuilder::builder()
+            .open(0.0)
+            .high(0.0)
+            .low(0.0)
+            .close(0.0)
+            .volume(0.0)
+            .open_time(open_time)
+            .close_time(close_time)
+            .build()
+            .ok()
+    }
+
+    /// Runs the multi-asset backtest, executing the provided function for each step of the timeline.
+    ///
+    /// The backtest given to the function holds the future candles, see [`Backtest::run_portfolio_with_context`].
+    ///
+    /// ### Arguments
+    /// * `strategy` - A closure that takes the backtest and the current candle of every symbol
+    ///   having one at this step.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn run_portfolio<S>(&mut self, strategy: S) -> Result<()>
+    where
+        S: FnMut(&mut Self, &BTreeMap<String, Candle>) -> Result<()>,
+    {
+        self.run_portfolio_strategy(&mut FnPortfolioStrategy(strategy))
+    }
+
+    /// Runs the multi-asset backtest, executing the provided function with a look-ahead-safe context
+    /// for each step of the timeline.
+    ///
+    /// The candles of each symbol are read with [`StrategyContext::candles_of`].
+    ///
+    /// ### Arguments
+    /// * `strategy` - A closure that takes the strategy context and the current candle of every symbol
+    ///   having one at this step.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn run_portfolio_with_context<S>(&mut self, strategy: S) -> Result<()>
+    where
+        S: FnMut(&mut StrategyContext, &BTreeMap<String, Candle>) -> Result<()>,
+    {
+        self.run_portfolio_strategy(&mut FnPortfolioContextStrategy(strategy))
+    }
+
+    /// Runs the multi-asset backtest with a strategy, calling [`Strategy::on_portfolio_candles`] for each step
+    /// of the timeline and its other hooks along the lifecycle of the backtest.
+    ///
+    /// ### Arguments
+    /// * `strategy` - The strategy, left in its final state after the run.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn run_portfolio_strategy<S: Strategy>(&mut self, strategy: &mut S) -> Result<()> {
+        self.with_notifications(strategy, |bt, strategy| {
+            while bt.index < bt.window.end {
+                bt.step(strategy, |strategy, ctx, _| {
+                    let candles = ctx.current_candles();
+                    strategy.on_portfolio_candles(ctx, &candles)
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the asset of a symbol.
+    pub(crate) fn asset(&self, symbol: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.symbol == symbol)
+    }
+
+    /// Returns the asset traded by an order in a multi-asset backtest.
+    fn asset_of(&self, symbol: Option<&str>) -> Option<&Asset> {
+        if self.is_portfolio() {
+            symbol.and_then(|symbol| self.asset(symbol))
+        } else {
+            None
+        }
+    }
+
+    /// Checks that the symbol of an order is traded by the backtest.
+    pub(crate) fn check_symbol(&self, order: &Order) -> Result<()> {
+        if !self.is_portfolio() {
+            return Ok(());
+        }
+        let symbol = order.symbol().ok_or(Error::MissingSymbol)?;
+        self.asset(symbol)
+            .map(|_| ())
+            .ok_or_else(|| Error::UnknownSymbol(symbol.to_string()))
+    }
+
+    /// Returns the instrument of a symbol.
+    pub(crate) fn instrument_of(&self, symbol: Option<&str>) -> &Instrument {
+        symbol
+            .and_then(|symbol| self.instruments.get(symbol))
+            .unwrap_or(&self.instrument)
+    }
+
+    /// Returns the current candle of the symbol traded by an order, or the clock candle in a single-asset backtest.
+    pub(crate) fn market_candle(&self, symbol: Option<&str>, candle: &Candle) -> Option<Candle> {
+        match self.asset_of(symbol) {
+            Some(asset) => asset.candle(self.index, candle.open_time()).cloned(),
+            None if self.is_portfolio() => None,
+            None => Some(candle.clone()),
+        }
+    }
+
+    /// Returns the candles of the symbol traded by an order up to the current step (included).
+    pub(crate) fn market_history(&self, symbol: Option<&str>) -> &[Candle] {
+        match self.asset_of(symbol) {
+            Some(asset) => asset.history(self.index),
+            None => &self.data[..(self.index + 1).min(self.data.len())],
+        }
+    }
+
+    /// Returns the candles of a symbol up to a step of the timeline (included).
+    pub(crate) fn history_of(&self, symbol: &str, step: usize) -> &[Candle] {
+        self.asset(symbol).map(|asset| asset.history(step)).unwrap_or_default()
+    }
+
+    /// Returns the number of candles of a symbol since a step of the timeline, or the number of steps
+    /// in a single-asset backtest.
+    pub(crate) fn candles_since(&self, symbol: Option<&str>, step: usize) -> usize {
+        match self.asset_of(symbol) {
+            Some(asset) => asset
+                .history(self.index)
+                .len()
+                .saturating_sub(asset.history(step).len()),
+            None => self.index.saturating_sub(step),
+        }
+    }
+
+    /// Returns the price used to value a position: the last known close of its symbol,
+    /// or the model value of an option.
+    pub(crate) fn mark_price(&self, symbol: Option<&str>, candle: &Candle) -> f64 {
+        if let Some(price) = self.option_mark(symbol, candle) {
+            return price;
+        }
+        self.asset_of(symbol)
+            .and_then(|asset| asset.last_candle(self.index))
+            .map_or(candle.close(), |candle| candle.close())
+    }
+}
+
+#[cfg(test)]
+use super::testing::{get_flat_candle, hour};
+
+#[cfg(test)]
+#[test]
+fn align_series() {
+    let series = vec![
+        (
+            "A".to_string(),
+            vec![get_flat_candle(hour(0), 10.0), get_flat_candle(hour(2), 12.0)],
+        ),
+        (
+            "B".to_string(),
+            vec![get_flat_candle(hour(1), 21.0), get_flat_candle(hour(2), 22.0)],
+        ),
+    ];
+    let (clock, assets) = align(series).unwrap();
+
+    assert_eq!(clock, vec![hour(0), hour(1), hour(2)]);
+    assert!(assets[0].candle(1, clock[1]).is_none());
+    assert_eq!(assets[0].last_candle(1).unwrap().close(), 10.0);
+    assert!(assets[1].last_candle(0).is_none());
+    assert_eq!(assets[1].history(2).len(), 2);
+
+    let series = vec![("A".to_string(), vec![]), ("A".to_string(), vec![])];
+    assert!(matches!(align(series), Err(Error::DuplicateSymbol(_))));
+}
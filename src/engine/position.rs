@@ -1,5 +1,12 @@
-use super::order::{Order, OrderSide};
-use crate::{errors::*, utils::random_id};
+use super::{
+    Candle,
+    exit::{ExitContext, ExitReason, ExitRule},
+    order::{Order, OrderSide, ProfitTrigger, StopAdjustment},
+    wallet::{Amount, IntoAmount, to_f64},
+};
+use crate::{PercentCalculus, errors::*, utils::random_id};
+
+use chrono::{DateTime, Utc};
 
 /// Represents the side of a position (long or short).
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -19,8 +26,15 @@ pub struct Position {
     order: Order,
     /// The side of the position, either long or short.
     pub side: PositionSide,
+    initial_stop: Option<f64>,
+    entry: Option<(usize, DateTime<Utc>)>,
+    pending_exit: bool,
     #[cfg(feature = "metrics")]
     exit_price: Option<f64>,
+    #[cfg(feature = "metrics")]
+    exit_reason: Option<ExitReason>,
+    #[cfg(feature = "metrics")]
+    exit_fx_rate: Option<f64>,
 }
 
 impl PartialEq for Position {
@@ -35,6 +49,13 @@ impl From<Order> for Position {
             id: random_id(),
             #[cfg(feature = "metrics")]
             exit_price: None,
+            #[cfg(feature = "metrics")]
+            exit_reason: None,
+            #[cfg(feature = "metrics")]
+            exit_fx_rate: None,
+            initial_stop: value.stop_loss(),
+            entry: None,
+            pending_exit: false,
             order: value.clone(),
             side: match value.side {
                 OrderSide::Buy => PositionSide::Long,
@@ -58,6 +79,11 @@ impl std::ops::DerefMut for Position {
 }
 
 impl Position {
+    /// Returns the id of the position.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     #[cfg(feature = "metrics")]
     /// Updates the `exit_price`.
     pub(crate) fn set_exit_price(&mut self, exit_price: f64) -> Result<()> {
@@ -69,20 +95,206 @@ impl Position {
     }
 
     #[cfg(feature = "metrics")]
-    /// Returns the estimated profit and loss if it is closed at the `exit_price`.
+    /// Updates the rate converting the exit price into the account currency.
+    pub(crate) fn set_exit_fx_rate(&mut self, fx_rate: f64) {
+        self.exit_fx_rate = Some(fx_rate);
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Updates the `exit_reason`.
+    pub(crate) fn set_exit_reason(&mut self, exit_reason: ExitReason) {
+        self.exit_reason = Some(exit_reason);
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Returns the reason why the position has been closed, if it is closed.
+    pub fn exit_reason(&self) -> Option<&ExitReason> {
+        self.exit_reason.as_ref()
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Returns the realized profit and loss in the account currency, once the position is closed.
     pub(crate) fn pnl(&self) -> Result<f64> {
         let exit_price = self.exit_price.ok_or(Error::ExitPrice(0.0))?;
-        self.estimate_pnl(exit_price)
+        let fx_rate = self.exit_fx_rate.unwrap_or(self.fx_rate());
+        self.account_pnl_amount(exit_price, fx_rate).map(to_f64)
     }
 
     /// Returns the estimated profit and loss if it is closed at the `exit_price`.
+    ///
+    /// The price difference is multiplied by the contract multiplier of the instrument.
     pub fn estimate_pnl(&self, exit_price: f64) -> Result<f64> {
+        self.pnl_amount(exit_price).map(to_f64)
+    }
+
+    /// Returns the estimated profit and loss as an accounting amount.
+    pub(crate) fn pnl_amount(&self, exit_price: f64) -> Result<Amount> {
+        let exit_price = exit_price.into_amount()?;
+        let entry_price = self.entry_price()?.into_amount()?;
         let pnl = match self.side {
-            PositionSide::Long => (exit_price - self.entry_price()?) * self.quantity,
-            PositionSide::Short => (self.entry_price()? - exit_price) * self.quantity,
-        };
+            PositionSide::Long => exit_price - entry_price,
+            PositionSide::Short => entry_price - exit_price,
+        } * self.quantity.into_amount()?
+            * self.multiplier().into_amount()?;
         Ok(pnl)
     }
+
+    /// Returns the amount credited in the account currency when the position is closed at the `exit_price`:
+    /// the cost and the profit and loss, converted at the exit rate.
+    pub(crate) fn settlement_amount(&self, exit_price: f64, fx_rate: f64) -> Result<Amount> {
+        Ok((self.cost_amount()? + self.pnl_amount(exit_price)?) * fx_rate.into_amount()?)
+    }
+
+    /// Returns the profit and loss in the account currency if it is closed at the `exit_price`,
+    /// including the gain or loss on the conversion rate since the entry.
+    pub(crate) fn account_pnl_amount(&self, exit_price: f64, fx_rate: f64) -> Result<Amount> {
+        Ok(self.settlement_amount(exit_price, fx_rate)? - self.account_cost_amount()?)
+    }
+
+    /// Checks the exit rules of the position on the candle.
+    ///
+    /// When several rules are triggered, the first one reached on the intrabar path wins.
+    /// An exit price never reached inside the candle (e.g., after a gap) counts as reached at the open.
+    ///
+    /// ### Returns
+    /// The exit price and the reason if the position should be closed.
+    pub(crate) fn check_exit(&mut self, candle: &Candle, context: &ExitContext) -> Result<Option<(f64, ExitReason)>> {
+        let mut first_exit: Option<(f64, f64, ExitReason)> = None;
+        for index in 0..self.order.exit_rules().len() {
+            // the rule runs on a copy, so that it sees the position with all its exit rules
+            let Some(mut exit_rule) = self.order.exit_rules().get(index).cloned() else {
+                break;
+            };
+            let exit = exit_rule
+                .exit_price(self, candle, context)
+                .map(|exit_price| exit_price.map(|exit_price| (exit_price, ExitReason::of(&exit_rule, exit_price))));
+            self.order.set_exit_rule(index, exit_rule);
+            let Some((exit_price, reason)) = exit? else {
+                continue;
+            };
+            let position = candle.intrabar_position(exit_price).unwrap_or(0.0);
+            if first_exit.as_ref().is_none_or(|(_, first, _)| position < *first) {
+                first_exit = Some((exit_price, position, reason));
+            }
+        }
+        Ok(first_exit.map(|(exit_price, _, reason)| (exit_price, reason)))
+    }
+
+    /// Adjusts the quantity, the entry price and the stops of the position for a stock split.
+    pub(crate) fn split(&mut self, ratio: f64) {
+        self.order.split(ratio);
+        self.initial_stop = self.initial_stop.map(|stop| stop / ratio);
+    }
+
+    /// Continues a rolled position: keeps its entry and shifts its initial stop by the spread between the contracts.
+    pub(crate) fn roll_from(&mut self, position: &Position, spread: f64) {
+        self.entry = position.entry;
+        self.initial_stop = position.initial_stop.map(|stop| stop + spread);
+    }
+
+    /// Records the index and the open time of the candle on which the position was filled.
+    pub(crate) fn set_entry(&mut self, index: usize, open_time: DateTime<Utc>) {
+        self.entry = Some((index, open_time));
+    }
+
+    /// Returns the index of the candle on which the position was filled.
+    pub fn entry_index(&self) -> Option<usize> {
+        self.entry.map(|(index, _)| index)
+    }
+
+    /// Returns the open time of the candle on which the position was filled.
+    pub fn entry_time(&self) -> Option<DateTime<Utc>> {
+        self.entry.map(|(_, open_time)| open_time)
+    }
+
+    /// Returns true if the time exit of the position is due on the given candle.
+    ///
+    /// ### Arguments
+    /// * `candles` - The number of candles of the position's symbol since the fill candle.
+    /// * `close_time` - The close time of the current candle.
+    pub(crate) fn is_time_exit_due(&self, candles: usize, close_time: DateTime<Utc>) -> bool {
+        match (self.time_exit(), self.entry) {
+            (Some(time_exit), Some((_, entry_time))) => time_exit.is_due(candles, entry_time, close_time),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the position waits to be closed at the next candle open.
+    pub(crate) fn is_pending_exit(&self) -> bool {
+        self.pending_exit
+    }
+
+    /// Marks the position to be closed at the next candle open.
+    pub(crate) fn set_pending_exit(&mut self) {
+        self.pending_exit = true;
+    }
+
+    /// Returns the stop-loss price set when the position was opened, if any.
+    pub fn initial_stop(&self) -> Option<f64> {
+        self.initial_stop
+    }
+
+    /// Returns the initial risk `R` per unit, the distance between the entry price and the initial stop-loss.
+    pub fn initial_risk(&self) -> Result<Option<f64>> {
+        let entry_price = self.entry_price()?;
+        Ok(self
+            .initial_stop
+            .map(|stop| (entry_price - stop).abs())
+            .filter(|risk| *risk > 0.0))
+    }
+
+    /// Returns the stop-loss price required by the triggered stop adjustments at the given `price`.
+    ///
+    /// Returns `None` if no adjustment is triggered or if the position is not in profit.
+    pub(crate) fn adjusted_stop(&self, price: f64) -> Result<Option<f64>> {
+        let entry_price = self.entry_price()?;
+        let gain = match self.side {
+            PositionSide::Long => price - entry_price,
+            PositionSide::Short => entry_price - price,
+        };
+        if gain <= 0.0 {
+            return Ok(None);
+        }
+
+        let initial_risk = self.initial_risk()?;
+        let mut new_stop: Option<f64> = None;
+        for adjustment in self.stop_adjustments() {
+            let (trigger, stop) = match (adjustment, &self.side) {
+                (StopAdjustment::BreakEven(trigger, offset), PositionSide::Long) => {
+                    (trigger, entry_price.addpercent(*offset))
+                }
+                (StopAdjustment::BreakEven(trigger, offset), PositionSide::Short) => {
+                    (trigger, entry_price.subpercent(*offset))
+                }
+                (StopAdjustment::LockProfit(trigger, percent), PositionSide::Long) => {
+                    (trigger, entry_price + gain.how_many(*percent))
+                }
+                (StopAdjustment::LockProfit(trigger, percent), PositionSide::Short) => {
+                    (trigger, entry_price - gain.how_many(*percent))
+                }
+            };
+
+            let triggered = match trigger {
+                ProfitTrigger::Percent(percent) => gain / entry_price * 100.0 >= *percent,
+                ProfitTrigger::RMultiple(multiple) => initial_risk.is_some_and(|risk| gain >= multiple * risk),
+            };
+            if !triggered {
+                continue;
+            }
+
+            new_stop = match (new_stop, &self.side) {
+                (Some(current), PositionSide::Long) => Some(current.max(stop)),
+                (Some(current), PositionSide::Short) => Some(current.min(stop)),
+                (None, _) => Some(stop),
+            };
+        }
+
+        // a stop beyond the current price would close the position at a price never traded
+        Ok(new_stop.filter(|stop| match self.side {
+            PositionSide::Long => *stop < price,
+            PositionSide::Short => *stop > price,
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +392,7 @@ fn estimate_pnl_short_position() {
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn position_with_exit_rule() {
     let order: Order = (
         OrderType::Limit(100.0),
@@ -201,6 +414,7 @@ fn position_with_exit_rule() {
 
 #[cfg(test)]
 #[test]
+#[allow(deprecated)]
 fn position_set_trailingstop() {
     let order: Order = (
         OrderType::Market(100.0),
@@ -218,3 +432,56 @@ fn position_set_trailingstop() {
         panic!("Expected TrailingStop order type");
     }
 }
+
+#[cfg(test)]
+#[test]
+fn position_initial_risk() {
+    let order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TakeProfitAndStopLoss(0.0, 95.0),
+        1.0,
+        OrderSide::Buy,
+    )
+        .into();
+    let mut position = Position::from(order);
+    position.set_stoploss(100.0);
+
+    assert_eq!(position.initial_stop(), Some(95.0));
+    assert_eq!(position.initial_risk().unwrap(), Some(5.0));
+    assert_eq!(position.stop_loss(), Some(100.0));
+}
+
+#[cfg(test)]
+#[test]
+fn position_adjusted_stop_long() {
+    let order: Order = (
+        OrderType::Market(100.0),
+        OrderType::TakeProfitAndStopLoss(0.0, 95.0),
+        1.0,
+        OrderSide::Buy,
+    )
+        .into();
+    let order = order
+        .with_stop_adjustment(StopAdjustment::BreakEven(ProfitTrigger::RMultiple(1.0), 1.0))
+        .with_stop_adjustment(StopAdjustment::LockProfit(ProfitTrigger::Percent(10.0), 50.0));
+    let position = Position::from(order);
+
+    assert_eq!(position.adjusted_stop(90.0).unwrap(), None);
+    assert_eq!(position.adjusted_stop(104.0).unwrap(), None);
+    assert_eq!(position.adjusted_stop(105.0).unwrap(), Some(101.0));
+    assert_eq!(position.adjusted_stop(120.0).unwrap(), Some(110.0));
+}
+
+#[cfg(test)]
+#[test]
+fn position_adjusted_stop_short() {
+    let order: Order = (OrderType::Market(100.0), 1.0, OrderSide::Sell).into();
+    let order = order
+        .with_stop_adjustment(StopAdjustment::BreakEven(ProfitTrigger::Percent(2.0), 0.0))
+        .with_stop_adjustment(StopAdjustment::BreakEven(ProfitTrigger::RMultiple(1.0), 0.0));
+    let position = Position::from(order);
+
+    assert_eq!(position.initial_risk().unwrap(), None);
+    assert_eq!(position.adjusted_stop(99.0).unwrap(), None);
+    assert_eq!(position.adjusted_stop(98.0).unwrap(), Some(100.0));
+}
@@ -0,0 +1,217 @@
+use super::{Backtest, ExitReason, Order, OrderSide, OrderType, Position, PositionSide, Schedule, to_f64};
+use crate::errors::{Error, Result};
+
+/// Rules of the rebalancings made by [`Backtest::rebalance_to`].
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// // rebalance on the first candle of each month, the symbols drifting by more than 5% only
+/// let rebalance = Rebalance::new().with_band(5.0).with_schedule(Schedule::Monthly);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rebalance {
+    band: f64,
+    schedule: Option<Schedule>,
+}
+
+impl Rebalance {
+    /// Creates new rules, rebalancing on every call without band.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the rebalance band: a symbol is traded only when its weight drifts from
+    /// the target by more than the band (e.g., 5.0 for 5 percentage points).
+    pub fn with_band(mut self, band: f64) -> Self {
+        self.band = band.max(0.0);
+        self
+    }
+
+    /// Sets the schedule of the rebalancings: calls outside the schedule do nothing.
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Returns the rebalance band in percentage points.
+    pub fn band(&self) -> f64 {
+        self.band
+    }
+
+    /// Returns the schedule of the rebalancings, if any.
+    pub fn schedule(&self) -> Option<&Schedule> {
+        self.schedule.as_ref()
+    }
+}
+
+/// Tolerance on the quantities, avoiding dust positions after a full reduction.
+const EPSILON: f64 = 1e-9;
+
+impl Backtest {
+    /// Sets the rules of the rebalancings, see [`Backtest::rebalance_to`].
+    pub fn with_rebalance(mut self, rebalance: Rebalance) -> Self {
+        self.rebalance = rebalance;
+        self
+    }
+
+    /// Returns the rules of the rebalancings.
+    pub fn rebalance(&self) -> &Rebalance {
+        &self.rebalance
+    }
+
+    /// Moves the long holdings to target portfolio weights at the close of the current candle.
+    ///
+    /// The equity is the balance plus the value of the open positions. Holdings above their
+    /// target are reduced first, oldest positions first, then market buy orders are placed
+    /// for the holdings below their target. The buy quantities leave room for the market fee
+    /// and are rounded down to the lot step of the instrument; the buys below the minimum
+    /// quantity or notional are skipped. Held symbols missing from the weights are sold,
+    /// short positions are left untouched, and symbols without a candle at this step are not traded.
+    ///
+    /// Nothing is traded outside the schedule of the [`Rebalance`] rules, or for the symbols
+    /// drifting less than the band.
+    ///
+    /// ### Arguments
+    /// * `weights` - The target weight of each symbol (e.g., 0.6 for 60% of the equity).
+    ///   In a single-asset backtest, the weights must have one symbol, given to the orders.
+    ///
+    /// ### Returns
+    /// The placed buy orders, or an error.
+    pub fn rebalance_to<S: AsRef<str>>(&mut self, weights: &[(S, f64)]) -> Result<Vec<Order>> {
+        let total = weights.iter().map(|(_, weight)| *weight).sum::<f64>();
+        if weights.iter().any(|(_, weight)| !weight.is_finite() || *weight < 0.0) || total > 1.0 + EPSILON {
+            return Err(Error::InvalidWeights);
+        }
+        // a single-asset backtest holds one symbol, whatever the symbol of its positions
+        if !self.is_portfolio()
+            && weights
+                .iter()
+                .any(|(symbol, _)| symbol.as_ref() != weights[0].0.as_ref())
+        {
+            return Err(Error::InvalidWeights);
+        }
+        if self.is_portfolio()
+            && let Some((symbol, _)) = weights
+                .iter()
+                .find(|(symbol, _)| !self.symbols().any(|s| s == symbol.as_ref()))
+        {
+            return Err(Error::UnknownSymbol(symbol.as_ref().to_string()));
+        }
+
+        let clock = self.clock_candle(self.index).ok_or(Error::CandleNotFound)?;
+        let previous = self.index.checked_sub(1).and_then(|index| self.clock_candle(index));
+        if let Some(schedule) = &self.rebalance.schedule
+            && !schedule.is_due(self.index, &clock, previous.as_ref())
+        {
+            return Ok(Vec::new());
+        }
+
+        let mut equity = self.wallet.balance();
+        for position in &self.positions {
+            let price = self.mark_price(position.symbol(), &clock);
+            let fx_rate = self.fx_rate_of(position.symbol())?;
+            equity += to_f64(position.settlement_amount(price, fx_rate)?);
+        }
+        if equity <= 0.0 {
+            return Ok(Vec::new());
+        }
+
+        // target of each weighted or held symbol
+        let mut targets = weights
+            .iter()
+            .map(|(symbol, weight)| (symbol.as_ref().to_string(), *weight))
+            .collect::<Vec<_>>();
+        for position in &self.positions {
+            if let Some(symbol) = position.symbol()
+                && self.is_holding(position, symbol)
+                && !targets.iter().any(|(s, _)| s == symbol)
+            {
+                targets.push((symbol.to_string(), 0.0));
+            }
+        }
+
+        let mut buys = Vec::new();
+        for (symbol, weight) in targets {
+            let Some(candle) = self.market_candle(Some(&symbol), &clock) else {
+                continue;
+            };
+            let price = candle.close();
+            // value of one unit in the account currency
+            let unit = price * self.instrument_of(Some(&symbol)).multiplier() * self.fx_rate_of(Some(&symbol))?;
+            let quantity = self
+                .positions
+                .iter()
+                .filter(|position| self.is_holding(position, &symbol))
+                .map(|position| position.quantity)
+                .sum::<f64>();
+            let value = quantity * unit;
+            let target = weight * equity;
+            if ((value - target) / equity * 100.0).abs() <= self.rebalance.band {
+                continue;
+            }
+
+            if value > target {
+                let excess = (value - target) / unit;
+                let excess = if target <= 0.0 {
+                    quantity
+                } else {
+                    self.instrument_of(Some(&symbol)).round_quantity(excess)
+                };
+                self.reduce_holding(&symbol, excess, price)?;
+            } else {
+                buys.push((symbol, price, unit, target - value));
+            }
+        }
+
+        let market_fee = self.market_fees.map_or(0.0, |(market_fee, _)| market_fee);
+        let mut orders = Vec::new();
+        for (symbol, price, unit, missing) in buys {
+            let budget = missing.min(self.wallet.free_balance()?) / (1.0 + market_fee);
+            let quantity = self.instrument_of(Some(&symbol)).round_quantity(budget / unit);
+            if quantity <= 0.0 {
+                continue;
+            }
+            let order = Order::from((OrderType::Market(price), quantity, OrderSide::Buy)).with_symbol(symbol);
+            match self.place_order(order) {
+                Ok(()) => orders.extend(self.orders.back().cloned()),
+                // a trade too small for the instrument is skipped
+                Err(Error::MinQuantity(..) | Error::MinNotional(..)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(orders)
+    }
+
+    /// Returns true if the position is a long holding of the symbol.
+    ///
+    /// In a single-asset backtest, every long position is a holding of the series, except the options.
+    fn is_holding(&self, position: &Position, symbol: &str) -> bool {
+        matches!(position.side, PositionSide::Long)
+            && !self.is_option(position.symbol())
+            && (!self.is_portfolio() || position.symbol() == Some(symbol))
+    }
+
+    /// Sells a quantity of the long holdings of a symbol, oldest positions first.
+    fn reduce_holding(&mut self, symbol: &str, mut quantity: f64, price: f64) -> Result<()> {
+        while quantity > EPSILON {
+            let Some(index) = self.positions.iter().position(|p| self.is_holding(p, symbol)) else {
+                break;
+            };
+            if self.positions[index].quantity <= quantity + EPSILON {
+                let position = self.positions.remove(index).ok_or(Error::RemovePosition)?;
+                quantity -= position.quantity;
+                self.close_position_with(&position, price, false, ExitReason::Rebalance)?;
+            } else {
+                let mut part = self.positions[index].clone();
+                part.quantity = quantity;
+                self.positions[index].quantity -= quantity;
+                self.close_position_with(&part, price, false, ExitReason::Rebalance)?;
+                quantity = 0.0;
+            }
+        }
+        Ok(())
+    }
+}
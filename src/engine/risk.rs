@@ -0,0 +1,291 @@
+use chrono::Duration;
+
+use super::{Backtest, Candle, Order, OrderSide, PositionSide, to_f64};
+use crate::errors::{Error, Result};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::Event;
+
+/// Pre-trade limits checked by [`Backtest::place_order`] before the funds of an order are locked.
+///
+/// Every limit is disabled by default. The notional values are in the account currency, the open
+/// positions being valued at the close of the current candle. Each order opens its own position,
+/// so the pending orders count towards the exposure and the number of open positions.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::Duration;
+///
+/// let risk = RiskManager::new()
+///     .with_max_gross_exposure(50_000.0)
+///     .with_max_open_positions(5)
+///     .with_max_order_percent(10.0)
+///     .with_daily_loss_limit(1_000.0)
+///     .with_min_trade_interval(Duration::minutes(15));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskManager {
+    max_position_notional: Option<f64>,
+    max_gross_exposure: Option<f64>,
+    max_net_exposure: Option<f64>,
+    max_open_positions: Option<usize>,
+    max_pending_orders: Option<usize>,
+    max_order_percent: Option<f64>,
+    daily_loss_limit: Option<f64>,
+    min_trade_interval: Option<Duration>,
+}
+
+impl RiskManager {
+    /// Creates a new risk manager without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum absolute net notional of a symbol, its positions and pending orders included.
+    pub fn with_max_position_notional(mut self, notional: f64) -> Self {
+        self.max_position_notional = Some(notional);
+        self
+    }
+
+    /// Sets the maximum gross exposure: the sum of the absolute notionals of the positions and pending orders.
+    pub fn with_max_gross_exposure(mut self, exposure: f64) -> Self {
+        self.max_gross_exposure = Some(exposure);
+        self
+    }
+
+    /// Sets the maximum net exposure: the absolute sum of the long notionals minus the short notionals.
+    pub fn with_max_net_exposure(mut self, exposure: f64) -> Self {
+        self.max_net_exposure = Some(exposure);
+        self
+    }
+
+    /// Sets the maximum number of open positions, the pending orders included.
+    pub fn with_max_open_positions(mut self, count: usize) -> Self {
+        self.max_open_positions = Some(count);
+        self
+    }
+
+    /// Sets the maximum number of pending orders.
+    pub fn with_max_pending_orders(mut self, count: usize) -> Self {
+        self.max_pending_orders = Some(count);
+        self
+    }
+
+    /// Sets the maximum notional of an order as a percentage of the equity (e.g., 10.0 for 10%).
+    pub fn with_max_order_percent(mut self, percent: f64) -> Self {
+        self.max_order_percent = Some(percent);
+        self
+    }
+
+    /// Sets the maximum loss of equity since the start of the UTC day, after which no order is accepted until the next day.
+    ///
+    /// The deposits and withdrawals of the day are not counted as gains or losses.
+    pub fn with_daily_loss_limit(mut self, loss: f64) -> Self {
+        self.daily_loss_limit = Some(loss);
+        self
+    }
+
+    /// Sets the minimum time between two accepted orders, measured on the open times of their candles.
+    pub fn with_min_trade_interval(mut self, interval: Duration) -> Self {
+        self.min_trade_interval = Some(interval);
+        self
+    }
+
+    /// Returns the maximum net notional of a symbol, if any.
+    pub fn max_position_notional(&self) -> Option<f64> {
+        self.max_position_notional
+    }
+
+    /// Returns the maximum gross exposure, if any.
+    pub fn max_gross_exposure(&self) -> Option<f64> {
+        self.max_gross_exposure
+    }
+
+    /// Returns the maximum net exposure, if any.
+    pub fn max_net_exposure(&self) -> Option<f64> {
+        self.max_net_exposure
+    }
+
+    /// Returns the maximum number of open positions, if any.
+    pub fn max_open_positions(&self) -> Option<usize> {
+        self.max_open_positions
+    }
+
+    /// Returns the maximum number of pending orders, if any.
+    pub fn max_pending_orders(&self) -> Option<usize> {
+        self.max_pending_orders
+    }
+
+    /// Returns the maximum notional of an order as a percentage of the equity, if any.
+    pub fn max_order_percent(&self) -> Option<f64> {
+        self.max_order_percent
+    }
+
+    /// Returns the daily loss limit, if any.
+    pub fn daily_loss_limit(&self) -> Option<f64> {
+        self.daily_loss_limit
+    }
+
+    /// Returns the minimum time between two accepted orders, if any.
+    pub fn min_trade_interval(&self) -> Option<Duration> {
+        self.min_trade_interval
+    }
+}
+
+/// Returns the signed notional of an order: positive for a buy, negative for a sell.
+fn signed_notional(order: &Order) -> Result<f64> {
+    let notional = to_f64(order.account_cost_amount()?);
+    Ok(match order.side {
+        OrderSide::Buy => notional,
+        OrderSide::Sell => -notional,
+    })
+}
+
+impl Backtest {
+    /// Sets the pre-trade limits checked when an order is placed.
+    ///
+    /// The limits apply to every order, the child orders of the execution algorithms and the
+    /// buys of the rebalancings included. A rejected order is recorded as an [`Event::RejectOrder`](crate::metrics::Event)
+    /// with the `metrics` feature.
+    pub fn with_risk_manager(mut self, risk: RiskManager) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Returns the pre-trade limits.
+    pub fn risk_manager(&self) -> &RiskManager {
+        &self.risk
+    }
+
+    /// Records the equity at the start of the UTC day of the candle, for the daily loss limit.
+    pub(crate) fn start_risk_day(&mut self, candle: &Candle) -> Result<()> {
+        if self.risk.daily_loss_limit.is_none() {
+            return Ok(());
+        }
+        let day = candle.open_time().date_naive();
+        if self.risk_day.is_some_and(|(start, _)| start == day) {
+            return Ok(());
+        }
+        let equity = match self.index.checked_sub(1).and_then(|index| self.clock_candle(index)) {
+            Some(previous) => self.equity_snapshot(&previous)?.equity(),
+            None => self.wallet.balance(),
+        };
+        self.risk_day = Some((day, equity));
+        Ok(())
+    }
+
+    /// Moves the equity at the start of the day by a deposit (positive) or a withdrawal (negative),
+    /// so that the cashflows are not counted in the daily loss.
+    pub(crate) fn shift_risk_day(&mut self, amount: f64) {
+        if let Some((_, start)) = self.risk_day.as_mut() {
+            *start += amount;
+        }
+    }
+
+    /// Checks a normalized order against the trading halts and the pre-trade limits, recording the rejection.
+    pub(crate) fn check_risk(&mut self, order: &Order) -> Result<()> {
+        let result = self.check_halt().and_then(|_| self.risk_violation(order));
+        #[cfg(feature = "metrics")]
+        if let Err(e) = &result {
+            self.events.push(Event::RejectOrder {
+                order: order.clone(),
+                reason: e.to_string(),
+            });
+        }
+        result
+    }
+
+    /// Returns the first limit violated by the order.
+    fn risk_violation(&self, order: &Order) -> Result<()> {
+        let risk = &self.risk;
+        if risk == &RiskManager::default() {
+            return Ok(());
+        }
+        let time = self.current_time()?;
+
+        if let (Some(interval), Some(last)) = (risk.min_trade_interval, self.last_trade)
+            && time - last < interval
+        {
+            return Err(Error::MinTradeInterval((interval - (time - last)).num_seconds()));
+        }
+        if let Some(max) = risk.max_pending_orders
+            && self.orders.len() + 1 > max
+        {
+            return Err(Error::MaxPendingOrders(max));
+        }
+        if let Some(max) = risk.max_open_positions
+            && self.positions.len() + self.orders.len() + 1 > max
+        {
+            return Err(Error::MaxOpenPositions(max));
+        }
+
+        let candle = self.current_candle().ok_or(Error::CandleNotFound)?;
+        let notional = signed_notional(order)?;
+        if risk.max_order_percent.is_some() || risk.daily_loss_limit.is_some() {
+            let equity = self.equity_snapshot(&candle)?.equity();
+            if let Some(max) = risk.max_order_percent {
+                let percent = notional.abs() / equity * 100.0;
+                if equity <= 0.0 || percent > max {
+                    return Err(Error::MaxOrderSize(percent, max));
+                }
+            }
+            if let (Some(limit), Some((_, start))) = (risk.daily_loss_limit, self.risk_day) {
+                let loss = start - equity;
+                if loss >= limit {
+                    return Err(Error::DailyLossLimit(loss, limit));
+                }
+            }
+        }
+
+        let is_portfolio = self.is_portfolio();
+        let of_symbol = |symbol: Option<&str>| !is_portfolio || symbol == order.symbol();
+        let (mut gross, mut net, mut symbol_net) = (notional.abs(), notional, notional);
+        for position in &self.positions {
+            let price = self.mark_price(position.symbol(), &candle);
+            let value = position.quantity * price * position.multiplier() * self.fx_rate_of(position.symbol())?;
+            let value = match position.side {
+                PositionSide::Long => value,
+                PositionSide::Short => -value,
+            };
+            gross += value.abs();
+            net += value;
+            if of_symbol(position.symbol()) {
+                symbol_net += value;
+            }
+        }
+        for pending in &self.orders {
+            let value = signed_notional(pending)?;
+            gross += value.abs();
+            net += value;
+            if of_symbol(pending.symbol()) {
+                symbol_net += value;
+            }
+        }
+        if let Some(max) = risk.max_position_notional
+            && symbol_net.abs() > max
+        {
+            return Err(Error::MaxPositionNotional(symbol_net.abs(), max));
+        }
+        if let Some(max) = risk.max_gross_exposure
+            && gross > max
+        {
+            return Err(Error::MaxGrossExposure(gross, max));
+        }
+        if let Some(max) = risk.max_net_exposure
+            && net.abs() > max
+        {
+            return Err(Error::MaxNetExposure(net.abs(), max));
+        }
+        Ok(())
+    }
+
+    /// Records the time of an accepted order, for the minimum time between trades.
+    pub(crate) fn record_trade(&mut self) -> Result<()> {
+        if self.risk.min_trade_interval.is_some() {
+            self.last_trade = Some(self.current_time()?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Datelike, Utc, Weekday};
+
+use super::Candle;
+
+/// Schedule of a recurring action of the backtest (e.g., a monthly deposit).
+///
+/// Calendar schedules trigger on the first candle of each period, including the first candle of the data.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Once, on the first candle opening at or after the given time.
+    At(DateTime<Utc>),
+    /// Every `n` candles, starting on the first candle.
+    EveryCandles(usize),
+    /// On the first candle of each day.
+    Daily,
+    /// On the first candle of each week, weeks starting on the given weekday.
+    Weekly(Weekday),
+    /// On the first candle of each month.
+    Monthly,
+}
+
+impl Schedule {
+    /// Returns true if the schedule triggers on the candle.
+    ///
+    /// ### Arguments
+    /// * `index` - The index of the candle.
+    /// * `candle` - The candle.
+    /// * `previous` - The previous candle, if any.
+    pub fn is_due(&self, index: usize, candle: &Candle, previous: Option<&Candle>) -> bool {
+        let time = candle.open_time();
+        let previous_time = previous.map(|c| c.open_time());
+        match self {
+            Self::At(at) => time >= *at && previous_time.is_none_or(|previous| previous < *at),
+            Self::EveryCandles(n) => *n > 0 && index.is_multiple_of(*n),
+            Self::Daily => previous_time.is_none_or(|previous| previous.date_naive() != time.date_naive()),
+            Self::Weekly(start) => previous_time.is_none_or(|previous| {
+                previous.date_naive().week(*start).first_day() != time.date_naive().week(*start).first_day()
+            }),
+            Self::Monthly => {
+                previous_time.is_none_or(|previous| (previous.year(), previous.month()) != (time.year(), time.month()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use super::testing::{get_flat_candle, time};
+
+#[cfg(test)]
+#[test]
+fn schedule_is_due() {
+    let candles = [
+        get_flat_candle(time("2025-01-30T10:00:00Z"), 100.0),
+        get_flat_candle(time("2025-01-31T10:00:00Z"), 100.0),
+        get_flat_candle(time("2025-02-03T10:00:00Z"), 100.0),
+        get_flat_candle(time("2025-02-03T11:00:00Z"), 100.0),
+    ];
+    let due = |schedule: Schedule| {
+        (0..candles.len())
+            .filter(|&i| schedule.is_due(i, &candles[i], i.checked_sub(1).map(|p| &candles[p])))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(due(Schedule::Monthly), vec![0, 2]);
+    assert_eq!(due(Schedule::Weekly(Weekday::Mon)), vec![0, 2]);
+    assert_eq!(due(Schedule::Daily), vec![0, 1, 2]);
+    assert_eq!(due(Schedule::EveryCandles(2)), vec![0, 2]);
+    assert_eq!(due(Schedule::At(candles[1].close_time())), vec![2]);
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+
+use super::timeframe::to_utc;
+
+/// Trait for trading calendars, giving the trading sessions of an exchange.
+///
+/// Implement this trait to plug a custom calendar into the backtest,
+/// or use [`Calendar`] to describe trading hours, holidays and half-days.
+pub trait TradingCalendar: fmt::Debug + Send + Sync {
+    /// Returns the open and close times of the session containing `time`, if any.
+    fn session(&self, time: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)>;
+
+    /// Returns true if the market is open at `time`.
+    fn is_open(&self, time: DateTime<Utc>) -> bool {
+        self.session(time).is_some()
+    }
+
+    /// Returns the number of minutes from `time` to the close of the current session.
+    ///
+    /// Returns `None` if the market is closed at `time`.
+    fn minutes_to_close(&self, time: DateTime<Utc>) -> Option<i64> {
+        self.session(time).map(|(_, close)| (close - time).num_minutes().max(0))
+    }
+}
+
+/// Trading calendar of an exchange.
+///
+/// Trading hours are set per weekday in the exchange timezone. A [`chrono::FixedOffset`] has no
+/// daylight saving time: to follow it, add the `chrono-tz` crate to your dependencies and use its
+/// timezones (e.g., `chrono_tz::America::New_York`). A session closing before it opens
+/// runs overnight and ends on the next day (e.g., futures opening at 18:00 and closing at 17:00).
+/// Holidays and half-days refer to the date on which the session opens.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::{FixedOffset, NaiveDate, NaiveTime};
+///
+/// let open = NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+/// let close = NaiveTime::from_hms_opt(16, 0, 0).unwrap();
+/// let calendar = Calendar::new(FixedOffset::west_opt(5 * 3600).unwrap())
+///     .weekdays(open, close)
+///     .holiday(NaiveDate::from_ymd_opt(2025, 12, 25).unwrap())
+///     .half_day(NaiveDate::from_ymd_opt(2025, 12, 24).unwrap(), NaiveTime::from_hms_opt(13, 0, 0).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Calendar<Tz: TimeZone> {
+    timezone: Tz,
+    hours: [Option<(NaiveTime, NaiveTime)>; 7],
+    holidays: BTreeSet<NaiveDate>,
+    half_days: BTreeMap<NaiveDate, NaiveTime>,
+}
+
+impl<Tz: TimeZone> Calendar<Tz> {
+    /// Creates a new calendar with no trading hours in the given exchange timezone.
+    pub fn new(timezone: Tz) -> Self {
+        Self {
+            timezone,
+            hours: [None; 7],
+            holidays: BTreeSet::new(),
+            half_days: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the trading hours of a weekday.
+    pub fn hours(mut self, weekday: Weekday, open: NaiveTime, close: NaiveTime) -> Self {
+        self.hours[weekday.num_days_from_monday() as usize] = Some((open, close));
+        self
+    }
+
+    /// Sets the same trading hours from Monday to Friday.
+    pub fn weekdays(self, open: NaiveTime, close: NaiveTime) -> Self {
+        [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]
+            .into_iter()
+            .fold(self, |calendar, weekday| calendar.hours(weekday, open, close))
+    }
+
+    /// Adds a holiday, a day without session.
+    pub fn holiday(mut self, date: NaiveDate) -> Self {
+        self.holidays.insert(date);
+        self
+    }
+
+    /// Adds a half-day, a day on which the session closes early.
+    pub fn half_day(mut self, date: NaiveDate, close: NaiveTime) -> Self {
+        self.half_days.insert(date, close);
+        self
+    }
+
+    /// Returns the session opening on the given date, in UTC.
+    ///
+    /// An open or close time skipped by a change of offset moves to the next valid instant.
+    fn session_of(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.holidays.contains(&date) {
+            return None;
+        }
+        let (open, close) = self.hours[date.weekday().num_days_from_monday() as usize]?;
+        let close = self.half_days.get(&date).copied().unwrap_or(close);
+        let close_date = if close > open { date } else { date + Duration::days(1) };
+
+        let open = to_utc(&self.timezone, date.and_time(open));
+        let close = to_utc(&self.timezone, close_date.and_time(close));
+        Some((open, close))
+    }
+}
+
+impl<Tz> TradingCalendar for Calendar<Tz>
+where
+    Tz: TimeZone + fmt::Debug + Send + Sync,
+    Tz::Offset: Send + Sync,
+{
+    fn session(&self, time: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let date = time.with_timezone(&self.timezone).date_naive();
+        // an overnight session may have opened the day before
+        [date, date - Duration::days(1)]
+            .into_iter()
+            .filter_map(|date| self.session_of(date))
+            .find(|(open, close)| *open <= time && time < *close)
+    }
+}
+
+#[cfg(test)]
+fn new_york() -> Calendar<chrono::FixedOffset> {
+    Calendar::new(chrono::FixedOffset::west_opt(5 * 3600).unwrap())
+        .weekdays(
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+        )
+        .holiday(NaiveDate::from_ymd_opt(2025, 12, 25).unwrap())
+        .half_day(
+            NaiveDate::from_ymd_opt(2025, 12, 24).unwrap(),
+            NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+        )
+}
+
+#[cfg(test)]
+fn utc(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+#[cfg(test)]
+#[test]
+fn calendar_trading_hours() {
+    let calendar = new_york();
+
+    // Monday 2025-12-22
+    assert!(!calendar.is_open(utc("2025-12-22T14:29:00Z")));
+    assert!(calendar.is_open(utc("2025-12-22T14:30:00Z")));
+    assert!(calendar.is_open(utc("2025-12-22T20:59:00Z")));
+    assert!(!calendar.is_open(utc("2025-12-22T21:00:00Z")));
+    assert_eq!(calendar.minutes_to_close(utc("2025-12-22T20:00:00Z")), Some(60));
+    assert_eq!(calendar.minutes_to_close(utc("2025-12-22T22:00:00Z")), None);
+
+    // Saturday 2025-12-27
+    assert!(!calendar.is_open(utc("2025-12-27T15:00:00Z")));
+}
+
+#[cfg(test)]
+#[test]
+fn calendar_holidays_and_half_days() {
+    let calendar = new_york();
+
+    // half-day, closes at 13:00
+    assert!(calendar.is_open(utc("2025-12-24T17:59:00Z")));
+    assert!(!calendar.is_open(utc("2025-12-24T18:00:00Z")));
+
+    // holiday
+    assert!(!calendar.is_open(utc("2025-12-25T15:00:00Z")));
+}
+
+#[cfg(test)]
+#[test]
+fn calendar_overnight_session() {
+    let open = NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+    let close = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+    let calendar = Calendar::new(Utc)
+        .hours(Weekday::Sun, open, close)
+        .hours(Weekday::Mon, open, close);
+
+    // Sunday 2025-12-21 18:00 to Monday 17:00
+    assert!(!calendar.is_open(utc("2025-12-21T17:00:00Z")));
+    assert!(calendar.is_open(utc("2025-12-21T18:00:00Z")));
+    assert!(calendar.is_open(utc("2025-12-22T16:59:00Z")));
+    assert!(!calendar.is_open(utc("2025-12-22T17:30:00Z")));
+    assert!(calendar.is_open(utc("2025-12-22T18:30:00Z")));
+    assert_eq!(calendar.minutes_to_close(utc("2025-12-22T16:00:00Z")), Some(60));
+}
+
+/// Eastern time around the change to daylight saving time of 2025-03-09, when 02:00 becomes 03:00.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct SpringForward;
+
+#[cfg(test)]
+impl SpringForward {
+    fn offset(daylight: bool) -> chrono::FixedOffset {
+        chrono::FixedOffset::west_opt(if daylight { 4 * 3600 } else { 5 * 3600 }).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl TimeZone for SpringForward {
+    type Offset = chrono::FixedOffset;
+
+    fn from_offset(_offset: &chrono::FixedOffset) -> Self {
+        Self
+    }
+
+    fn offset_from_local_date(&self, _local: &NaiveDate) -> chrono::MappedLocalTime<chrono::FixedOffset> {
+        unimplemented!()
+    }
+
+    fn offset_from_local_datetime(
+        &self,
+        local: &chrono::NaiveDateTime,
+    ) -> chrono::MappedLocalTime<chrono::FixedOffset> {
+        let change = NaiveDate::from_ymd_opt(2025, 3, 9)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap();
+        if *local < change {
+            chrono::MappedLocalTime::Single(Self::offset(false))
+        } else if *local < change + Duration::hours(1) {
+            chrono::MappedLocalTime::None
+        } else {
+            chrono::MappedLocalTime::Single(Self::offset(true))
+        }
+    }
+
+    fn offset_from_utc_date(&self, _utc: &NaiveDate) -> chrono::FixedOffset {
+        unimplemented!()
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &chrono::NaiveDateTime) -> chrono::FixedOffset {
+        let change = NaiveDate::from_ymd_opt(2025, 3, 9)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        Self::offset(*utc >= change)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn calendar_session_in_offset_gap() {
+    let calendar = Calendar::new(SpringForward).hours(
+        Weekday::Sun,
+        NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+        NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+    );
+
+    // the 02:30 open is skipped: the session opens at 03:30 EDT instead of being dropped
+    assert!(!calendar.is_open(utc("2025-03-09T07:29:00Z")));
+    assert!(calendar.is_open(utc("2025-03-09T07:30:00Z")));
+    assert_eq!(calendar.minutes_to_close(utc("2025-03-09T19:00:00Z")), Some(60));
+}
@@ -0,0 +1,166 @@
+use super::{Backtest, Candle, Order};
+use crate::errors::{Error, Result};
+
+/// Position sizing method used by [`Backtest::position_size`].
+///
+/// The percentages are percentages of the equity, the balance plus the value of the open positions
+/// at the close of the current candle.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// // risk 1% of the equity between the entry and the stop-loss
+/// let sizing = Sizing::FixedRisk(1.0);
+/// // half Kelly, 2% of the equity until the history has a winning and a losing trade
+/// let kelly = Sizing::Kelly { fraction: 0.5, fallback: 2.0 };
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sizing {
+    /// Invests a fixed percentage of the equity.
+    ///
+    /// ### Arguments
+    /// * `0` - The notional of the order as a percentage of the equity (e.g., 10.0 for 10%).
+    FixedFractional(f64),
+
+    /// Risks a fixed percentage of the equity between the entry price and the stop-loss of the order.
+    ///
+    /// ### Arguments
+    /// * `0` - The loss at the stop-loss as a percentage of the equity (e.g., 1.0 for 1%).
+    FixedRisk(f64),
+
+    /// Invests the Kelly fraction of the equity, estimated from the closed trades of the backtest.
+    ///
+    /// The Kelly fraction is `W - (1 - W) / R`, with `W` the win rate and `R` the average win
+    /// divided by the average loss, bounded between 0 and 1.
+    Kelly {
+        /// The part of the Kelly fraction invested (e.g., 0.5 for half Kelly).
+        fraction: f64,
+        /// The percentage of the equity invested until the history has a winning and a losing trade.
+        fallback: f64,
+    },
+
+    /// Sizes the order so that a move of `multiplier` ATR costs a percentage of the equity.
+    Volatility {
+        /// The loss on a move of `multiplier` ATR, as a percentage of the equity.
+        percent: f64,
+        /// The number of candles of the average true range.
+        period: usize,
+        /// The number of ATR of the move.
+        multiplier: f64,
+    },
+
+    /// Shares a risk budget equally between the open positions and the new one.
+    ///
+    /// The new order risks `percent / (open positions + 1)` of the equity between its entry price and its stop-loss.
+    ///
+    /// ### Arguments
+    /// * `0` - The risk budget of all the positions as a percentage of the equity.
+    EqualRisk(f64),
+}
+
+/// Returns the average true range of the last `period` candles, or `None` without enough candles.
+fn average_true_range(candles: &[Candle], period: usize) -> Option<f64> {
+    if period == 0 || candles.len() < period + 1 {
+        return None;
+    }
+    let candles = &candles[candles.len() - period - 1..];
+    let sum = candles
+        .windows(2)
+        .map(|pair| {
+            let (previous, candle) = (&pair[0], &pair[1]);
+            (candle.high() - candle.low())
+                .max((candle.high() - previous.close()).abs())
+                .max((candle.low() - previous.close()).abs())
+        })
+        .sum::<f64>();
+    Some(sum / period as f64)
+}
+
+/// Returns the Kelly fraction of the closed trades, or `None` without a winning and a losing trade.
+fn kelly_fraction(pnls: &[f64]) -> Option<f64> {
+    let (wins, losses): (Vec<f64>, Vec<f64>) = pnls.iter().filter(|pnl| **pnl != 0.0).partition(|pnl| **pnl > 0.0);
+    if wins.is_empty() || losses.is_empty() {
+        return None;
+    }
+    let win_rate = wins.len() as f64 / (wins.len() + losses.len()) as f64;
+    let average_win = wins.iter().sum::<f64>() / wins.len() as f64;
+    let average_loss = losses.iter().map(|loss| loss.abs()).sum::<f64>() / losses.len() as f64;
+    Some((win_rate - (1.0 - win_rate) / (average_win / average_loss)).clamp(0.0, 1.0))
+}
+
+impl Backtest {
+    /// Returns the quantity of an order sized with a sizing method at the current candle.
+    ///
+    /// The order gives the entry price, the symbol and the stop-loss; its quantity is ignored.
+    /// The quantity accounts for the contract multiplier and the quote currency of the instrument,
+    /// and is rounded down to its lot step. It is 0 below the minimum quantity or notional.
+    ///
+    /// ### Arguments
+    /// * `sizing` - The sizing method.
+    /// * `order` - The order to size.
+    ///
+    /// ### Returns
+    /// The quantity, or an error if the order has no stop-loss for a risk-based method,
+    /// or if there are not enough candles for the average true range.
+    pub fn position_size(&self, sizing: &Sizing, order: &Order) -> Result<f64> {
+        let candle = self.current_candle().ok_or(Error::CandleNotFound)?;
+        let equity = self.equity_snapshot(&candle)?.equity();
+        let instrument = self.instrument_of(order.symbol());
+        let price = order.entry_price()?;
+        // value of one unit of price move in the account currency
+        let point = instrument.multiplier() * self.fx_rate_of(order.symbol())?;
+        let stop_distance = || {
+            order
+                .stop_loss()
+                .map(|stop| (price - stop).abs())
+                .filter(|distance| *distance > 0.0)
+                .ok_or(Error::MissingStopLoss)
+        };
+
+        let quantity = match sizing {
+            Sizing::FixedFractional(percent) => equity * percent / 100.0 / (price * point),
+            Sizing::FixedRisk(percent) => equity * percent / 100.0 / (stop_distance()? * point),
+            Sizing::Kelly { fraction, fallback } => {
+                let percent = kelly_fraction(&self.trade_pnls).map_or(*fallback, |kelly| kelly * fraction * 100.0);
+                equity * percent / 100.0 / (price * point)
+            }
+            Sizing::Volatility {
+                percent,
+                period,
+                multiplier,
+            } => {
+                let atr = average_true_range(self.market_history(order.symbol()), *period)
+                    .ok_or(Error::NotEnoughCandles(*period + 1))?;
+                equity * percent / 100.0 / (atr * multiplier * point)
+            }
+            Sizing::EqualRisk(percent) => {
+                let shares = (self.positions.len() + 1) as f64;
+                equity * percent / 100.0 / shares / (stop_distance()? * point)
+            }
+        };
+        if !quantity.is_finite() || quantity <= 0.0 {
+            return Ok(0.0);
+        }
+
+        let quantity = instrument.round_quantity(quantity);
+        if quantity < instrument.min_quantity()
+            || quantity * price * instrument.multiplier() < instrument.min_notional()
+        {
+            return Ok(0.0);
+        }
+        Ok(quantity)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn kelly_fraction_of_trades() {
+    // W = 0.6, R = 2: 0.6 - 0.4 / 2
+    let pnls = [20.0, 20.0, 20.0, -10.0, -10.0];
+    assert!((kelly_fraction(&pnls).unwrap() - 0.4).abs() < 1e-12);
+    assert_eq!(kelly_fraction(&[20.0, 10.0]), None);
+    // a losing history is not traded
+    assert_eq!(kelly_fraction(&[10.0, -20.0, -20.0]), Some(0.0));
+}
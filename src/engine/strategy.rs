@@ -0,0 +1,515 @@
+use std::collections::{BTreeMap, VecDeque, vec_deque::Iter};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use super::{Aggregation, Backtest, Candle, ExitReason, Order, ParentOrder, Periods, Position, Sizing, Wallet};
+use crate::errors::{Error, Result};
+
+/// A trading strategy driven by the lifecycle of a backtest.
+///
+/// Only [`Strategy::on_candle`] is required; the other hooks do nothing by default. The fills, the
+/// cancellations and the closes of a candle are reported once the candle is executed, in the order
+/// they happened, and the hooks can place new orders.
+///
+/// The hooks reach the backtest through a [`StrategyContext`], which only exposes the candles up to
+/// the current one.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+///
+/// #[derive(Default)]
+/// struct BuyAndHold {
+///     trades: usize,
+/// }
+///
+/// impl Strategy for BuyAndHold {
+///     fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> bts_rs::errors::Result<()> {
+///         if ctx.positions().count() == 0 && ctx.orders().count() == 0 {
+///             ctx.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+///         }
+///         Ok(())
+///     }
+///
+///     fn on_order_filled(&mut self, _ctx: &mut StrategyContext, _position: &Position) -> bts_rs::errors::Result<()> {
+///         self.trades += 1;
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Strategy {
+    /// Called once before the first candle.
+    fn on_start(&mut self, _ctx: &mut StrategyContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for each candle, before the pending orders are executed.
+    fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> Result<()>;
+
+    /// Called for each candle of a run with an aggregator, with the aggregated candles.
+    ///
+    /// Defaults to [`Strategy::on_candle`] with the current candle, or returns [`Error::CandleNotFound`]
+    /// in a multi-asset backtest, which has no current candle (see [`StrategyContext::candles_of`]).
+    fn on_aggregated_candles(&mut self, ctx: &mut StrategyContext, _candles: Vec<&Candle>) -> Result<()> {
+        let candle = ctx.candle().cloned().ok_or(Error::CandleNotFound)?;
+        self.on_candle(ctx, &candle)
+    }
+
+    /// Called for each step of a multi-asset backtest, with the current candle of every symbol having one.
+    ///
+    /// Defaults to [`Strategy::on_candle`] with each of these candles, in the order of their symbols.
+    fn on_portfolio_candles(&mut self, ctx: &mut StrategyContext, candles: &BTreeMap<String, Candle>) -> Result<()> {
+        for candle in candles.values() {
+            self.on_candle(ctx, candle)?;
+        }
+        Ok(())
+    }
+
+    /// Called when an order is filled and opens a position.
+    fn on_order_filled(&mut self, _ctx: &mut StrategyContext, _position: &Position) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a pending order is cancelled and its funds unlocked.
+    fn on_order_cancelled(&mut self, _ctx: &mut StrategyContext, _order: &Order) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a position is closed, with the reason of the exit and the P&L in the account currency.
+    fn on_position_closed(
+        &mut self,
+        _ctx: &mut StrategyContext,
+        _position: &Position,
+        _reason: &ExitReason,
+        _pnl: f64,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once after the last candle.
+    fn on_finish(&mut self, _ctx: &mut StrategyContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// State of the backtest given to the strategies.
+///
+/// The context only exposes the candles up to the current one: a strategy cannot look ahead.
+/// Before the first candle, in [`Strategy::on_start`], no candle is visible; after the last one,
+/// in [`Strategy::on_finish`], all of them are.
+pub struct StrategyContext<'a> {
+    bt: &'a mut Backtest,
+    visible: usize,
+}
+
+impl<'a> StrategyContext<'a> {
+    /// Creates a new strategy context showing the first `visible` candles.
+    pub(crate) fn new(bt: &'a mut Backtest, visible: usize) -> Self {
+        Self { bt, visible }
+    }
+
+    /// Returns the index of the current candle.
+    pub fn index(&self) -> usize {
+        self.bt.index
+    }
+
+    /// Returns the current candle, or `None` before the first candle.
+    ///
+    /// Always `None` in a multi-asset backtest, see [`StrategyContext::candles_of`].
+    pub fn candle(&self) -> Option<&Candle> {
+        self.candles().last()
+    }
+
+    /// Returns the open time of the current candle, or of the current step in a multi-asset backtest,
+    /// or `None` before the first candle.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        let step = self.visible.min(self.bt.clock.len()).checked_sub(1)?;
+        self.bt.clock.get(step).copied()
+    }
+
+    /// Returns the candles up to the current one (included).
+    ///
+    /// The candles are empty in a multi-asset backtest, see [`StrategyContext::candles_of`].
+    pub fn candles(&self) -> &[Candle] {
+        &self.bt.data[..self.visible.min(self.bt.data.len())]
+    }
+
+    /// Returns the last `n` candles up to the current one (included), oldest first.
+    ///
+    /// Fewer candles are returned at the start of the backtest, and none in a multi-asset backtest
+    /// (see [`StrategyContext::candles_of`]).
+    pub fn history(&self, n: usize) -> &[Candle] {
+        let candles = self.candles();
+        &candles[candles.len().saturating_sub(n)..]
+    }
+
+    /// Returns the candles of a symbol of a multi-asset backtest up to the current step (included).
+    ///
+    /// The candles are empty for a symbol not traded by the backtest.
+    pub fn candles_of(&self, symbol: &str) -> &[Candle] {
+        match self.visible.min(self.bt.clock.len()).checked_sub(1) {
+            Some(step) => self.bt.history_of(symbol, step),
+            None => &[],
+        }
+    }
+
+    /// Returns the current candle of every symbol having one at this step, see [`Backtest::current_candles`].
+    pub fn current_candles(&self) -> BTreeMap<String, Candle> {
+        self.bt.current_candles()
+    }
+
+    /// Returns the wallet of the backtest.
+    pub fn wallet(&self) -> &Wallet {
+        &self.bt.wallet
+    }
+
+    /// Returns the equity at the close of the current candle, or the balance before the first candle.
+    pub fn equity(&self) -> Result<f64> {
+        match self.candle() {
+            Some(candle) => Ok(self.bt.equity_snapshot(candle)?.equity()),
+            None => Ok(self.bt.wallet.balance()),
+        }
+    }
+
+    /// Returns an iterator over the pending orders.
+    pub fn orders(&self) -> Iter<'_, Order> {
+        self.bt.orders()
+    }
+
+    /// Returns an iterator over the parent orders, including the completed and cancelled ones.
+    pub fn parent_orders(&self) -> std::slice::Iter<'_, ParentOrder> {
+        self.bt.parent_orders()
+    }
+
+    /// Returns an iterator over the open positions.
+    pub fn positions(&self) -> Iter<'_, Position> {
+        self.bt.positions()
+    }
+
+    /// Returns the realized P&L of the closed positions in the account currency, oldest first.
+    pub fn trade_pnls(&self) -> &[f64] {
+        self.bt.trade_pnls()
+    }
+
+    /// Returns true if trading is halted by the circuit breaker.
+    pub fn is_halted(&self) -> bool {
+        self.bt.is_halted()
+    }
+
+    /// Returns true if the current candle is inside the trading window, see [`Backtest::with_window`].
+    pub fn in_window(&self) -> bool {
+        self.bt.in_window()
+    }
+
+    /// Places a new order, see [`Backtest::place_order`].
+    pub fn place_order(&mut self, order: Order) -> Result<()> {
+        self.bt.place_order(order)
+    }
+
+    /// Places a parent order, see [`Backtest::place_parent_order`].
+    pub fn place_parent_order(&mut self, parent: ParentOrder) -> Result<()> {
+        self.bt.place_parent_order(parent)
+    }
+
+    /// Deletes a pending order, see [`Backtest::delete_order`].
+    pub fn delete_order(&mut self, order: &Order, force_remove: bool) -> Result<()> {
+        self.bt.delete_order(order, force_remove)
+    }
+
+    /// Cancels a parent order, see [`Backtest::cancel_parent_order`].
+    pub fn cancel_parent_order(&mut self, parent: &ParentOrder) -> Result<()> {
+        self.bt.cancel_parent_order(parent)
+    }
+
+    /// Closes an open position, see [`Backtest::close_position`].
+    pub fn close_position(&mut self, position: &Position, exit_price: f64, force_remove: bool) -> Result<f64> {
+        self.bt.close_position(position, exit_price, force_remove)
+    }
+
+    /// Closes all open positions, see [`Backtest::close_all_positions`].
+    pub fn close_all_positions(&mut self, exit_price: f64) -> Result<()> {
+        self.bt.close_all_positions(exit_price)
+    }
+
+    /// Returns the quantity of an order sized at the current candle, see [`Backtest::position_size`].
+    pub fn position_size(&self, sizing: &Sizing, order: &Order) -> Result<f64> {
+        self.bt.position_size(sizing, order)
+    }
+}
+
+/// Only shows the visible candles, of each symbol in a multi-asset backtest, the backtest holding the future ones.
+impl fmt::Debug for StrategyContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("StrategyContext");
+        match self.bt.is_portfolio() {
+            true => debug.field(
+                "candles",
+                &self
+                    .bt
+                    .symbols()
+                    .map(|symbol| (symbol, self.candles_of(symbol)))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            false => debug.field("candles", &self.candles()),
+        };
+        debug.finish_non_exhaustive()
+    }
+}
+
+/// An event of the backtest reported to the strategy hooks.
+#[derive(Debug, Clone)]
+pub(crate) enum Notification {
+    Filled(Position),
+    Cancelled(Order),
+    Closed(Position, ExitReason, f64),
+}
+
+/// Runs a closure as a strategy.
+pub(crate) struct FnStrategy<F>(pub(crate) F);
+
+impl<F> Strategy for FnStrategy<F>
+where
+    F: FnMut(&mut Backtest, &Candle) -> Result<()>,
+{
+    fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> Result<()> {
+        (self.0)(ctx.bt, candle)
+    }
+}
+
+/// Runs a closure taking the strategy context as a strategy.
+pub(crate) struct FnContextStrategy<F>(pub(crate) F);
+
+impl<F> Strategy for FnContextStrategy<F>
+where
+    F: FnMut(&mut StrategyContext, &Candle) -> Result<()>,
+{
+    fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> Result<()> {
+        (self.0)(ctx, candle)
+    }
+}
+
+/// Runs a closure taking the aggregated candles as a strategy.
+pub(crate) struct FnAggregatedStrategy<F>(pub(crate) F);
+
+impl<F> Strategy for FnAggregatedStrategy<F>
+where
+    F: FnMut(&mut Backtest, Vec<&Candle>) -> Result<()>,
+{
+    fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> Result<()> {
+        (self.0)(ctx.bt, vec![candle])
+    }
+
+    fn on_aggregated_candles(&mut self, ctx: &mut StrategyContext, candles: Vec<&Candle>) -> Result<()> {
+        (self.0)(ctx.bt, candles)
+    }
+}
+
+/// Runs a closure taking the backtest and the candles of a multi-asset step as a strategy.
+pub(crate) struct FnPortfolioStrategy<F>(pub(crate) F);
+
+impl<F> Strategy for FnPortfolioStrategy<F>
+where
+    F: FnMut(&mut Backtest, &BTreeMap<String, Candle>) -> Result<()>,
+{
+    fn on_candle(&mut self, _ctx: &mut StrategyContext, _candle: &Candle) -> Result<()> {
+        //? only the multi-asset runs use the strategy, which have no single candle
+        Err(Error::CandleNotFound)
+    }
+
+    fn on_portfolio_candles(&mut self, ctx: &mut StrategyContext, candles: &BTreeMap<String, Candle>) -> Result<()> {
+        (self.0)(ctx.bt, candles)
+    }
+}
+
+/// Runs a closure taking the strategy context and the candles of a multi-asset step as a strategy.
+pub(crate) struct FnPortfolioContextStrategy<F>(pub(crate) F);
+
+impl<F> Strategy for FnPortfolioContextStrategy<F>
+where
+    F: FnMut(&mut StrategyContext, &BTreeMap<String, Candle>) -> Result<()>,
+{
+    fn on_candle(&mut self, _ctx: &mut StrategyContext, _candle: &Candle) -> Result<()> {
+        //? only the multi-asset runs use the strategy, which have no single candle
+        Err(Error::CandleNotFound)
+    }
+
+    fn on_portfolio_candles(&mut self, ctx: &mut StrategyContext, candles: &BTreeMap<String, Candle>) -> Result<()> {
+        (self.0)(ctx, candles)
+    }
+}
+
+impl Backtest {
+    /// Runs the backtest, executing the provided function with a look-ahead-safe context for each candle.
+    ///
+    /// ### Arguments
+    /// * `strategy` - A closure that takes the strategy context and the current candle.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn run_with_context<S>(&mut self, strategy: S) -> Result<()>
+    where
+        S: FnMut(&mut StrategyContext, &Candle) -> Result<()>,
+    {
+        self.run_strategy(&mut FnContextStrategy(strategy))
+    }
+
+    /// Runs the backtest with a strategy, calling its hooks along the lifecycle of the backtest.
+    ///
+    /// ### Arguments
+    /// * `strategy` - The strategy, left in its final state after the run.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn run_strategy<S: Strategy>(&mut self, strategy: &mut S) -> Result<()> {
+        self.with_notifications(strategy, |bt, strategy| {
+            while bt.index < bt.window.end {
+                bt.step(strategy, |strategy, ctx, candle| strategy.on_candle(ctx, candle))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs the backtest with a strategy and an aggregator, calling [`Strategy::on_aggregated_candles`]
+    /// for each candle.
+    ///
+    /// ### Arguments
+    /// * `aggregator` - An aggregator that defines how to group candles (e.g., by timeframe).
+    /// * `strategy` - The strategy, left in its final state after the run.
+    ///
+    /// ### Returns
+    /// Ok if successful, or an error.
+    pub fn run_strategy_with_aggregator<A, S>(&mut self, aggregator: &A, strategy: &mut S) -> Result<()>
+    where
+        A: Aggregation,
+        S: Strategy,
+    {
+        let factors = aggregator.factors();
+        let timeframes = aggregator.timeframes();
+        if factors.is_empty() && timeframes.is_empty() {
+            return Err(Error::InvalidFactor);
+        }
+        // the timeframes take precedence over the factors
+        let mut periods = (!timeframes.is_empty()).then(|| Periods::new(timeframes));
+
+        let mut current_candles = BTreeMap::new();
+        let mut aggregated_candles_map = BTreeMap::new();
+
+        // Initialize the map with empty queues for each factor
+        for &factor in factors {
+            current_candles.insert(factor, VecDeque::with_capacity(factor));
+            aggregated_candles_map.insert(factor, VecDeque::with_capacity(1));
+        }
+
+        self.with_notifications(strategy, |bt, strategy| {
+            while bt.index < bt.window.end {
+                bt.step(strategy, |strategy, ctx, candle| {
+                    if let Some(periods) = periods.as_mut() {
+                        periods.push(aggregator, candle)?;
+                    } else {
+                        for (_, deque) in current_candles.iter_mut() {
+                            deque.push_back(candle.clone());
+                        }
+
+                        for (factor, agg) in aggregated_candles_map.iter_mut() {
+                            let deque = current_candles.get_mut(factor).ok_or(Error::CandleDataEmpty)?;
+                            let zero = deque.make_contiguous();
+                            if aggregator.should_aggregate(*factor, zero) {
+                                let candle = aggregator.aggregate(zero)?;
+                                agg.pop_front();
+                                deque.pop_front();
+                                agg.push_back(candle);
+                            }
+                        }
+                    }
+
+                    let agg_candles = match periods.as_ref() {
+                        Some(periods) => periods.candles(),
+                        None => aggregated_candles_map.values().flatten().collect(),
+                    };
+                    strategy.on_aggregated_candles(ctx, agg_candles)
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs the current step: applies the cashflows, the corporate actions and the start of the risk day,
+    /// calls the strategy, executes the orders and the positions, and reports the notifications.
+    ///
+    /// ### Arguments
+    /// * `strategy` - The strategy notified at the end of the step.
+    /// * `on_step` - Calls the strategy with the context and the candle of the step.
+    pub(crate) fn step<S, F>(&mut self, strategy: &mut S, on_step: F) -> Result<()>
+    where
+        S: Strategy,
+        F: FnOnce(&mut S, &mut StrategyContext, &Candle) -> Result<()>,
+    {
+        let candle = self.clock_candle(self.index).ok_or(Error::CandleNotFound)?;
+        self.open_window();
+        self.apply_cashflows(&candle)?;
+        self.apply_corporate_actions(&candle)?;
+        self.start_risk_day(&candle)?;
+        let visible = self.index + 1;
+        on_step(strategy, &mut StrategyContext::new(self, visible), &candle)?;
+        self.execute_orders(&candle)?;
+        self.execute_positions(&candle)?;
+        self.notify(strategy, visible)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    /// Runs the candles between [`Strategy::on_start`] and [`Strategy::on_finish`], recording the
+    /// notifications of the hooks meanwhile.
+    ///
+    /// The positions left open by [`Strategy::on_finish`] are marked again at the last close.
+    pub(crate) fn with_notifications<S, F>(&mut self, strategy: &mut S, run: F) -> Result<()>
+    where
+        S: Strategy,
+        F: FnOnce(&mut Self, &mut S) -> Result<()>,
+    {
+        self.notifications = Some(Vec::new());
+        let len = self.window.end;
+        let result = strategy
+            .on_start(&mut StrategyContext::new(self, 0))
+            .and_then(|_| self.notify(strategy, 0))
+            .and_then(|_| run(self, strategy))
+            .and_then(|_| strategy.on_finish(&mut StrategyContext::new(self, len)))
+            .and_then(|_| self.notify(strategy, len))
+            .and_then(
+                |_| match self.window.clone().last().and_then(|index| self.clock_candle(index)) {
+                    Some(candle) => self.update_unrealized_pnl(&candle),
+                    None => Ok(()),
+                },
+            );
+        self.notifications = None;
+        result
+    }
+
+    /// Records an event for the strategy hooks, if a strategy is running.
+    pub(crate) fn push_notification(&mut self, notification: Notification) {
+        if let Some(notifications) = self.notifications.as_mut() {
+            notifications.push(notification);
+        }
+    }
+
+    /// Reports the recorded events to the strategy hooks, including the events of the hooks themselves.
+    fn notify<S: Strategy>(&mut self, strategy: &mut S, visible: usize) -> Result<()> {
+        loop {
+            let notifications = match self.notifications.as_mut() {
+                Some(notifications) if !notifications.is_empty() => std::mem::take(notifications),
+                _ => return Ok(()),
+            };
+            for notification in notifications {
+                let ctx = &mut StrategyContext::new(self, visible);
+                match notification {
+                    Notification::Filled(position) => strategy.on_order_filled(ctx, &position)?,
+                    Notification::Cancelled(order) => strategy.on_order_cancelled(ctx, &order)?,
+                    Notification::Closed(position, reason, pnl) => {
+                        strategy.on_position_closed(ctx, &position, &reason, pnl)?
+                    }
+                }
+            }
+        }
+    }
+}
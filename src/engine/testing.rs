@@ -0,0 +1,34 @@
+/* Tests only */
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::{Candle, CandleBuilder};
+
+/// Returns a time given in RFC 3339 (e.g., "2025-01-31T10:00:00Z").
+pub(crate) fn time(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+/// Returns the time `hours` hours after 2025-01-01 00:00 UTC.
+pub(crate) fn hour(hours: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_secs(1735689600).unwrap() + Duration::hours(hours)
+}
+
+/// Builds a one-hour candle opening at its close.
+pub(crate) fn get_candle(open_time: DateTime<Utc>, low: f64, high: f64, close: f64, volume: f64) -> Candle {
+    CandleBuilder::builder()
+        .open(close)
+        .high(high)
+        .low(low)
+        .close(close)
+        .volume(volume)
+        .open_time(open_time)
+        .close_time(open_time + Duration::hours(1))
+        .build()
+        .unwrap()
+}
+
+/// Builds a one-hour candle with all its prices at `close` and a volume of 1.
+pub(crate) fn get_flat_candle(open_time: DateTime<Utc>, close: f64) -> Candle {
+    get_candle(open_time, close, close, close, 1.0)
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use super::{Aggregation, Candle};
+use crate::errors::{Error, Result};
+
+/// Calendar period of a time-based aggregation.
+///
+/// The periods are aligned in the timezone of the aggregator: the minutes and hours on midnight
+/// (e.g., 4 hours open at 00:00, 04:00, 08:00...), the weeks on Monday and the months on their first day.
+/// A period of minutes or hours not dividing a day is cut at midnight.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timeframe {
+    /// A number of minutes.
+    Minutes(u32),
+    /// A number of hours.
+    Hours(u32),
+    /// A calendar day.
+    Day,
+    /// A calendar week, from Monday.
+    Week,
+    /// A calendar month.
+    Month,
+}
+
+impl Timeframe {
+    /// Returns the start and the end of the period containing `time`, in UTC.
+    ///
+    /// ### Arguments
+    /// * `time` - A time of the period.
+    /// * `timezone` - The timezone aligning the periods.
+    ///
+    /// ### Returns
+    /// The period, or an error if the timeframe is empty.
+    pub fn period<Tz: TimeZone>(&self, time: DateTime<Utc>, timezone: &Tz) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        let local = time.with_timezone(timezone).naive_local();
+        let date = local.date();
+        let day = |date: NaiveDate| date.and_time(NaiveTime::MIN);
+        let (start, end) = match *self {
+            Timeframe::Minutes(minutes) => Self::intraday(local, minutes)?,
+            Timeframe::Hours(hours) => Self::intraday(local, hours.saturating_mul(60))?,
+            Timeframe::Day => (day(date), day(date + Duration::days(1))),
+            Timeframe::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday().into());
+                (day(monday), day(monday + Duration::days(7)))
+            }
+            Timeframe::Month => {
+                let first = date.with_day(1).ok_or(Error::InvalidFactor)?;
+                let next = match first.month() {
+                    12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1),
+                    month => first.with_month(month + 1),
+                };
+                (day(first), day(next.ok_or(Error::InvalidFactor)?))
+            }
+        };
+        Ok((to_utc(timezone, start), to_utc(timezone, end)))
+    }
+
+    /// Returns the local period of `minutes` containing a local time, aligned on midnight.
+    fn intraday(local: NaiveDateTime, minutes: u32) -> Result<(NaiveDateTime, NaiveDateTime)> {
+        if minutes == 0 {
+            return Err(Error::InvalidFactor);
+        }
+        let midnight = local.date().and_time(NaiveTime::MIN);
+        let length = Duration::minutes(minutes.into());
+        let elapsed = (local - midnight).num_minutes() / length.num_minutes();
+        let start = midnight + length * elapsed as i32;
+        Ok((start, (start + length).min(midnight + Duration::days(1))))
+    }
+}
+
+/// Returns the first instant of a local time, or of the next hour if it is skipped by a change of offset.
+pub(crate) fn to_utc<Tz: TimeZone>(timezone: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| timezone.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+/// Aggregator grouping the candles by calendar periods of their open time.
+///
+/// A period is closed by its last candle, or by the first candle of a later period when the data
+/// has a gap. Its aggregated candle is then given to the strategy until the next period is closed.
+/// The periods partly covered by the data (e.g., at its start or across a gap) are aggregated too,
+/// unless [`TimeAggregator::complete_only`] is set.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::FixedOffset;
+///
+/// // 4-hour bars aligned on midnight UTC, and daily bars of New York, without daylight saving time
+/// let aggregator = TimeAggregator::new(chrono::Utc).timeframe(Timeframe::Hours(4));
+/// let aggregator = TimeAggregator::new(FixedOffset::west_opt(5 * 3600).unwrap())
+///     .timeframe(Timeframe::Day)
+///     .complete_only();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeAggregator<Tz: TimeZone = Utc> {
+    timezone: Tz,
+    timeframes: Vec<Timeframe>,
+    complete_only: bool,
+}
+
+impl<Tz: TimeZone> TimeAggregator<Tz> {
+    /// Creates a new aggregator without timeframe, aligning the periods in the exchange timezone.
+    pub fn new(timezone: Tz) -> Self {
+        Self {
+            timezone,
+            timeframes: Vec::new(),
+            complete_only: false,
+        }
+    }
+
+    /// Adds a timeframe, its candles following the ones of the previous timeframes.
+    pub fn timeframe(mut self, timeframe: Timeframe) -> Self {
+        self.timeframes.push(timeframe);
+        self
+    }
+
+    /// Skips the periods not fully covered by the candles.
+    pub fn complete_only(mut self) -> Self {
+        self.complete_only = true;
+        self
+    }
+}
+
+impl<Tz: TimeZone> Aggregation for TimeAggregator<Tz> {
+    fn factors(&self) -> &[usize] {
+        &[]
+    }
+
+    fn timeframes(&self) -> &[Timeframe] {
+        &self.timeframes
+    }
+
+    fn period(&self, timeframe: &Timeframe, time: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        timeframe.period(time, &self.timezone)
+    }
+
+    fn should_aggregate_period(
+        &self,
+        _timeframe: &Timeframe,
+        candles: &[Candle],
+        period: (DateTime<Utc>, DateTime<Utc>),
+    ) -> bool {
+        let covered = candles
+            .iter()
+            .map(|candle| candle.close_time() - candle.open_time())
+            .sum::<Duration>();
+        !self.complete_only || covered >= period.1 - period.0
+    }
+}
+
+/// Candles of the current period of a timeframe, and the aggregated candle of its last closed period.
+#[derive(Debug)]
+struct Bucket {
+    timeframe: Timeframe,
+    period: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    candles: Vec<Candle>,
+    last: Option<Candle>,
+}
+
+impl Bucket {
+    /// Closes the current period, aggregating its candles.
+    fn close<A: Aggregation>(&mut self, aggregator: &A) -> Result<()> {
+        let candles = std::mem::take(&mut self.candles);
+        if let Some(period) = self.period.take()
+            && !candles.is_empty()
+            && aggregator.should_aggregate_period(&self.timeframe, &candles, period)
+        {
+            self.last = Some(aggregator.aggregate(&candles)?);
+        }
+        Ok(())
+    }
+}
+
+/// Candles grouped by the timeframes of an aggregator during a run.
+#[derive(Debug)]
+pub(crate) struct Periods(Vec<Bucket>);
+
+impl Periods {
+    /// Creates the empty periods of the timeframes.
+    pub(crate) fn new(timeframes: &[Timeframe]) -> Self {
+        Self(
+            timeframes
+                .iter()
+                .map(|&timeframe| Bucket {
+                    timeframe,
+                    period: None,
+                    candles: Vec::new(),
+                    last: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Adds a candle to its period of each timeframe, closing the periods it ends.
+    pub(crate) fn push<A: Aggregation>(&mut self, aggregator: &A, candle: &Candle) -> Result<()> {
+        for bucket in self.0.iter_mut() {
+            let period = aggregator.period(&bucket.timeframe, candle.open_time())?;
+            // a gap in the data: the previous period ended without its last candle
+            if bucket.period.is_some_and(|current| current != period) {
+                bucket.close(aggregator)?;
+            }
+            bucket.period = Some(period);
+            bucket.candles.push(candle.clone());
+            if candle.close_time() >= period.1 {
+                bucket.close(aggregator)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the aggregated candles of the last closed periods, in the order of the timeframes.
+    pub(crate) fn candles(&self) -> Vec<&Candle> {
+        self.0.iter().filter_map(|bucket| bucket.last.as_ref()).collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn calendar_periods() {
+    use chrono::FixedOffset;
+
+    let time = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+    let period = |timeframe: Timeframe, at: &str| timeframe.period(time(at), &Utc).unwrap();
+
+    assert_eq!(
+        period(Timeframe::Hours(4), "2025-01-01T06:00:00Z"),
+        (time("2025-01-01T04:00:00Z"), time("2025-01-01T08:00:00Z"))
+    );
+    assert_eq!(
+        period(Timeframe::Minutes(15), "2025-01-01T06:44:59Z"),
+        (time("2025-01-01T06:30:00Z"), time("2025-01-01T06:45:00Z"))
+    );
+    // 5 hours are cut at midnight
+    assert_eq!(
+        period(Timeframe::Hours(5), "2025-01-01T22:00:00Z"),
+        (time("2025-01-01T20:00:00Z"), time("2025-01-02T00:00:00Z"))
+    );
+    // 2025-01-01 is a Wednesday
+    assert_eq!(
+        period(Timeframe::Week, "2025-01-01T12:00:00Z"),
+        (time("2024-12-30T00:00:00Z"), time("2025-01-06T00:00:00Z"))
+    );
+    assert_eq!(
+        period(Timeframe::Month, "2024-12-31T23:00:00Z"),
+        (time("2024-12-01T00:00:00Z"), time("2025-01-01T00:00:00Z"))
+    );
+    assert_eq!(
+        period(Timeframe::Month, "2024-02-10T00:00:00Z"),
+        (time("2024-02-01T00:00:00Z"), time("2024-03-01T00:00:00Z"))
+    );
+
+    // the days of UTC-5 start at 05:00 UTC
+    let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+    assert_eq!(
+        Timeframe::Day.period(time("2025-01-02T03:00:00Z"), &new_york).unwrap(),
+        (time("2025-01-01T05:00:00Z"), time("2025-01-02T05:00:00Z"))
+    );
+    assert_eq!(
+        Timeframe::Hours(4)
+            .period(time("2025-01-02T03:00:00Z"), &new_york)
+            .unwrap(),
+        (time("2025-01-02T01:00:00Z"), time("2025-01-02T05:00:00Z"))
+    );
+    assert!(Timeframe::Hours(0).period(time("2025-01-01T00:00:00Z"), &Utc).is_err());
+}
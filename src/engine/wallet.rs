@@ -1,19 +1,88 @@
+use chrono::{DateTime, Utc};
+
+use super::{EntryReason, LedgerAccount, LedgerEntry, Reference};
 use crate::errors::{Error, Result};
 
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+/// Type of the accounting amounts: a decimal with the `decimal` feature, a `f64` otherwise.
+#[cfg(feature = "decimal")]
+pub(crate) type Amount = rust_decimal::Decimal;
+
+/// Type of the accounting amounts: a decimal with the `decimal` feature, a `f64` otherwise.
+#[cfg(not(feature = "decimal"))]
+pub(crate) type Amount = f64;
+
+/// The zero amount.
+pub(crate) const ZERO: Amount = {
+    #[cfg(feature = "decimal")]
+    {
+        rust_decimal::Decimal::ZERO
+    }
+    #[cfg(not(feature = "decimal"))]
+    {
+        0.0
+    }
+};
+
+/// Conversion of the prices, quantities and amounts of the API into accounting amounts.
+pub(crate) trait IntoAmount {
+    /// Converts the value into an accounting amount.
+    fn into_amount(self) -> Result<Amount>;
+}
+
+impl IntoAmount for f64 {
+    fn into_amount(self) -> Result<Amount> {
+        #[cfg(feature = "decimal")]
+        return Amount::from_f64(self).ok_or(Error::InvalidAmount(self));
+        #[cfg(not(feature = "decimal"))]
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl IntoAmount for Amount {
+    fn into_amount(self) -> Result<Amount> {
+        Ok(self)
+    }
+}
+
+/// Converts an accounting amount into a `f64` for the API.
+pub(crate) fn to_f64(amount: Amount) -> f64 {
+    #[cfg(feature = "decimal")]
+    return amount.to_f64().unwrap_or(f64::NAN);
+    #[cfg(not(feature = "decimal"))]
+    amount
+}
+
 /// Represents a trading wallet with balance and locked funds management.
+///
+/// With the `decimal` feature, the accounting is done with exact decimal amounts,
+/// and the amounts are converted from and to `f64` at the API boundary.
+///
+/// Every mutation is recorded in a double-entry ledger, see [`Wallet::ledger`] and [`Wallet::reconcile`].
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct Wallet {
     // Initial balance used for reset
-    initial_balance: f64,
+    initial_balance: Amount,
     // Available balance
-    balance: f64,
+    balance: Amount,
     // Funds locked in open positions
-    locked: f64,
+    locked: Amount,
     // Unrealized profit/loss from open positions
-    unrealized_pnl: f64,
+    unrealized_pnl: Amount,
     // Cumulative fees paid
-    fees: f64,
+    fees: Amount,
+    // Deposits minus withdrawals
+    net_deposits: Amount,
+    // Entries of the wallet mutations
+    ledger: Vec<LedgerEntry>,
+    // Order or position of the next entries
+    reference: Reference,
+    // Open time of the candle of the next entries
+    time: Option<DateTime<Utc>>,
 }
 
 impl Wallet {
@@ -23,120 +92,292 @@ impl Wallet {
         if balance <= 0.0 {
             return Err(Error::NegZeroBalance(balance));
         }
+        let balance = balance.into_amount()?;
 
-        Ok(Self {
+        let mut wallet = Self {
             balance,
-            fees: 0.0,
-            locked: 0.0,
-            unrealized_pnl: 0.0,
+            fees: ZERO,
+            locked: ZERO,
+            unrealized_pnl: ZERO,
+            net_deposits: ZERO,
             initial_balance: balance,
-        })
+            ledger: Vec::new(),
+            reference: Reference::None,
+            time: None,
+        };
+        wallet.record(
+            EntryReason::Deposit,
+            LedgerAccount::Cash,
+            LedgerAccount::Capital,
+            balance,
+        );
+        Ok(wallet)
     }
 
     /// Returns the initial balance.
     pub fn initial_balance(&self) -> f64 {
-        self.initial_balance
+        to_f64(self.initial_balance)
     }
 
     /// Returns the locked balance.
     pub fn locked(&self) -> f64 {
-        self.locked
+        to_f64(self.locked)
     }
 
     /// Returns the unrealized pnl.
     pub fn unrealized_pnl(&self) -> f64 {
-        self.unrealized_pnl
+        to_f64(self.unrealized_pnl)
     }
 
     /// Returns the fees paid to the market.
     pub fn fees_paid(&self) -> f64 {
-        self.fees
+        to_f64(self.fees)
+    }
+
+    /// Returns the deposits minus the withdrawals made since the start.
+    pub fn net_deposits(&self) -> f64 {
+        to_f64(self.net_deposits)
     }
 
     /// Returns the balance.
     pub fn balance(&self) -> f64 {
-        self.balance
+        to_f64(self.balance)
     }
 
     /// Returns the total balance.
     pub fn total_balance(&self) -> f64 {
-        self.balance + self.unrealized_pnl
+        to_f64(self.balance + self.unrealized_pnl)
     }
 
     /// Returns the free balance (available for new trades).
     pub fn free_balance(&self) -> Result<f64> {
+        self.free().map(to_f64)
+    }
+
+    /// Returns the entries of the wallet mutations, oldest first.
+    pub fn ledger(&self) -> &[LedgerEntry] {
+        &self.ledger
+    }
+
+    /// Checks that the wallet matches the sum of its ledger entries.
+    ///
+    /// The balance must equal the free and locked funds of the ledger, and the locked funds,
+    /// the fees and the unrealized P&L their own accounts. Without the `decimal` feature,
+    /// a relative tolerance absorbs the floating point drifts.
+    ///
+    /// ### Returns
+    /// Ok if the wallet reconciles, or an error with the first mismatching account.
+    pub fn reconcile(&self) -> Result<()> {
+        let sum = |accounts: &[LedgerAccount]| {
+            self.ledger
+                .iter()
+                .flat_map(|entry| accounts.iter().map(|account| entry.net(*account)))
+                .fold(ZERO, |sum, net| sum + net)
+        };
+        let checks = [
+            (
+                "balance",
+                self.balance,
+                sum(&[LedgerAccount::Cash, LedgerAccount::Locked]),
+            ),
+            ("locked", self.locked, sum(&[LedgerAccount::Locked])),
+            ("fees", self.fees, sum(&[LedgerAccount::Fees])),
+            (
+                "unrealized pnl",
+                self.unrealized_pnl,
+                sum(&[LedgerAccount::UnrealizedPnl]),
+            ),
+        ];
+        for (account, actual, expected) in checks {
+            let (actual, expected) = (to_f64(actual), to_f64(expected));
+            #[cfg(feature = "decimal")]
+            let tolerance = 0.0;
+            #[cfg(not(feature = "decimal"))]
+            let tolerance = 1e-9 * actual.abs().max(expected.abs()).max(1.0);
+            if (actual - expected).abs() > tolerance {
+                return Err(Error::Unreconciled(account.to_string(), actual, expected));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the order or the position, and the candle time, of the next entries.
+    pub(crate) fn on(&mut self, reference: Reference, time: Option<DateTime<Utc>>) -> &mut Self {
+        self.reference = reference;
+        self.time = time;
+        self
+    }
+
+    /// Records an entry moving the amount from the credited account to the debited one.
+    fn record(&mut self, reason: EntryReason, debit: LedgerAccount, credit: LedgerAccount, amount: Amount) {
+        self.ledger.push(LedgerEntry::new(
+            self.time,
+            reason,
+            debit,
+            credit,
+            amount,
+            self.reference,
+        ));
+    }
+
+    /// Returns the free balance as an accounting amount.
+    fn free(&self) -> Result<Amount> {
         let free_balance = self.balance - self.locked;
-        if free_balance < 0.0 {
-            return Err(Error::NegFreeBalance(self.balance, self.locked));
+        if free_balance < ZERO {
+            return Err(Error::NegFreeBalance(to_f64(self.balance), to_f64(self.locked)));
         }
         Ok(free_balance)
     }
 
-    /// Adds funds to the wallet.
-    pub(crate) fn add(&mut self, amount: f64) -> Result<f64> {
+    /// Adds funds to the wallet: the cost of a closed position, its realized P&L, or a cashflow.
+    pub(crate) fn add(&mut self, amount: impl IntoAmount, reason: EntryReason) -> Result<f64> {
+        let amount = amount.into_amount()?;
+        let credit = match reason {
+            EntryReason::FillCost => LedgerAccount::Positions,
+            EntryReason::Funding => LedgerAccount::Funding,
+            _ => LedgerAccount::RealizedPnl,
+        };
         self.balance += amount;
+        self.record(reason, LedgerAccount::Cash, credit, amount);
         self.free_balance()
     }
 
+    /// Deposits funds into the wallet.
+    pub(crate) fn deposit(&mut self, amount: f64) -> Result<()> {
+        if amount <= 0.0 || !amount.is_finite() {
+            return Err(Error::NegZeroBalance(amount));
+        }
+        let amount = amount.into_amount()?;
+        self.balance += amount;
+        self.net_deposits += amount;
+        self.record(
+            EntryReason::Deposit,
+            LedgerAccount::Cash,
+            LedgerAccount::Capital,
+            amount,
+        );
+        Ok(())
+    }
+
+    /// Withdraws funds from the free balance of the wallet.
+    pub(crate) fn withdraw(&mut self, amount: f64) -> Result<()> {
+        if amount <= 0.0 || !amount.is_finite() {
+            return Err(Error::NegZeroBalance(amount));
+        }
+        let free_balance = self.free()?;
+        let amount = amount.into_amount()?;
+        if free_balance < amount {
+            return Err(Error::InsufficientFunds(to_f64(amount), to_f64(free_balance)));
+        }
+        self.balance -= amount;
+        self.net_deposits -= amount;
+        self.record(
+            EntryReason::Withdrawal,
+            LedgerAccount::Capital,
+            LedgerAccount::Cash,
+            amount,
+        );
+        Ok(())
+    }
+
     /// Subtracts funds from the balance (after an order is executed).
     /// Assumes funds are already locked.
-    pub(crate) fn sub(&mut self, amount: f64) -> Result<f64> {
+    pub(crate) fn sub(&mut self, amount: impl IntoAmount) -> Result<f64> {
+        let amount = amount.into_amount()?;
         self.balance -= amount;
         self.locked -= amount;
+        self.record(
+            EntryReason::FillCost,
+            LedgerAccount::Positions,
+            LedgerAccount::Locked,
+            amount,
+        );
         self.free_balance()
     }
 
     /// Subtracts the market fees from the balance (after a position is executed).
-    pub(crate) fn sub_fees(&mut self, amount: f64) -> Result<f64> {
+    pub(crate) fn sub_fees(&mut self, amount: impl IntoAmount) -> Result<f64> {
+        let amount = amount.into_amount()?;
         self.balance -= amount;
         self.fees += amount;
+        self.record(EntryReason::Fee, LedgerAccount::Fees, LedgerAccount::Cash, amount);
         self.free_balance()
     }
 
     /// Locks additional funds for a position.
-    pub(crate) fn lock(&mut self, amount: f64) -> Result<()> {
-        if amount <= 0.0 {
-            return Err(Error::NegZeroBalance(amount));
+    pub(crate) fn lock(&mut self, amount: impl IntoAmount) -> Result<()> {
+        let amount = amount.into_amount()?;
+        if amount <= ZERO {
+            return Err(Error::NegZeroBalance(to_f64(amount)));
         }
-        let free_balance = self.free_balance()?;
+        let free_balance = self.free()?;
         if free_balance < amount {
-            return Err(Error::InsufficientFunds(amount, free_balance));
+            return Err(Error::InsufficientFunds(to_f64(amount), to_f64(free_balance)));
         }
         self.locked += amount;
+        self.record(EntryReason::Lock, LedgerAccount::Locked, LedgerAccount::Cash, amount);
         Ok(())
     }
 
     /// Unlocks funds when an order/position is closed.
-    pub(crate) fn unlock(&mut self, amount: f64) -> Result<()> {
-        if amount <= 0.0 {
-            return Err(Error::NegZeroBalance(amount));
+    pub(crate) fn unlock(&mut self, amount: impl IntoAmount) -> Result<()> {
+        let amount = amount.into_amount()?;
+        if amount <= ZERO {
+            return Err(Error::NegZeroBalance(to_f64(amount)));
         }
-        if self.locked - amount < 0.0 {
-            return Err(Error::UnlockBalance(self.locked, amount));
+        if self.locked - amount < ZERO {
+            return Err(Error::UnlockBalance(to_f64(self.locked), to_f64(amount)));
         }
         self.locked -= amount;
+        self.record(EntryReason::Unlock, LedgerAccount::Cash, LedgerAccount::Locked, amount);
         Ok(())
     }
 
     /// Updates the unrealized P&L.
-    pub(crate) fn set_unrealized_pnl(&mut self, pnl: f64) {
+    pub(crate) fn set_unrealized_pnl(&mut self, pnl: impl IntoAmount) -> Result<()> {
+        let pnl = pnl.into_amount()?;
+        let change = pnl - self.unrealized_pnl;
         self.unrealized_pnl = pnl;
+        if change != ZERO {
+            self.record(
+                EntryReason::Revaluation,
+                LedgerAccount::UnrealizedPnl,
+                LedgerAccount::Valuation,
+                change,
+            );
+        }
+        Ok(())
     }
 
     /// Subtracts the given amount from the wallet's unrealized P&L.
     ///
     /// This function is used when a position's unrealized P&L needs to be adjusted,
     /// typically when a position is closed and its P&L becomes realized.
-    pub(crate) fn sub_pnl(&mut self, amount: f64) {
+    pub(crate) fn sub_pnl(&mut self, amount: Amount) {
         self.unrealized_pnl -= amount;
+        self.record(
+            EntryReason::Revaluation,
+            LedgerAccount::Valuation,
+            LedgerAccount::UnrealizedPnl,
+            amount,
+        );
     }
 
     /// Resets the wallet to its initial balance.
     pub(crate) fn reset(&mut self) {
-        self.fees = 0.0;
-        self.locked = 0.0;
-        self.unrealized_pnl = 0.0;
+        self.fees = ZERO;
+        self.locked = ZERO;
+        self.unrealized_pnl = ZERO;
+        self.net_deposits = ZERO;
         self.balance = self.initial_balance;
+        self.ledger.clear();
+        self.on(Reference::None, None);
+        self.record(
+            EntryReason::Deposit,
+            LedgerAccount::Cash,
+            LedgerAccount::Capital,
+            self.initial_balance,
+        );
     }
 }
 
@@ -146,7 +387,7 @@ fn new_wallet_valid_balance() {
     let wallet = Wallet::new(100.0).unwrap();
     assert_eq!(wallet.balance(), 100.0);
     assert_eq!(wallet.free_balance().unwrap(), 100.0);
-    assert_eq!(wallet.locked, 0.0);
+    assert_eq!(wallet.locked(), 0.0);
 }
 
 #[cfg(test)]
@@ -174,13 +415,13 @@ fn lock_and_unlock_funds() {
 
     // Test lock
     wallet.lock(20.0).unwrap();
-    assert_eq!(wallet.balance, 100.0);
-    assert_eq!(wallet.locked, 20.0);
+    assert_eq!(wallet.balance(), 100.0);
+    assert_eq!(wallet.locked(), 20.0);
 
     // Test unlock
     wallet.unlock(20.0).unwrap();
-    assert_eq!(wallet.balance, 100.0);
-    assert_eq!(wallet.locked, 0.0);
+    assert_eq!(wallet.balance(), 100.0);
+    assert_eq!(wallet.locked(), 0.0);
 }
 
 #[cfg(test)]
@@ -209,8 +450,8 @@ fn sub_funds() {
     // open position
     let free_balance = wallet.sub(20.0).unwrap();
     assert_eq!(free_balance, 80.0);
-    assert_eq!(wallet.balance, 80.0);
-    assert_eq!(wallet.locked, 0.0);
+    assert_eq!(wallet.balance(), 80.0);
+    assert_eq!(wallet.locked(), 0.0);
 }
 
 #[cfg(test)]
@@ -218,10 +459,25 @@ fn sub_funds() {
 fn add_funds() {
     let mut wallet = Wallet::new(100.0).unwrap();
     // close position
-    let free_balance = wallet.add(50.0).unwrap();
+    let free_balance = wallet.add(50.0, EntryReason::Funding).unwrap();
     assert_eq!(free_balance, 150.0);
-    assert_eq!(wallet.balance, 150.0);
-    assert_eq!(wallet.locked, 0.0);
+    assert_eq!(wallet.balance(), 150.0);
+    assert_eq!(wallet.locked(), 0.0);
+}
+
+#[cfg(test)]
+#[test]
+fn deposit_and_withdraw_funds() {
+    let mut wallet = Wallet::new(100.0).unwrap();
+    wallet.deposit(50.0).unwrap();
+    wallet.lock(100.0).unwrap();
+    assert!(matches!(wallet.withdraw(60.0), Err(Error::InsufficientFunds(_, _))));
+    assert!(matches!(wallet.deposit(-10.0), Err(Error::NegZeroBalance(_))));
+
+    wallet.withdraw(30.0).unwrap();
+    assert_eq!(wallet.balance(), 120.0);
+    assert_eq!(wallet.net_deposits(), 20.0);
+    assert_eq!(wallet.initial_balance(), 100.0);
 }
 
 #[cfg(test)]
@@ -230,13 +486,13 @@ fn reset_wallet() {
     let mut wallet = Wallet::new(100.0).unwrap();
     wallet.lock(20.0).unwrap();
     wallet.sub(20.0).unwrap();
-    wallet.add(10.0).unwrap();
+    wallet.add(10.0, EntryReason::FillCost).unwrap();
     wallet.sub_fees(0.2).unwrap();
 
     wallet.reset();
-    assert_eq!(wallet.fees, 0.0);
-    assert_eq!(wallet.locked, 0.0);
-    assert_eq!(wallet.balance, 100.0);
+    assert_eq!(wallet.fees_paid(), 0.0);
+    assert_eq!(wallet.locked(), 0.0);
+    assert_eq!(wallet.balance(), 100.0);
     assert_eq!(wallet.total_balance(), 100.0);
     assert_eq!(wallet.free_balance().unwrap(), 100.0);
 }
@@ -248,20 +504,21 @@ fn open_close_profit_position() {
 
     // place order
     wallet.lock(20.0).unwrap();
-    assert_eq!(wallet.balance, 100.0);
-    assert_eq!(wallet.locked, 20.0);
+    assert_eq!(wallet.balance(), 100.0);
+    assert_eq!(wallet.locked(), 20.0);
     assert_eq!(wallet.free_balance().unwrap(), 80.0);
 
     // open position
     wallet.sub(20.0).unwrap();
-    assert_eq!(wallet.balance, 80.0);
-    assert_eq!(wallet.locked, 0.0);
+    assert_eq!(wallet.balance(), 80.0);
+    assert_eq!(wallet.locked(), 0.0);
     assert_eq!(wallet.free_balance().unwrap(), 80.0);
 
     // close profitable position
-    wallet.add(30.0).unwrap(); // 20.0 (initial locked) + 10.0 (profit)
-    assert_eq!(wallet.balance, 110.0);
-    assert_eq!(wallet.locked, 0.0);
+    wallet.add(20.0, EntryReason::FillCost).unwrap(); // initial locked
+    wallet.add(10.0, EntryReason::RealizedPnl).unwrap(); // profit
+    assert_eq!(wallet.balance(), 110.0);
+    assert_eq!(wallet.locked(), 0.0);
     assert_eq!(wallet.free_balance().unwrap(), 110.0);
 }
 
@@ -272,20 +529,21 @@ fn open_close_loss_position() {
 
     // place order
     wallet.lock(20.0).unwrap();
-    assert_eq!(wallet.balance, 100.0);
-    assert_eq!(wallet.locked, 20.0);
+    assert_eq!(wallet.balance(), 100.0);
+    assert_eq!(wallet.locked(), 20.0);
     assert_eq!(wallet.free_balance().unwrap(), 80.0);
 
     // open position
     wallet.sub(20.0).unwrap();
-    assert_eq!(wallet.balance, 80.0);
-    assert_eq!(wallet.locked, 0.0);
+    assert_eq!(wallet.balance(), 80.0);
+    assert_eq!(wallet.locked(), 0.0);
     assert_eq!(wallet.free_balance().unwrap(), 80.0);
 
     // close unprofitable position
-    wallet.add(10.0).unwrap(); // 20.0 (initial locked) - 10.0 (loss)
-    assert_eq!(wallet.balance, 90.0);
-    assert_eq!(wallet.locked, 0.0);
+    wallet.add(20.0, EntryReason::FillCost).unwrap(); // initial locked
+    wallet.add(-10.0, EntryReason::RealizedPnl).unwrap(); // loss
+    assert_eq!(wallet.balance(), 90.0);
+    assert_eq!(wallet.locked(), 0.0);
     assert_eq!(wallet.free_balance().unwrap(), 90.0);
 }
 
@@ -293,13 +551,66 @@ fn open_close_loss_position() {
 #[test]
 fn unrealized_pnl() {
     let mut wallet = Wallet::new(100.0).unwrap();
-    wallet.set_unrealized_pnl(10.0); // unrealized gain
-    assert_eq!(wallet.unrealized_pnl, 10.0);
+    wallet.set_unrealized_pnl(10.0).unwrap(); // unrealized gain
+    assert_eq!(wallet.unrealized_pnl(), 10.0);
     assert_eq!(wallet.total_balance(), 110.0);
     assert_eq!(wallet.free_balance().unwrap(), 100.0);
 
-    wallet.set_unrealized_pnl(-5.0); // unrealized loss
-    assert_eq!(wallet.unrealized_pnl, -5.0);
+    wallet.set_unrealized_pnl(-5.0).unwrap(); // unrealized loss
+    assert_eq!(wallet.unrealized_pnl(), -5.0);
     assert_eq!(wallet.total_balance(), 95.0);
     assert_eq!(wallet.free_balance().unwrap(), 100.0);
 }
+
+#[cfg(all(test, feature = "decimal"))]
+#[test]
+fn decimal_accounting_is_exact() {
+    let mut wallet = Wallet::new(100.0).unwrap();
+    wallet.lock(0.3).unwrap();
+    // with f64, the last unlock fails on a residual of 0.09999999999999998
+    for _ in 0..3 {
+        wallet.unlock(0.1).unwrap();
+    }
+    assert_eq!(wallet.locked(), 0.0);
+
+    for _ in 0..10 {
+        wallet.sub_fees(0.01).unwrap();
+    }
+    assert_eq!(wallet.fees_paid(), 0.1);
+    assert_eq!(wallet.balance(), 99.9);
+}
+
+#[cfg(test)]
+#[test]
+fn ledger_reconciles_wallet() {
+    let mut wallet = Wallet::new(100.0).unwrap();
+    wallet.on(Reference::Order(1), None).lock(20.0).unwrap();
+    wallet.on(Reference::Position(2), None).sub(20.0).unwrap();
+    wallet.sub_fees(0.2).unwrap();
+    wallet.set_unrealized_pnl(5.0).unwrap();
+    wallet.add(20.0, EntryReason::FillCost).unwrap();
+    wallet.add(-4.0, EntryReason::RealizedPnl).unwrap();
+    wallet.set_unrealized_pnl(0.0).unwrap();
+    wallet.reconcile().unwrap();
+
+    let reasons = wallet.ledger().iter().map(|entry| entry.reason()).collect::<Vec<_>>();
+    assert_eq!(
+        reasons,
+        [
+            EntryReason::Deposit,
+            EntryReason::Lock,
+            EntryReason::FillCost,
+            EntryReason::Fee,
+            EntryReason::Revaluation,
+            EntryReason::FillCost,
+            EntryReason::RealizedPnl,
+            EntryReason::Revaluation,
+        ]
+    );
+    assert_eq!(wallet.ledger()[1].reference(), Reference::Order(1));
+    assert_eq!(wallet.ledger()[3].reference(), Reference::Position(2));
+
+    // a mutation outside the ledger is detected
+    wallet.balance += 1.0.into_amount().unwrap();
+    assert!(matches!(wallet.reconcile(), Err(Error::Unreconciled(..))));
+}
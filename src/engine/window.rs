@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+
+use super::{Backtest, Candle};
+use crate::errors::{Error, Result};
+#[cfg(feature = "metrics")]
+use crate::metrics::Event;
+
+impl Backtest {
+    /// Restricts the trading and the metrics to a window of candles, given by their indexes.
+    ///
+    /// The candles before the window warm the strategy up: it runs on them, but its orders are
+    /// rejected with [`Error::OutsideWindow`]. The equity curve and the metrics start at the first
+    /// candle of the window, and the run stops after its last candle.
+    ///
+    /// ### Arguments
+    /// * `window` - The indexes of the candles of the window, bounded by the data.
+    pub fn with_window(mut self, window: Range<usize>) -> Self {
+        let end = window.end.min(self.clock.len());
+        self.window = window.start.min(end)..end;
+        self
+    }
+
+    /// Restricts the trading and the metrics to the candles opening between two times, see [`Backtest::with_window`].
+    ///
+    /// ### Arguments
+    /// * `start` - The first open time of the window (included).
+    /// * `end` - The last open time of the window (excluded).
+    pub fn with_time_window(self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        let index_of = |time: DateTime<Utc>| self.clock.partition_point(|open_time| *open_time < time);
+        let window = index_of(start)..index_of(end);
+        self.with_window(window)
+    }
+
+    /// Returns the indexes of the candles of the trading window, all the candles by default.
+    pub fn window(&self) -> Range<usize> {
+        self.window.clone()
+    }
+
+    /// Returns true if the current candle is inside the trading window.
+    pub fn in_window(&self) -> bool {
+        self.window.contains(&self.index)
+    }
+
+    /// Returns the open time of the first candle and the close time of the last candle of the trading window.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn window_period(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let first = self.clock_candle(self.window.clone().next()?)?;
+        let last = self.clock_candle(self.window.clone().last()?)?;
+        Some((first.open_time(), last.close_time()))
+    }
+
+    /// Returns the current candle, or the last candle of the window once the run is over.
+    pub(crate) fn current_candle(&self) -> Option<Candle> {
+        match self.index < self.window.end {
+            true => self.clock_candle(self.index),
+            false => self.clock_candle(self.window.end.checked_sub(1)?),
+        }
+    }
+
+    /// Returns an error if the current candle warms the strategy up, before the trading window.
+    pub(crate) fn check_window(&self) -> Result<()> {
+        match self.index < self.window.start {
+            true => Err(Error::OutsideWindow),
+            false => Ok(()),
+        }
+    }
+
+    /// Returns the events since the opening of the window and the balance at the opening.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn window_events(&self) -> (&[Event], f64) {
+        let (events, balance) = self.window_opening;
+        (&self.events[events.min(self.events.len())..], balance)
+    }
+
+    /// Records the state of the account at the opening of the window, the start of the metrics.
+    pub(crate) fn open_window(&mut self) {
+        #[cfg(feature = "metrics")]
+        if self.index == self.window.start {
+            self.window_opening = (self.events.len(), self.wallet.balance());
+        }
+    }
+}
@@ -115,6 +115,182 @@ pub enum Error {
     #[error("Try another order type")]
     MismatchedOrderType,
 
+    /// The execution algorithm of a parent order is invalid.
+    ///
+    /// The number of slices, the visible quantity and the quantity must be positive.
+    #[error("Invalid execution algorithm")]
+    InvalidExecutionAlgo,
+
+    /// The requested parent order was not found.
+    #[error("Parent order not found")]
+    ParentOrderNotFound,
+
+    /// The price is not a multiple of the tick size of the instrument.
+    ///
+    /// ### Arguments
+    /// * `0` - The price.
+    /// * `1` - The tick size.
+    #[error("Price {0} is not a multiple of the tick size {1}")]
+    InvalidTickSize(f64, f64),
+
+    /// The quantity is not a multiple of the lot step of the instrument.
+    ///
+    /// ### Arguments
+    /// * `0` - The quantity.
+    /// * `1` - The lot step.
+    #[error("Quantity {0} is not a multiple of the lot step {1}")]
+    InvalidLotSize(f64, f64),
+
+    /// The quantity is below the minimum quantity of the instrument.
+    ///
+    /// ### Arguments
+    /// * `0` - The quantity.
+    /// * `1` - The minimum quantity.
+    #[error("Quantity {0} is below the minimum quantity {1}")]
+    MinQuantity(f64, f64),
+
+    /// The notional is below the minimum notional of the instrument.
+    ///
+    /// ### Arguments
+    /// * `0` - The notional (price * quantity * multiplier).
+    /// * `1` - The minimum notional.
+    #[error("Notional {0} is below the minimum notional {1}")]
+    MinNotional(f64, f64),
+
+    /// The amount can't be converted into an accounting amount (e.g., NaN or infinite).
+    #[error("Invalid amount {0}")]
+    InvalidAmount(f64),
+
+    /// A symbol is given to several series of a multi-asset backtest.
+    #[error("Duplicate symbol {0}")]
+    DuplicateSymbol(String),
+
+    /// The symbol is not traded by the multi-asset backtest.
+    #[error("Unknown symbol {0}")]
+    UnknownSymbol(String),
+
+    /// The order has no symbol in a multi-asset backtest.
+    #[error("The order has no symbol")]
+    MissingSymbol,
+
+    /// The target weights of a rebalancing are invalid.
+    ///
+    /// Each weight must be positive or zero, and their sum must not exceed 1.
+    /// A single-asset backtest takes the weight of one symbol only.
+    #[error("Invalid target weights")]
+    InvalidWeights,
+
+    /// The chain of futures contracts is empty, missing, or has duplicate symbols.
+    #[error("Invalid contract chain")]
+    InvalidContractChain,
+
+    /// The volatility series of the underlying of an option is missing.
+    #[error("Missing volatility of {0}")]
+    MissingVolatility(String),
+
+    /// The model value of an option is zero or invalid, the option can't be opened.
+    #[error("Invalid option premium {0}")]
+    OptionPremium(f64),
+
+    /// The split ratio or the dividend of a corporate action is invalid.
+    ///
+    /// The ratio must be positive, and the dividend positive and lower than the last close before the ex-date.
+    #[error("Invalid corporate action")]
+    InvalidCorporateAction,
+
+    /// The rate series of a currency is missing or has an invalid rate.
+    #[error("Missing FX rate of {0}")]
+    MissingFxRate(String),
+
+    /// The wallet doesn't match the sum of its ledger entries.
+    ///
+    /// ### Arguments
+    /// * `0` - The mismatching account.
+    /// * `1` - The amount of the wallet.
+    /// * `2` - The sum of the ledger entries.
+    #[error("Unreconciled {0}: {1} in the wallet, {2} in the ledger")]
+    Unreconciled(String, f64, f64),
+
+    /// The net notional of a symbol would exceed the limit of the risk manager.
+    ///
+    /// ### Arguments
+    /// * `0` - The net notional with the order.
+    /// * `1` - The maximum notional.
+    #[error("Position notional {0} exceeds the maximum {1}")]
+    MaxPositionNotional(f64, f64),
+
+    /// The gross exposure would exceed the limit of the risk manager.
+    ///
+    /// ### Arguments
+    /// * `0` - The gross exposure with the order.
+    /// * `1` - The maximum exposure.
+    #[error("Gross exposure {0} exceeds the maximum {1}")]
+    MaxGrossExposure(f64, f64),
+
+    /// The net exposure would exceed the limit of the risk manager.
+    ///
+    /// ### Arguments
+    /// * `0` - The absolute net exposure with the order.
+    /// * `1` - The maximum exposure.
+    #[error("Net exposure {0} exceeds the maximum {1}")]
+    MaxNetExposure(f64, f64),
+
+    /// The number of open positions would exceed the limit of the risk manager.
+    #[error("Maximum of {0} open positions reached")]
+    MaxOpenPositions(usize),
+
+    /// The number of pending orders would exceed the limit of the risk manager.
+    #[error("Maximum of {0} pending orders reached")]
+    MaxPendingOrders(usize),
+
+    /// The notional of the order exceeds the percentage of the equity allowed by the risk manager.
+    ///
+    /// ### Arguments
+    /// * `0` - The notional of the order as a percentage of the equity.
+    /// * `1` - The maximum percentage.
+    #[error("Order size {0}% of the equity exceeds the maximum {1}%")]
+    MaxOrderSize(f64, f64),
+
+    /// The loss since the start of the day reached the limit of the risk manager.
+    ///
+    /// ### Arguments
+    /// * `0` - The loss of equity since the start of the day.
+    /// * `1` - The daily loss limit.
+    #[error("Daily loss {0} reached the limit {1}")]
+    DailyLossLimit(f64, f64),
+
+    /// The last order is too recent for the minimum time between trades of the risk manager.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of seconds left before the next order.
+    #[error("Minimum time between trades not elapsed, {0}s left")]
+    MinTradeInterval(i64),
+
+    /// The order has no stop-loss, required by a risk-based sizing method.
+    #[error("The order has no stop-loss")]
+    MissingStopLoss,
+
+    /// There are not enough candles to compute an indicator.
+    ///
+    /// ### Arguments
+    /// * `0` - The number of candles required.
+    #[error("Not enough candles, {0} required")]
+    NotEnoughCandles(usize),
+
+    /// Trading is halted by the circuit breaker.
+    #[error("Trading is halted")]
+    TradingHalted,
+
+    /// The parameters of an indicator are invalid.
+    ///
+    /// The periods must be positive, and the fast period of a MACD shorter than its slow period.
+    #[error("Invalid indicator parameters")]
+    InvalidIndicator,
+
+    /// The current candle is before the trading window: it only warms the strategy up.
+    #[error("Trading is outside the window")]
+    OutsideWindow,
+
     /// An I/O error occurred.
     ///
     /// ### Arguments
@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+
+use super::Indicator;
+use crate::engine::Candle;
+use crate::errors::{Error, Result};
+
+/// Simple moving average of the closes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    /// Creates a new simple moving average over `period` candles.
+    pub fn new(period: usize) -> Result<Self> {
+        if period == 0 {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        })
+    }
+
+    /// Returns the period of the average.
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Updates the average with the next value of a series.
+    pub fn next_value(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        self.is_ready().then(|| self.sum / self.period as f64)
+    }
+}
+
+impl Indicator for Sma {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        self.next_value(candle.close())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.len() == self.period
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+    }
+}
+
+/// Exponential moving average of the closes.
+///
+/// The smoothing factor is `2 / (period + 1)`, and the average starts from the simple average of
+/// the first `period` closes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ema {
+    seed: Sma,
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// Creates a new exponential moving average over `period` candles.
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            seed: Sma::new(period)?,
+            value: None,
+        })
+    }
+
+    /// Returns the period of the average.
+    pub fn period(&self) -> usize {
+        self.seed.period
+    }
+
+    /// Updates the average with the next value of a series.
+    pub fn next_value(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(previous) => Some(previous + (value - previous) * 2.0 / (self.period() as f64 + 1.0)),
+            None => self.seed.next_value(value),
+        };
+        self.value
+    }
+}
+
+impl Indicator for Ema {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        self.next_value(candle.close())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period()
+    }
+
+    fn reset(&mut self) {
+        self.seed.reset();
+        self.value = None;
+    }
+}
+
+/// Weighted moving average of the closes, the weights decreasing linearly from the last close.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wma {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl Wma {
+    /// Creates a new weighted moving average over `period` candles.
+    pub fn new(period: usize) -> Result<Self> {
+        if period == 0 {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+        })
+    }
+
+    /// Returns the period of the average.
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Updates the average with the next value of a series.
+    pub fn next_value(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if !self.is_ready() {
+            return None;
+        }
+        let weighted = self
+            .window
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i + 1) as f64 * value)
+            .sum::<f64>();
+        let weights = (self.period * (self.period + 1) / 2) as f64;
+        Some(weighted / weights)
+    }
+}
+
+impl Indicator for Wma {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        self.next_value(candle.close())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.len() == self.period
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// Wilder's smoothed average, `(previous * (period - 1) + value) / period`, starting from the
+/// simple average of the first `period` values.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Wilder {
+    seed: Sma,
+    value: Option<f64>,
+}
+
+impl Wilder {
+    /// Creates a new smoothed average over `period` values.
+    pub(crate) fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            seed: Sma::new(period)?,
+            value: None,
+        })
+    }
+
+    /// Updates the average with the next value of a series.
+    pub(crate) fn next_value(&mut self, value: f64) -> Option<f64> {
+        let period = self.seed.period as f64;
+        self.value = match self.value {
+            Some(previous) => Some((previous * (period - 1.0) + value) / period),
+            None => self.seed.next_value(value),
+        };
+        self.value
+    }
+
+    /// Returns true once the average has a value.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Resets the average.
+    pub(crate) fn reset(&mut self) {
+        self.seed.reset();
+        self.value = None;
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn moving_averages_of_closes() {
+    use super::{assert_close, assert_series, get_candles, ta_ema, ta_sma, values};
+
+    let candles = get_candles();
+    let closes = candles.iter().map(|candle| candle.close()).collect::<Vec<_>>();
+    let mut sma = Sma::new(5).unwrap();
+    let mut ema = Ema::new(5).unwrap();
+    let mut wma = Wma::new(5).unwrap();
+    let (smas, emas, wmas) = (
+        values(&mut sma, &candles),
+        values(&mut ema, &candles),
+        values(&mut wma, &candles),
+    );
+
+    // closes 95, 102, 99, 106, 103, 99...
+    assert_eq!(smas[..4], [None; 4]);
+    assert_eq!(emas[..4], [None; 4]);
+    assert_eq!(wmas[..4], [None; 4]);
+    assert_close(smas[4].unwrap(), 101.0);
+    assert_close(smas[5].unwrap(), 101.8);
+    assert_close(smas[39].unwrap(), 119.2);
+    // seeded with the simple average, then 101 + (99 - 101) / 3
+    assert_close(emas[4].unwrap(), 101.0);
+    assert_close(emas[5].unwrap(), 100.333_333_333_333_34);
+    // the averages of the `ta` crate
+    assert_series(&smas, &ta_sma(5, &closes));
+    assert_series(&emas, &ta_ema(5, &closes));
+    // (95 + 2 * 102 + 3 * 99 + 4 * 106 + 5 * 103) / 15
+    assert_close(wmas[4].unwrap(), 102.333_333_333_333_33);
+    assert_close(wmas[5].unwrap(), 101.666_666_666_666_67);
+    assert_close(wmas[39].unwrap(), 119.8);
+
+    assert!(sma.is_ready() && ema.is_ready() && wma.is_ready());
+    assert_eq!((sma.warm_up(), ema.warm_up(), wma.warm_up()), (5, 5, 5));
+    ema.reset();
+    assert!(!ema.is_ready());
+    assert_eq!(values(&mut ema, &candles), emas);
+    assert!(Sma::new(0).is_err());
+}
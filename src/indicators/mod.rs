@@ -0,0 +1,192 @@
+//! Streaming technical indicators.
+//!
+//! Each indicator consumes the candles one by one through the [`Indicator`] trait, and returns
+//! its value once it has seen enough candles to be warmed up.
+//!
+//! - Moving averages: `Sma`, `Ema`, `Wma`.
+//! - Oscillators: `Rsi`, `Macd`, `ImpulseMacd`.
+//! - Volatility: `Bollinger`, `Atr`, `Donchian`.
+//! - Trend: `Adx`, `ParabolicSar`, `SuperTrend`.
+//! - Volume: `Vwap`, `Obv`.
+
+mod average;
+mod oscillator;
+mod trend;
+mod volatility;
+mod volume;
+
+pub use average::*;
+pub use oscillator::*;
+pub use trend::*;
+pub use volatility::*;
+pub use volume::*;
+
+use crate::engine::Candle;
+
+/// Trait for the indicators updated candle by candle.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::DateTime;
+///
+/// let mut sma = Sma::new(2).unwrap();
+/// let candle = CandleBuilder::builder()
+///     .open(100.0)
+///     .high(110.0)
+///     .low(95.0)
+///     .close(105.0)
+///     .volume(1.0)
+///     .open_time(DateTime::default())
+///     .close_time(DateTime::default())
+///     .build()
+///     .unwrap();
+///
+/// // the first candle warms the average up
+/// assert_eq!(sma.next(&candle), None);
+/// assert_eq!(sma.next(&candle), Some(105.0));
+/// assert!(sma.is_ready());
+/// ```
+pub trait Indicator {
+    /// Type of the values of the indicator.
+    type Output;
+
+    /// Updates the indicator with the next candle.
+    ///
+    /// ### Returns
+    /// The value of the indicator, or `None` during the warm-up.
+    fn next(&mut self, candle: &Candle) -> Option<Self::Output>;
+
+    /// Returns true once the indicator has seen enough candles to return values.
+    fn is_ready(&self) -> bool;
+
+    /// Returns the number of candles needed for the first value.
+    fn warm_up(&self) -> usize;
+
+    /// Resets the indicator to its state before the first candle.
+    fn reset(&mut self);
+}
+
+/// Builds 40 hourly candles from a deterministic pattern of integer prices.
+#[cfg(test)]
+fn get_candles() -> Vec<Candle> {
+    use crate::engine::CandleBuilder;
+    use chrono::{DateTime, Duration};
+
+    (0..40)
+        .map(|i: i64| {
+            let close = (100 + (i * 7) % 11 - 5 + i / 2) as f64;
+            let high = close + (i % 3 + 1) as f64;
+            let low = close - ((i * 5) % 4 + 1) as f64;
+            let open_time = DateTime::from_timestamp_secs(1735689600).unwrap() + Duration::hours(i);
+            CandleBuilder::builder()
+                .open(close)
+                .high(high)
+                .low(low)
+                .close(close)
+                .volume((100 + (i * 37) % 50) as f64)
+                .open_time(open_time)
+                .close_time(open_time + Duration::hours(1))
+                .build()
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Runs an indicator over candles, returning its values.
+#[cfg(test)]
+fn values<I: Indicator>(indicator: &mut I, candles: &[Candle]) -> Vec<Option<I::Output>> {
+    candles.iter().map(|candle| indicator.next(candle)).collect()
+}
+
+/// Asserts that a value matches its reference.
+#[cfg(test)]
+fn assert_close(value: f64, expected: f64) {
+    assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+}
+
+/// Asserts that the values of an indicator match their reference, including the warm-up.
+#[cfg(test)]
+fn assert_series(values: &[Option<f64>], expected: &[Option<f64>]) {
+    assert_eq!(values.len(), expected.len());
+    for (value, expected) in values.iter().zip(expected) {
+        assert_eq!(value.is_some(), expected.is_some(), "{value:?} != {expected:?}");
+        if let (Some(value), Some(expected)) = (value, expected) {
+            assert_close(*value, *expected);
+        }
+    }
+}
+
+/// Reference simple average of the `ta` crate, without its values over partial windows.
+#[cfg(test)]
+fn ta_sma(period: usize, values: &[f64]) -> Vec<Option<f64>> {
+    use ta::{Next, indicators::SimpleMovingAverage};
+
+    let mut sma = SimpleMovingAverage::new(period).unwrap();
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| Some(sma.next(*value)).filter(|_| i + 1 >= period))
+        .collect()
+}
+
+/// Reference exponential average of the `ta` crate, started from the simple average of the
+/// first `period` values like [`Ema`].
+#[cfg(test)]
+fn ta_ema(period: usize, values: &[f64]) -> Vec<Option<f64>> {
+    ta_smoothed(period, period, values)
+}
+
+/// Reference Wilder's average: the exponential average of the `ta` crate over `2 * period - 1`
+/// values has the smoothing factor `1 / period`.
+#[cfg(test)]
+fn ta_wilder(period: usize, values: &[f64]) -> Vec<Option<f64>> {
+    ta_smoothed(period, 2 * period - 1, values)
+}
+
+/// Exponential average of the `ta` crate over `ema_period` values, started from the simple average
+/// of the first `period` values.
+#[cfg(test)]
+fn ta_smoothed(period: usize, ema_period: usize, values: &[f64]) -> Vec<Option<f64>> {
+    use ta::{Next, indicators::ExponentialMovingAverage};
+
+    let seeds = ta_sma(period, values);
+    let mut ema = ExponentialMovingAverage::new(ema_period).unwrap();
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| match (i + 1).cmp(&period) {
+            std::cmp::Ordering::Less => None,
+            std::cmp::Ordering::Equal => seeds[i].map(|seed| ema.next(seed)),
+            std::cmp::Ordering::Greater => Some(ema.next(*value)),
+        })
+        .collect()
+}
+
+/// Reference true ranges of the `ta` crate.
+#[cfg(test)]
+fn ta_true_ranges(candles: &[Candle]) -> Vec<f64> {
+    use ta::{DataItem, Next, indicators::TrueRange};
+
+    let mut true_range = TrueRange::new();
+    candles
+        .iter()
+        .map(|candle| {
+            let item = DataItem::builder()
+                .open(candle.open())
+                .high(candle.high())
+                .low(candle.low())
+                .close(candle.close())
+                .volume(candle.volume())
+                .build()
+                .unwrap();
+            true_range.next(&item)
+        })
+        .collect()
+}
+
+/// Pads a series computed from the values of another one with the warm-up of the other series.
+#[cfg(test)]
+fn pad(warm_up: usize, values: Vec<Option<f64>>) -> Vec<Option<f64>> {
+    std::iter::repeat_n(None, warm_up).chain(values).collect()
+}
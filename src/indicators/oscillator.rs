@@ -0,0 +1,323 @@
+use super::{Ema, Indicator, Sma, Wilder};
+use crate::engine::Candle;
+use crate::errors::{Error, Result};
+
+/// Relative strength index of the closes, with Wilder's smoothing of the gains and losses.
+///
+/// The index is between 0 and 100: it is 100 without losses over the period, and 50 on flat closes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rsi {
+    period: usize,
+    previous: Option<f64>,
+    gain: Wilder,
+    loss: Wilder,
+}
+
+impl Rsi {
+    /// Creates a new relative strength index over `period` closes (e.g., 14).
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            period,
+            previous: None,
+            gain: Wilder::new(period)?,
+            loss: Wilder::new(period)?,
+        })
+    }
+
+    /// Returns the period of the index.
+    pub fn period(&self) -> usize {
+        self.period
+    }
+}
+
+impl Indicator for Rsi {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        let close = candle.close();
+        let previous = self.previous.replace(close)?;
+        let change = close - previous;
+        let gain = self.gain.next_value(change.max(0.0));
+        let loss = self.loss.next_value((-change).max(0.0));
+        match (gain?, loss?) {
+            (0.0, 0.0) => Some(50.0),
+            (_, 0.0) => Some(100.0),
+            (gain, loss) => Some(100.0 - 100.0 / (1.0 + gain / loss)),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.loss.is_ready()
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period + 1
+    }
+
+    fn reset(&mut self) {
+        self.previous = None;
+        self.gain.reset();
+        self.loss.reset();
+    }
+}
+
+/// Values of the MACD and the Impulse MACD.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MacdOutput {
+    /// The MACD line.
+    pub macd: f64,
+    /// The signal line, the average of the MACD line.
+    pub signal: f64,
+    /// The MACD line minus the signal line.
+    pub histogram: f64,
+}
+
+impl MacdOutput {
+    /// Creates the values from the MACD and signal lines.
+    fn new(macd: f64, signal: f64) -> Self {
+        Self {
+            macd,
+            signal,
+            histogram: macd - signal,
+        }
+    }
+}
+
+/// Moving average convergence divergence of the closes.
+///
+/// The MACD line is the fast minus the slow exponential average of the closes, and the signal line
+/// the exponential average of the MACD line.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    /// Creates a new MACD with the periods of its averages (e.g., 12, 26 and 9).
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Result<Self> {
+        if fast >= slow {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            fast: Ema::new(fast)?,
+            slow: Ema::new(slow)?,
+            signal: Ema::new(signal)?,
+        })
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<MacdOutput> {
+        let (fast, slow) = (self.fast.next(candle), self.slow.next(candle));
+        let macd = fast? - slow?;
+        let signal = self.signal.next_value(macd)?;
+        Some(MacdOutput::new(macd, signal))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.signal.is_ready()
+    }
+
+    fn warm_up(&self) -> usize {
+        self.slow.period() + self.signal.period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.fast.reset();
+        self.slow.reset();
+        self.signal.reset();
+    }
+}
+
+/// Impulse MACD, the LazyBear variant of the MACD.
+///
+/// The MACD line is the distance of the zero-lag exponential average of the typical price
+/// (high + low + close) / 3 to the channel of Wilder's averages of the highs and the lows,
+/// 0 inside the channel. The signal line is the simple average of the MACD line.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpulseMacd {
+    length: usize,
+    high: Wilder,
+    low: Wilder,
+    ema: Ema,
+    ema_of_ema: Ema,
+    signal: Sma,
+}
+
+impl ImpulseMacd {
+    /// Creates a new Impulse MACD with the period of the averages and of the signal line (e.g., 34 and 9).
+    pub fn new(length: usize, signal: usize) -> Result<Self> {
+        Ok(Self {
+            length,
+            high: Wilder::new(length)?,
+            low: Wilder::new(length)?,
+            ema: Ema::new(length)?,
+            ema_of_ema: Ema::new(length)?,
+            signal: Sma::new(signal)?,
+        })
+    }
+}
+
+impl Indicator for ImpulseMacd {
+    type Output = MacdOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<MacdOutput> {
+        let typical = (candle.high() + candle.low() + candle.close()) / 3.0;
+        let high = self.high.next_value(candle.high());
+        let low = self.low.next_value(candle.low());
+        let ema = self.ema.next_value(typical)?;
+        let ema_of_ema = self.ema_of_ema.next_value(ema)?;
+        let (high, low) = (high?, low?);
+        // zero-lag average
+        let middle = 2.0 * ema - ema_of_ema;
+        let macd = if middle > high {
+            middle - high
+        } else if middle < low {
+            middle - low
+        } else {
+            0.0
+        };
+        let signal = self.signal.next_value(macd)?;
+        Some(MacdOutput::new(macd, signal))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.signal.is_ready()
+    }
+
+    fn warm_up(&self) -> usize {
+        2 * self.length - 1 + self.signal.period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.high.reset();
+        self.low.reset();
+        self.ema.reset();
+        self.ema_of_ema.reset();
+        self.signal.reset();
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn rsi_of_wilder_example() {
+    use super::{assert_close, get_candles, values};
+    use crate::engine::CandleBuilder;
+    use chrono::DateTime;
+
+    // the closes of the example of Wilder's RSI, computed without rounding the averages
+    let closes = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61, 46.28, 46.28, 46.00,
+        46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45, 45.78, 45.35, 44.03, 44.18, 44.22, 44.57, 43.42, 42.66,
+        43.13,
+    ];
+    let expected = [
+        70.46, 66.25, 66.48, 69.35, 66.29, 57.92, 62.88, 63.21, 56.01, 62.34, 54.67, 50.39, 40.02, 41.49, 41.90, 45.50,
+        37.32, 33.09, 37.79,
+    ];
+    let candles = closes
+        .iter()
+        .map(|close| {
+            CandleBuilder::builder()
+                .open(*close)
+                .high(*close)
+                .low(*close)
+                .close(*close)
+                .volume(1.0)
+                .open_time(DateTime::default())
+                .close_time(DateTime::default())
+                .build()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut rsi = Rsi::new(14).unwrap();
+    let rsis = values(&mut rsi, &candles);
+    assert_eq!(rsi.warm_up(), 15);
+    assert_eq!(rsis[..14], [None; 14]);
+    for (rsi, expected) in rsis[14..].iter().zip(expected) {
+        assert!((rsi.unwrap() - expected).abs() < 0.005, "{rsi:?} != {expected}");
+    }
+
+    let mut rsi = Rsi::new(14).unwrap();
+    let rsis = values(&mut rsi, &get_candles());
+    assert_close(rsis[14].unwrap(), 61.643_835_616_438_36);
+    assert_close(rsis[39].unwrap(), 58.080_188_164_598_766);
+}
+
+#[cfg(test)]
+#[test]
+fn macd_and_impulse_macd() {
+    use super::{assert_close, assert_series, get_candles, pad, ta_ema, ta_sma, ta_wilder, values};
+
+    let candles = get_candles();
+    let closes = candles.iter().map(|candle| candle.close()).collect::<Vec<_>>();
+    let assert_outputs = |outputs: &[Option<MacdOutput>], macd: &[Option<f64>], signal: &[Option<f64>]| {
+        let line = outputs
+            .iter()
+            .map(|output| output.map(|output| output.macd))
+            .collect::<Vec<_>>();
+        let signal_line = outputs
+            .iter()
+            .map(|output| output.map(|output| output.signal))
+            .collect::<Vec<_>>();
+        // the MACD line is given with the signal line only
+        let macd = macd
+            .iter()
+            .zip(signal)
+            .map(|(macd, signal)| signal.and(*macd))
+            .collect::<Vec<_>>();
+        assert_series(&line, &macd);
+        assert_series(&signal_line, signal);
+        for output in outputs.iter().flatten() {
+            assert_close(output.histogram, output.macd - output.signal);
+        }
+    };
+
+    let mut macd = Macd::new(5, 10, 4).unwrap();
+    let macds = values(&mut macd, &candles);
+    assert_eq!(macd.warm_up(), 13);
+    assert_eq!(macds[..12], [None; 12]);
+    // the averages of the `ta` crate
+    let line = ta_ema(5, &closes)
+        .into_iter()
+        .zip(ta_ema(10, &closes))
+        .map(|(fast, slow)| Some(fast? - slow?))
+        .collect::<Vec<_>>();
+    let signal = pad(9, ta_ema(4, &line.iter().flatten().copied().collect::<Vec<_>>()));
+    assert_outputs(&macds, &line, &signal);
+    assert!(Macd::new(10, 5, 4).is_err());
+
+    let mut impulse = ImpulseMacd::new(5, 4).unwrap();
+    let impulses = values(&mut impulse, &candles);
+    assert_eq!(impulse.warm_up(), 12);
+    assert_eq!(impulses[..11], [None; 11]);
+    // the zero-lag average inside the channel
+    assert_eq!(impulses[11].unwrap().macd, 0.0);
+    let highs = ta_wilder(5, &candles.iter().map(|candle| candle.high()).collect::<Vec<_>>());
+    let lows = ta_wilder(5, &candles.iter().map(|candle| candle.low()).collect::<Vec<_>>());
+    let typicals = candles
+        .iter()
+        .map(|candle| (candle.high() + candle.low() + candle.close()) / 3.0)
+        .collect::<Vec<_>>();
+    let emas = ta_ema(5, &typicals);
+    let emas_of_emas = pad(4, ta_ema(5, &emas.iter().flatten().copied().collect::<Vec<_>>()));
+    let line = (0..candles.len())
+        .map(|i| {
+            let (high, low) = (highs[i]?, lows[i]?);
+            let middle = 2.0 * emas[i]? - emas_of_emas[i]?;
+            Some((middle - high).max(0.0) + (middle - low).min(0.0))
+        })
+        .collect::<Vec<_>>();
+    let signal = pad(8, ta_sma(4, &line.iter().flatten().copied().collect::<Vec<_>>()));
+    assert_outputs(&impulses, &line, &signal);
+    assert!(impulses.iter().flatten().any(|output| output.macd > 0.0));
+}
@@ -0,0 +1,490 @@
+use super::{Atr, Indicator, Wilder, true_range};
+use crate::engine::Candle;
+use crate::errors::{Error, Result};
+
+/// Values of the average directional index.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AdxOutput {
+    /// The average directional index, the strength of the trend between 0 and 100.
+    pub adx: f64,
+    /// The positive directional indicator.
+    pub plus_di: f64,
+    /// The negative directional indicator.
+    pub minus_di: f64,
+}
+
+/// Average directional index of Wilder.
+///
+/// The directional movements and the true range are smoothed with Wilder's averages over the period,
+/// and the index is Wilder's average of the directional index.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adx {
+    period: usize,
+    previous: Option<(f64, f64, f64)>,
+    range: Wilder,
+    plus: Wilder,
+    minus: Wilder,
+    adx: Wilder,
+}
+
+impl Adx {
+    /// Creates a new average directional index over `period` candles (e.g., 14).
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            period,
+            previous: None,
+            range: Wilder::new(period)?,
+            plus: Wilder::new(period)?,
+            minus: Wilder::new(period)?,
+            adx: Wilder::new(period)?,
+        })
+    }
+}
+
+impl Indicator for Adx {
+    type Output = AdxOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<AdxOutput> {
+        let (high, low, close) = self.previous.replace((candle.high(), candle.low(), candle.close()))?;
+        let up = candle.high() - high;
+        let down = low - candle.low();
+        let plus = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus = if down > up && down > 0.0 { down } else { 0.0 };
+
+        let range = self.range.next_value(true_range(candle, Some(close)));
+        let plus = self.plus.next_value(plus);
+        let minus = self.minus.next_value(minus);
+        let (range, plus, minus) = (range?, plus?, minus?);
+        let (plus_di, minus_di) = match range {
+            0.0 => (0.0, 0.0),
+            _ => (100.0 * plus / range, 100.0 * minus / range),
+        };
+        let dx = match plus_di + minus_di {
+            0.0 => 0.0,
+            sum => 100.0 * (plus_di - minus_di).abs() / sum,
+        };
+        Some(AdxOutput {
+            adx: self.adx.next_value(dx)?,
+            plus_di,
+            minus_di,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.adx.is_ready()
+    }
+
+    fn warm_up(&self) -> usize {
+        2 * self.period
+    }
+
+    fn reset(&mut self) {
+        self.previous = None;
+        self.range.reset();
+        self.plus.reset();
+        self.minus.reset();
+        self.adx.reset();
+    }
+}
+
+/// Value of a trailing stop indicator and the direction of the trend.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StopOutput {
+    /// The stop level: below the price in an uptrend, above it in a downtrend.
+    pub value: f64,
+    /// True in an uptrend.
+    pub long: bool,
+}
+
+/// State of the Parabolic SAR.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SarState {
+    long: bool,
+    sar: f64,
+    extreme: f64,
+    factor: f64,
+}
+
+/// Parabolic stop and reverse of Wilder.
+///
+/// The trend starts on the second candle, up if the close rose, from the extreme of the first candle.
+/// The stop then moves towards the extreme price of the trend by an acceleration factor, raised by
+/// `step` at each new extreme up to `max`, and never inside the range of the two previous candles.
+/// The trend reverses when a candle reaches the stop.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParabolicSar {
+    step: f64,
+    max: f64,
+    candles: Vec<(f64, f64, f64)>,
+    state: Option<SarState>,
+}
+
+impl ParabolicSar {
+    /// Creates a new Parabolic SAR with the step and the maximum of the acceleration factor (e.g., 0.02 and 0.2).
+    pub fn new(step: f64, max: f64) -> Result<Self> {
+        if step <= 0.0 || max < step {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            step,
+            max,
+            candles: Vec::with_capacity(3),
+            state: None,
+        })
+    }
+}
+
+impl Indicator for ParabolicSar {
+    type Output = StopOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<StopOutput> {
+        let (high, low) = (candle.high(), candle.low());
+        self.candles.push((high, low, candle.close()));
+        if self.candles.len() > 3 {
+            self.candles.remove(0);
+        }
+        let state = match self.state {
+            None => {
+                let &[(previous_high, previous_low, previous_close), _] = self.candles.as_slice() else {
+                    return None;
+                };
+                let long = candle.close() >= previous_close;
+                SarState {
+                    long,
+                    sar: if long { previous_low } else { previous_high },
+                    extreme: if long {
+                        high.max(previous_high)
+                    } else {
+                        low.min(previous_low)
+                    },
+                    factor: self.step,
+                }
+            }
+            Some(SarState {
+                long,
+                sar,
+                extreme,
+                factor,
+            }) => {
+                let sar = sar + factor * (extreme - sar);
+                // the two previous candles
+                let (first, second) = (self.candles[0], self.candles[1]);
+                if long {
+                    let sar = sar.min(first.1).min(second.1);
+                    if low <= sar {
+                        SarState {
+                            long: false,
+                            sar: extreme,
+                            extreme: low,
+                            factor: self.step,
+                        }
+                    } else if high > extreme {
+                        SarState {
+                            long,
+                            sar,
+                            extreme: high,
+                            factor: (factor + self.step).min(self.max),
+                        }
+                    } else {
+                        SarState {
+                            long,
+                            sar,
+                            extreme,
+                            factor,
+                        }
+                    }
+                } else {
+                    let sar = sar.max(first.0).max(second.0);
+                    if high >= sar {
+                        SarState {
+                            long: true,
+                            sar: extreme,
+                            extreme: high,
+                            factor: self.step,
+                        }
+                    } else if low < extreme {
+                        SarState {
+                            long,
+                            sar,
+                            extreme: low,
+                            factor: (factor + self.step).min(self.max),
+                        }
+                    } else {
+                        SarState {
+                            long,
+                            sar,
+                            extreme,
+                            factor,
+                        }
+                    }
+                }
+            }
+        };
+        self.state = Some(state);
+        Some(StopOutput {
+            value: state.sar,
+            long: state.long,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.state.is_some()
+    }
+
+    fn warm_up(&self) -> usize {
+        2
+    }
+
+    fn reset(&mut self) {
+        self.candles.clear();
+        self.state = None;
+    }
+}
+
+/// SuperTrend: a trailing stop a multiple of the average true range away from the middle of the candle.
+///
+/// The bands around (high + low) / 2 only tighten while the close stays inside them, and the trend
+/// reverses when the close crosses the stop. The first value is in a downtrend.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuperTrend {
+    atr: Atr,
+    multiplier: f64,
+    previous_close: Option<f64>,
+    bands: Option<(f64, f64, bool)>,
+}
+
+impl SuperTrend {
+    /// Creates a new SuperTrend over `period` candles, `multiplier` ATR away from the price (e.g., 10 and 3.0).
+    pub fn new(period: usize, multiplier: f64) -> Result<Self> {
+        if multiplier <= 0.0 {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            atr: Atr::new(period)?,
+            multiplier,
+            previous_close: None,
+            bands: None,
+        })
+    }
+}
+
+impl Indicator for SuperTrend {
+    type Output = StopOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<StopOutput> {
+        let close = candle.close();
+        let previous_close = self.previous_close.replace(close);
+        let atr = self.atr.next(candle)?;
+        let middle = (candle.high() + candle.low()) / 2.0;
+        let (upper, lower) = (middle + self.multiplier * atr, middle - self.multiplier * atr);
+
+        let (upper, lower, long) = match (self.bands, previous_close) {
+            (Some((previous_upper, previous_lower, long)), Some(previous_close)) => {
+                let upper = if upper < previous_upper || previous_close > previous_upper {
+                    upper
+                } else {
+                    previous_upper
+                };
+                let lower = if lower > previous_lower || previous_close < previous_lower {
+                    lower
+                } else {
+                    previous_lower
+                };
+                let long = if long { close >= lower } else { close > upper };
+                (upper, lower, long)
+            }
+            _ => (upper, lower, false),
+        };
+        self.bands = Some((upper, lower, long));
+        Some(StopOutput {
+            value: if long { lower } else { upper },
+            long,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.bands.is_some()
+    }
+
+    fn warm_up(&self) -> usize {
+        self.atr.warm_up()
+    }
+
+    fn reset(&mut self) {
+        self.atr.reset();
+        self.previous_close = None;
+        self.bands = None;
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn adx_of_candles() {
+    use super::{assert_series, get_candles, pad, ta_true_ranges, ta_wilder, values};
+
+    let candles = get_candles();
+    let mut adx = Adx::new(5).unwrap();
+    let adxs = values(&mut adx, &candles);
+    assert_eq!(adx.warm_up(), 10);
+    assert_eq!(adxs[..9], [None; 9]);
+    assert!(adxs[9].is_some());
+
+    // Wilder's averages of the true ranges of the `ta` crate and of the directional movements
+    let (pluses, minuses): (Vec<_>, Vec<_>) = candles
+        .windows(2)
+        .map(|pair| {
+            let up = pair[1].high() - pair[0].high();
+            let down = pair[0].low() - pair[1].low();
+            (
+                if up > down && up > 0.0 { up } else { 0.0 },
+                if down > up && down > 0.0 { down } else { 0.0 },
+            )
+        })
+        .unzip();
+    let ranges = ta_wilder(5, &ta_true_ranges(&candles)[1..]);
+    let (pluses, minuses) = (ta_wilder(5, &pluses), ta_wilder(5, &minuses));
+    let (plus_dis, minus_dis): (Vec<_>, Vec<_>) = (0..ranges.len())
+        .map(|i| match (ranges[i], pluses[i], minuses[i]) {
+            (Some(range), Some(plus), Some(minus)) => (Some(100.0 * plus / range), Some(100.0 * minus / range)),
+            _ => (None, None),
+        })
+        .unzip();
+    let dxs = plus_dis
+        .iter()
+        .zip(&minus_dis)
+        .filter_map(|(plus, minus)| Some(100.0 * ((*plus)? - (*minus)?).abs() / ((*plus)? + (*minus)?)))
+        .collect::<Vec<_>>();
+    let expected = pad(5, ta_wilder(5, &dxs));
+
+    let field = |field: fn(&AdxOutput) -> f64| adxs.iter().map(|output| output.as_ref().map(field)).collect::<Vec<_>>();
+    // the directional indicators are given with the index only
+    let only_with_index = |values: Vec<Option<f64>>| {
+        pad(1, values)
+            .into_iter()
+            .zip(&expected)
+            .map(|(value, adx)| adx.and(value))
+            .collect::<Vec<_>>()
+    };
+    assert_series(&field(|output| output.adx), &expected);
+    assert_series(&field(|output| output.plus_di), &only_with_index(plus_dis));
+    assert_series(&field(|output| output.minus_di), &only_with_index(minus_dis));
+}
+
+#[cfg(test)]
+#[test]
+fn trailing_stop_indicators() {
+    use super::{assert_close, values};
+    use crate::engine::CandleBuilder;
+    use chrono::DateTime;
+
+    // hand-computed examples: every expected stop follows from the rules of the indicator on the candles above it
+    let build_candles = |candles: &[(f64, f64, f64)]| {
+        candles
+            .iter()
+            .map(|&(high, low, close)| {
+                CandleBuilder::builder()
+                    .open(close)
+                    .high(high)
+                    .low(low)
+                    .close(close)
+                    .volume(1.0)
+                    .open_time(DateTime::default())
+                    .close_time(DateTime::default())
+                    .build()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>()
+    };
+    let assert_stops = |outputs: &[Option<StopOutput>], expected: &[Option<(f64, bool)>]| {
+        assert_eq!(outputs.len(), expected.len());
+        for (output, expected) in outputs.iter().zip(expected) {
+            match (output, expected) {
+                (Some(output), Some((value, long))) => {
+                    assert_close(output.value, *value);
+                    assert_eq!(output.long, *long);
+                }
+                _ => assert_eq!(output.is_none(), expected.is_none()),
+            }
+        }
+    };
+
+    // (high, low, close)
+    let candles = build_candles(&[
+        (10.0, 8.0, 9.0),
+        (11.0, 9.0, 10.5),
+        (12.0, 10.0, 11.5),
+        (14.0, 11.0, 13.5),
+        (15.0, 13.0, 14.0),
+        (14.5, 12.0, 12.5),
+        (13.0, 8.5, 9.0),
+        (10.0, 7.0, 7.5),
+        (9.0, 6.0, 6.5),
+        (15.0, 10.0, 14.5),
+    ]);
+    let mut sar = ParabolicSar::new(0.02, 0.2).unwrap();
+    assert_stops(
+        &values(&mut sar, &candles),
+        &[
+            None,
+            // the close rose: the stop starts at the previous low, extreme 11
+            Some((8.0, true)),
+            // 8 + 0.02 * (11 - 8) = 8.06, bounded by the low 8 of the previous candles, extreme 12
+            Some((8.0, true)),
+            // 8 + 0.04 * (12 - 8), extreme 14
+            Some((8.16, true)),
+            // 8.16 + 0.06 * (14 - 8.16), extreme 15
+            Some((8.5104, true)),
+            // 8.5104 + 0.08 * (15 - 8.5104)
+            Some((9.029568, true)),
+            // 9.029568 + 0.08 * (15 - 9.029568) = 9.507203 is above the low 8.5: reversal to the extreme 15
+            Some((15.0, false)),
+            // 15 + 0.02 * (8.5 - 15), extreme 7
+            Some((14.87, false)),
+            // 14.87 + 0.04 * (7 - 14.87), extreme 6
+            Some((14.5552, false)),
+            // 14.5552 + 0.06 * (6 - 14.5552) = 14.041888 is below the high 15: reversal to the extreme 6
+            Some((6.0, true)),
+        ],
+    );
+    assert!(ParabolicSar::new(0.2, 0.02).is_err());
+
+    let candles = build_candles(&[
+        (12.0, 10.0, 11.0),
+        (13.0, 11.0, 12.5),
+        (15.0, 12.0, 14.5),
+        (16.0, 14.0, 15.5),
+        (15.0, 13.0, 13.5),
+        (13.5, 11.0, 11.5),
+        (12.0, 10.0, 10.5),
+    ]);
+    let mut supertrend = SuperTrend::new(2, 1.0).unwrap();
+    let stops = values(&mut supertrend, &candles);
+    assert_stops(
+        &stops,
+        &[
+            None,
+            // true ranges 2 and 2: ATR 2, bands 12 ± 2 on a downtrend start
+            Some((14.0, false)),
+            // true range 3: ATR 2.5, the upper band 16 keeps 14, the close 14.5 breaks above it
+            Some((11.0, true)),
+            // true range 2: ATR 2.25, lower band 15 - 2.25
+            Some((12.75, true)),
+            // true range 2.5: ATR 2.375, the lower band 14 - 2.375 = 11.625 keeps 12.75
+            Some((12.75, true)),
+            // true range 2.5: ATR 2.4375, the close 11.5 falls below 12.75, upper band 12.25 + 2.4375
+            Some((14.6875, false)),
+            // true range 2: ATR 2.21875, upper band 11 + 2.21875
+            Some((13.21875, false)),
+        ],
+    );
+
+    supertrend.reset();
+    assert!(!supertrend.is_ready());
+    assert_eq!(values(&mut supertrend, &candles), stops);
+}
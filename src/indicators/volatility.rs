@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+use super::{Indicator, Wilder};
+use crate::engine::Candle;
+use crate::errors::{Error, Result};
+
+/// Upper, middle and lower lines of a channel.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BandsOutput {
+    /// The upper band.
+    pub upper: f64,
+    /// The middle line.
+    pub middle: f64,
+    /// The lower band.
+    pub lower: f64,
+}
+
+/// Bollinger bands: the simple average of the closes, plus and minus a multiple of their standard deviation.
+///
+/// The standard deviation is the population deviation of the closes over the period.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bollinger {
+    period: usize,
+    multiplier: f64,
+    window: VecDeque<f64>,
+}
+
+impl Bollinger {
+    /// Creates new Bollinger bands over `period` closes, `multiplier` deviations away from the average (e.g., 20 and 2.0).
+    pub fn new(period: usize, multiplier: f64) -> Result<Self> {
+        if period == 0 || multiplier < 0.0 {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            period,
+            multiplier,
+            window: VecDeque::with_capacity(period + 1),
+        })
+    }
+}
+
+impl Indicator for Bollinger {
+    type Output = BandsOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<BandsOutput> {
+        self.window.push_back(candle.close());
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if !self.is_ready() {
+            return None;
+        }
+        let period = self.period as f64;
+        let middle = self.window.iter().sum::<f64>() / period;
+        let variance = self.window.iter().map(|close| (close - middle).powi(2)).sum::<f64>() / period;
+        let deviation = self.multiplier * variance.sqrt();
+        Some(BandsOutput {
+            upper: middle + deviation,
+            middle,
+            lower: middle - deviation,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.len() == self.period
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// Returns the true range of a candle: its range extended to the previous close.
+pub(crate) fn true_range(candle: &Candle, previous_close: Option<f64>) -> f64 {
+    let range = candle.high() - candle.low();
+    match previous_close {
+        Some(close) => range
+            .max((candle.high() - close).abs())
+            .max((candle.low() - close).abs()),
+        None => range,
+    }
+}
+
+/// Average true range, with Wilder's smoothing.
+///
+/// The true range of the first candle is its range.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<f64>,
+    average: Wilder,
+}
+
+impl Atr {
+    /// Creates a new average true range over `period` candles (e.g., 14).
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            period,
+            previous_close: None,
+            average: Wilder::new(period)?,
+        })
+    }
+}
+
+impl Indicator for Atr {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        let range = true_range(candle, self.previous_close.replace(candle.close()));
+        self.average.next_value(range)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.average.is_ready()
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+
+    fn reset(&mut self) {
+        self.previous_close = None;
+        self.average.reset();
+    }
+}
+
+/// Donchian channel: the highest high and the lowest low of the period, and their middle.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Donchian {
+    period: usize,
+    window: VecDeque<(f64, f64)>,
+}
+
+impl Donchian {
+    /// Creates a new Donchian channel over `period` candles (e.g., 20).
+    pub fn new(period: usize) -> Result<Self> {
+        if period == 0 {
+            return Err(Error::InvalidIndicator);
+        }
+        Ok(Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+        })
+    }
+}
+
+impl Indicator for Donchian {
+    type Output = BandsOutput;
+
+    fn next(&mut self, candle: &Candle) -> Option<BandsOutput> {
+        self.window.push_back((candle.high(), candle.low()));
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if !self.is_ready() {
+            return None;
+        }
+        let upper = self.window.iter().map(|(high, _)| *high).fold(f64::MIN, f64::max);
+        let lower = self.window.iter().map(|(_, low)| *low).fold(f64::MAX, f64::min);
+        Some(BandsOutput {
+            upper,
+            middle: (upper + lower) / 2.0,
+            lower,
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.len() == self.period
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn volatility_channels() {
+    use super::{assert_close, assert_series, get_candles, ta_true_ranges, ta_wilder, values};
+    use ta::{Next, indicators::BollingerBands};
+
+    let candles = get_candles();
+    let assert_bands = |output: Option<BandsOutput>, (upper, middle, lower): (f64, f64, f64)| {
+        let output = output.unwrap();
+        assert_close(output.upper, upper);
+        assert_close(output.middle, middle);
+        assert_close(output.lower, lower);
+    };
+
+    let mut bollinger = Bollinger::new(5, 2.0).unwrap();
+    let bands = values(&mut bollinger, &candles);
+    assert_eq!(bands[..4], [None; 4]);
+    // the bands of the `ta` crate
+    let mut reference = BollingerBands::new(5, 2.0).unwrap();
+    for (output, candle) in bands.iter().zip(&candles) {
+        let expected = reference.next(candle.close());
+        if output.is_some() {
+            assert_bands(*output, (expected.upper, expected.average, expected.lower));
+        }
+    }
+
+    let mut atr = Atr::new(5).unwrap();
+    let atrs = values(&mut atr, &candles);
+    assert_eq!(atrs[..4], [None; 4]);
+    // true ranges 2, 9, 6, 8, 4, then 6: (5.8 * 4 + 6) / 5
+    assert_close(atrs[4].unwrap(), 5.8);
+    assert_close(atrs[5].unwrap(), 5.84);
+    // Wilder's average of the true ranges of the `ta` crate
+    assert_series(&atrs, &ta_wilder(5, &ta_true_ranges(&candles)));
+
+    let mut donchian = Donchian::new(5).unwrap();
+    let channels = values(&mut donchian, &candles);
+    assert_eq!(channels[..4], [None; 4]);
+    assert_bands(channels[4], (107.0, 100.5, 94.0));
+    assert_bands(channels[39], (124.0, 117.5, 111.0));
+
+    assert!(bollinger.is_ready() && atr.is_ready() && donchian.is_ready());
+    atr.reset();
+    assert_eq!(values(&mut atr, &candles), atrs);
+}
@@ -0,0 +1,147 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use super::Indicator;
+use crate::engine::Candle;
+
+/// Volume-weighted average of the typical price (high + low + close) / 3, reset at each session.
+///
+/// A session is a calendar day in the exchange timezone, given by the open time of the candles.
+/// A session without volume yet returns the typical price.
+///
+/// ### Example
+/// ```rust
+/// use bts_rs::prelude::*;
+/// use chrono::FixedOffset;
+///
+/// // sessions starting at midnight UTC
+/// let vwap = Vwap::new(chrono::Utc);
+/// // sessions starting at midnight in New York, without daylight saving time
+/// let vwap = Vwap::new(FixedOffset::west_opt(5 * 3600).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vwap<Tz: TimeZone = Utc> {
+    timezone: Tz,
+    session: Option<NaiveDate>,
+    value: f64,
+    volume: f64,
+}
+
+impl<Tz: TimeZone> Vwap<Tz> {
+    /// Creates a new session VWAP in the exchange timezone.
+    pub fn new(timezone: Tz) -> Self {
+        Self {
+            timezone,
+            session: None,
+            value: 0.0,
+            volume: 0.0,
+        }
+    }
+}
+
+impl<Tz: TimeZone> Indicator for Vwap<Tz> {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        let session = candle.open_time().with_timezone(&self.timezone).date_naive();
+        if self.session.replace(session) != Some(session) {
+            self.value = 0.0;
+            self.volume = 0.0;
+        }
+        let typical = (candle.high() + candle.low() + candle.close()) / 3.0;
+        self.value += typical * candle.volume();
+        self.volume += candle.volume();
+        match self.volume {
+            0.0 => Some(typical),
+            volume => Some(self.value / volume),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.session.is_some()
+    }
+
+    fn warm_up(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.session = None;
+        self.value = 0.0;
+        self.volume = 0.0;
+    }
+}
+
+/// On-balance volume: the cumulated volume, added on a rising close and subtracted on a falling one.
+///
+/// The volume starts at 0 on the first candle.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Obv {
+    previous_close: Option<f64>,
+    value: f64,
+}
+
+impl Obv {
+    /// Creates a new on-balance volume.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator for Obv {
+    type Output = f64;
+
+    fn next(&mut self, candle: &Candle) -> Option<f64> {
+        let close = candle.close();
+        if let Some(previous) = self.previous_close.replace(close) {
+            if close > previous {
+                self.value += candle.volume();
+            } else if close < previous {
+                self.value -= candle.volume();
+            }
+        }
+        Some(self.value)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.previous_close.is_some()
+    }
+
+    fn warm_up(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.previous_close = None;
+        self.value = 0.0;
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn volume_indicators() {
+    use super::{assert_close, get_candles, values};
+    use chrono::FixedOffset;
+
+    let candles = get_candles();
+    let mut vwap = Vwap::new(Utc);
+    let vwaps = values(&mut vwap, &candles);
+    assert_close(vwaps[0].unwrap(), 95.0);
+    assert_close(vwaps[1].unwrap(), 99.046_413_502_109_7);
+    assert_close(vwaps[23].unwrap(), 105.250_281_341_435_97);
+    // a new session at midnight
+    assert_close(vwaps[24].unwrap(), 110.0);
+    assert_close(vwaps[25].unwrap(), 113.326_996_197_718_63);
+    assert_close(vwaps[39].unwrap(), 115.638_020_833_333_33);
+
+    // the sessions of UTC-5 start at 05:00 UTC
+    let mut vwap = Vwap::new(FixedOffset::west_opt(5 * 3600).unwrap());
+    let vwaps = values(&mut vwap, &candles);
+    assert_close(vwaps[1].unwrap(), 99.046_413_502_109_7);
+    assert_close(vwaps[5].unwrap(), 99.333_333_333_333_33);
+
+    let mut obv = Obv::new();
+    let obvs = values(&mut obv, &candles);
+    assert_eq!(obvs[..3], [Some(0.0), Some(137.0), Some(13.0)]);
+    assert_eq!(obvs[39], Some(-1058.0));
+}
@@ -16,13 +16,17 @@
 //! | **`Candle`** | Represents OHLCV (Open, High, Low, Close, Volume) data for a single time period.               |
 //! | **`Order`**  | Market, limit, or conditional orders (e.g., stop-loss, take-profit).                          |
 //! | **`Position`** | Open trades with configurable exit rules (e.g., trailing stops).                              |
-//! | **`Wallet`** | Tracks balance, locked funds, unrealized P&L, and fees.                                       |
-//! | **`Metrics`** | Calculates performance metrics: P&L, drawdown, Sharpe ratio, win rate, and more.             |
+//! | **`Wallet`** | Tracks balance, locked funds, unrealized P&L, and fees, in a reconcilable double-entry ledger. |
+//! | **`Instrument`** | Tick size, lot step, minimum quantity and notional, contract multiplier, quote currency.      |
+//! | **`Metrics`** | Calculates performance metrics on the equity curve: P&L, drawdown, Sharpe ratio, win rate, and more. |
 //! | **`Optimizer`** | Calculates bests parameters *(indicators, RR, etc...)*.             |
-//! | **`Backtest`** | The engine that simulates strategy execution over historical data.                          |
+//! | **`Backtest`** | The engine that simulates strategy execution over historical data, on one or several symbols. |
+//! | **`Strategy`** | Reusable strategy with lifecycle hooks, reading the data through a look-ahead-safe context.    |
 //!
 //! ## Features
 //! ### 1. **Technical Indicators**
+//! - Streaming indicators with warm-up tracking: SMA, EMA, WMA, RSI, MACD, Impulse MACD, Bollinger bands, ATR, ADX,
+//!   Parabolic SAR, session VWAP, OBV, SuperTrend and Donchian channels.
 //! - Compatible with indicators crates like the [`ta`](https://crates.io/crates/ta) crate for 100+ additional indicators.
 //!
 //! ### 2. **Order Types & Exit Rules**
@@ -34,6 +38,20 @@
 //! | **Stop-Loss**            | Closes the position to limit losses.                                                          |
 //! | **Trailing Stop**        | Dynamically adjusts the stop price based on market movements.                                |
 //! | **Take-Profit + Stop-Loss** | Combines both rules for risk management.                                                   |
+//! | **Break-Even / Profit Lock** | Moves the stop-loss once the position reaches a profit target.                            |
+//! | **Custom Exit Rule**     | Closes the position with your own rule through the `ExitRule` trait.                          |
+//! | **Time Exit**            | Closes the position after a number of candles, a holding period, or at a time of day.        |
+//! | **Composite Exit**       | Combines several exit rules, the first one reached inside the candle closes the position.    |
+//! | **TWAP / VWAP / Iceberg** | Slices a large parent order into child orders across candles.                                |
+//! | **Rebalancing**          | Moves the holdings to target portfolio weights, with bands and a calendar schedule.          |
+//! | **Futures Roll**         | Rolls dated futures before expiry, with back-adjusted or ratio-adjusted continuous series.   |
+//! | **Options**              | Calls and puts marked with Black-Scholes or Black-76, settled at expiry, with greeks.        |
+//! | **Corporate Actions**    | Splits and dividends, on back-adjusted candles or applied to raw-price positions.            |
+//! | **Risk Manager**         | Pre-trade limits on notional, exposure, counts, order size, daily loss and trade frequency.  |
+//! | **Circuit Breaker**      | Halts trading on a drawdown or losing streak, optionally flattens, resumes after a cooldown. |
+//! | **Position Sizing**      | Fixed fractional, fixed risk, Kelly, volatility and equal-risk quantities, rounded to lots.   |
+//! | **Trading Window**       | Trades and measures a window of the data, the candles before it warming the strategy up.     |
+//! | **Time Aggregation**     | Bars of calendar periods (4 hours, days, weeks, months) in the exchange timezone, across gaps. |
 //!
 //! ### 3. **Performance Metrics**
 //! | Metric               | Description                                                                                     |
@@ -43,6 +61,8 @@
 //! | **Sharpe Ratio**     | Risk-adjusted return (higher = better).                                                      |
 //! | **Win Rate**         | Percentage of winning trades.                                                                 |
 //! | **Sortino Ratio**    | Like Sharpe ratio, but focuses only on downside volatility.                                  |
+//! | **Time-Weighted Return** | Return of the strategy, without the effect of deposits and withdrawals.                         |
+//! | **Money-Weighted Return** | Annualized internal rate of return (IRR) of the cashflows.                                    |
 //!
 //! ### 4. **Optimization Tools**
 //! - **Parallel Brute-Force**: Optimize strategy parameters (e.g., EMA periods) using multi-threading.
@@ -122,6 +142,7 @@
 //! |----------------|---------------------------------------------------------------------------------------------|
 //! | [`rayon`](https://crates.io/crates/rayon) | Parallel processing for optimization.                                                     |
 //! | [`serde`](https://crates.io/crates/serde) | Serialize/deserialize backtest results.                                                    |
+//! | [`rust_decimal`](https://crates.io/crates/rust_decimal) | Exact decimal wallet accounting with the `decimal` feature.                        |
 //! | [`plotters`](https://crates.io/crates/plotters) | Visualize market candlesticks data, equity curves and indicators.                                                   |
 //!
 //! ## Error Handling
@@ -178,6 +199,9 @@ pub mod engine;
 /// Error types for the library.
 pub mod errors;
 
+/// Streaming technical indicators: moving averages, oscillators, volatility, trend and volume.
+pub mod indicators;
+
 /// Utility functions and helpers.
 mod utils;
 
@@ -198,6 +222,7 @@ pub mod prelude {
     pub use super::*;
     pub use crate::engine::*;
     pub use crate::errors::*;
+    pub use crate::indicators::*;
 
     #[cfg(feature = "metrics")]
     pub use crate::metrics::*;
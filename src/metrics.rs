@@ -5,6 +5,7 @@
 //! - Profit factor
 //! - Sharpe ratio
 //! - Win rate
+//! - Time-weighted and money-weighted returns
 //!
 //! Events generated during backtesting.
 //!
@@ -13,6 +14,8 @@
 
 use std::fmt;
 
+use chrono::{DateTime, Utc};
+
 use crate::engine::*;
 
 /// Events generated during a backtest.
@@ -34,6 +37,26 @@ pub enum Event {
     /// This event is triggered when an order is canceled or executed.
     DelOrder(Order),
 
+    /// An order has been rejected by the pre-trade limits of the risk manager.
+    ///
+    /// This event is triggered when a placed order violates a limit, its funds are not locked.
+    RejectOrder {
+        /// The rejected order.
+        order: Order,
+        /// The violated limit.
+        reason: String,
+    },
+
+    /// A parent order has been added to the backtest.
+    ///
+    /// This event is triggered when a parent order is placed, before its first child order.
+    AddParentOrder(ParentOrder),
+
+    /// A parent order has been removed from the backtest.
+    ///
+    /// This event is triggered when a parent order is completed or cancelled.
+    DelParentOrder(ParentOrder),
+
     /// A position has been opened.
     ///
     /// This event is triggered when an order is executed and a new position is created.
@@ -44,6 +67,97 @@ pub enum Event {
     /// This event is triggered when a position is closed, either manually or by an exit rule.
     DelPosition(Position),
 
+    /// The stop-loss of a position has been moved by a stop adjustment.
+    ///
+    /// This event is triggered when a break-even or profit-lock rule moves the stop.
+    StopUpdate {
+        /// The position after the stop has moved.
+        position: Position,
+        /// The previous stop-loss price, if any.
+        from: Option<f64>,
+        /// The new stop-loss price.
+        to: f64,
+    },
+
+    /// A futures position has been rolled into the next contract of the chain.
+    ///
+    /// This event is triggered when a contract reaches its roll date.
+    Roll {
+        /// The closed position.
+        from: Position,
+        /// The position opened in the next contract.
+        to: Position,
+        /// The fees of both legs and the roll cost.
+        cost: f64,
+    },
+
+    /// A stock split has been applied to the open positions and the pending orders of a symbol.
+    ///
+    /// This event is triggered on the ex-date of the split.
+    Split {
+        /// The symbol of the series.
+        symbol: String,
+        /// The number of new shares for one old share.
+        ratio: f64,
+        /// The ex-date.
+        time: DateTime<Utc>,
+    },
+
+    /// A dividend has been credited to the long positions, or debited from the short positions, of a symbol.
+    ///
+    /// This event is triggered on the ex-date of the dividend.
+    Dividend {
+        /// The symbol of the series.
+        symbol: String,
+        /// The net amount credited to the wallet, negative for a net short holding.
+        amount: f64,
+        /// The ex-date.
+        time: DateTime<Utc>,
+    },
+
+    /// The mark-to-market state of the account has been sampled at the close of a candle.
+    ///
+    /// This event is triggered at the sampling interval of the equity curve, see [`Backtest::with_equity_sampling`].
+    Equity(EquitySnapshot),
+
+    /// Trading has been halted by the circuit breaker.
+    ///
+    /// This event is triggered at the close of the candle on which a kill-switch rule is reached.
+    Halt {
+        /// The rule reached.
+        reason: HaltReason,
+        /// The close time of the candle.
+        time: DateTime<Utc>,
+    },
+
+    /// Trading has resumed after a halt of the circuit breaker.
+    ///
+    /// This event is triggered at the close of the candle ending the cooldown or making a new equity high.
+    Resume {
+        /// The close time of the candle.
+        time: DateTime<Utc>,
+    },
+
+    /// Funds have been deposited into the wallet.
+    ///
+    /// This event is triggered by a scheduled or an on-demand deposit.
+    Deposit {
+        /// The deposited amount.
+        amount: f64,
+        /// The open time of the candle.
+        time: DateTime<Utc>,
+    },
+
+    /// Funds have been withdrawn from the wallet.
+    ///
+    /// This event is triggered by a scheduled or an on-demand withdrawal.
+    Withdrawal {
+        /// The withdrawn amount.
+        amount: f64,
+        /// The open time of the candle.
+        time: DateTime<Utc>,
+    },
+
     /// The wallet balance has been updated.
     ///
     /// This event is triggered after each trade or fee deduction.
@@ -84,13 +198,17 @@ impl From<&Wallet> for Event {
 pub struct Metrics {
     events: Vec<Event>,
     initial_balance: f64,
+    period: Option<(DateTime<Utc>, DateTime<Utc>)>,
 }
 
 impl From<&Backtest> for Metrics {
     fn from(value: &Backtest) -> Self {
+        // the metrics start at the trading window, after the warm-up candles
+        let (events, initial_balance) = value.window_events();
         Self {
-            initial_balance: value.initial_balance(),
-            events: value.events().cloned().collect(),
+            initial_balance,
+            events: events.to_vec(),
+            period: value.window_period(),
         }
     }
 }
@@ -101,29 +219,145 @@ impl Metrics {
         Self {
             events,
             initial_balance,
+            period: None,
         }
     }
 
-    /// Computes the maximum drawdown as a percentage.
-    pub fn max_drawdown(&self) -> f64 {
-        let mut balance_history = Vec::new();
+    /// Sets the start and end times of the backtest, required by the money-weighted return.
+    pub fn with_period(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.period = Some((start, end));
+        self
+    }
+
+    /// Returns the amount of a deposit (positive) or a withdrawal (negative) event.
+    fn cashflow(event: &Event) -> Option<(f64, DateTime<Utc>)> {
+        match event {
+            Event::Deposit { amount, time } => Some((*amount, *time)),
+            Event::Withdrawal { amount, time } => Some((-*amount, *time)),
+            _ => None,
+        }
+    }
+
+    /// Returns the equity events: the snapshots of the equity curve when the events have some,
+    /// the wallet updates otherwise.
+    fn equity_of(&self) -> impl Fn(&Event) -> Option<f64> + use<> {
+        let curve = self.events.iter().any(|event| matches!(event, Event::Equity(_)));
+        move |event| match event {
+            Event::Equity(snapshot) if curve => Some(snapshot.equity()),
+            Event::WalletUpdate { balance, .. } if !curve => Some(*balance),
+            _ => None,
+        }
+    }
+
+    /// Returns the last equity of the events.
+    fn final_balance(&self) -> f64 {
+        self.events
+            .iter()
+            .rev()
+            .find_map(self.equity_of())
+            .unwrap_or(self.initial_balance)
+    }
+
+    /// Computes the time-weighted return as a percentage.
+    ///
+    /// The returns between two cashflows are chained, so deposits and withdrawals are not counted as profits or losses.
+    pub fn time_weighted_return(&self) -> f64 {
+        let mut growth = 1.0;
+        let mut start_balance = self.initial_balance;
+        let mut balance = self.initial_balance;
+        let equity_of = self.equity_of();
 
         for event in &self.events {
-            if let Event::WalletUpdate { balance, .. } = event {
-                balance_history.push(*balance);
+            if let Some(b) = equity_of(event) {
+                balance = b;
+            } else if let Some((amount, _)) = Self::cashflow(event) {
+                if start_balance > 0.0 {
+                    growth *= balance / start_balance;
+                }
+                start_balance = balance + amount;
             }
         }
+        if start_balance > 0.0 {
+            growth *= self.final_balance() / start_balance;
+        }
+
+        (growth - 1.0) * 100.0
+    }
+
+    /// Computes the annualized money-weighted return (internal rate of return) as a percentage.
+    ///
+    /// The cashflows are the initial balance, the deposits and withdrawals, and the final balance.
+    /// Returns `None` without the period of the backtest (see [`Metrics::with_period`])
+    /// or if the rate can't be found.
+    pub fn money_weighted_return(&self) -> Option<f64> {
+        let (start, end) = self.period?;
+        let years = |time: DateTime<Utc>| (time - start).num_seconds() as f64 / (365.25 * 86400.0);
+        if years(end) <= 0.0 {
+            return None;
+        }
+
+        let mut cashflows = vec![(-self.initial_balance, 0.0)];
+        cashflows.extend(
+            self.events
+                .iter()
+                .filter_map(Self::cashflow)
+                .map(|(amount, time)| (-amount, years(time))),
+        );
+        cashflows.push((self.final_balance(), years(end)));
+
+        // net present value, decreasing with the rate
+        let npv = |rate: f64| {
+            cashflows
+                .iter()
+                .map(|(amount, t)| amount / (1.0 + rate).powf(*t))
+                .sum::<f64>()
+        };
+
+        let (mut low, mut high) = (-0.999_999, 1.0);
+        while npv(high) > 0.0 && high < 1e12 {
+            high *= 2.0;
+        }
+        if npv(low) < 0.0 || npv(high) > 0.0 {
+            return None;
+        }
+        for _ in 0..200 {
+            let mid = (low + high) / 2.0;
+            if npv(mid) > 0.0 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Some((low + high) / 2.0 * 100.0)
+    }
 
+    /// Computes the maximum drawdown as a percentage.
+    ///
+    /// The drawdown is computed on the equity curve, so the losses of the open positions are counted.
+    pub fn max_drawdown(&self) -> f64 {
         let mut max_peak = self.initial_balance;
         let mut max_drawdown = 0.0;
+        let mut previous_balance = self.initial_balance;
+        let equity_of = self.equity_of();
 
-        for &balance in &balance_history {
-            if balance > max_peak {
-                max_peak = balance;
+        for event in &self.events {
+            if let Some((amount, _)) = Self::cashflow(event) {
+                // the peak follows the cashflows, so they are not counted as drawdown
+                if previous_balance > 0.0 {
+                    max_peak *= (previous_balance + amount) / previous_balance;
+                }
+                previous_balance += amount;
             }
-            let drawdown = (max_peak - balance) / max_peak;
-            if drawdown > max_drawdown {
-                max_drawdown = drawdown;
+            if let Some(balance) = equity_of(event) {
+                previous_balance = balance;
+                if balance > max_peak {
+                    max_peak = balance;
+                }
+                let drawdown = (max_peak - balance) / max_peak;
+                if drawdown > max_drawdown {
+                    max_drawdown = drawdown;
+                }
             }
         }
 
@@ -156,16 +390,21 @@ impl Metrics {
     /// Computes the Sharpe ratio, a measure of risk-adjusted return.
     ///
     /// A higher Sharpe ratio indicates better risk-adjusted performance.
+    /// The returns are the ones of the equity curve, one per sampled candle.
     /// `risk_free_rate` is the annualized risk-free return (e.g., 0.0 for simplicity).
     pub fn sharpe_ratio(&self, risk_free_rate: f64) -> f64 {
         let mut returns = Vec::new();
         let mut previous_balance = self.initial_balance;
+        let equity_of = self.equity_of();
 
         for event in &self.events {
-            if let Event::WalletUpdate { balance, .. } = event {
-                let return_pct = (*balance - previous_balance) / previous_balance;
+            if let Some((amount, _)) = Self::cashflow(event) {
+                previous_balance += amount;
+            }
+            if let Some(balance) = equity_of(event) {
+                let return_pct = (balance - previous_balance) / previous_balance;
                 returns.push(return_pct);
-                previous_balance = *balance;
+                previous_balance = balance;
             }
         }
 
@@ -205,6 +444,10 @@ impl fmt::Display for Metrics {
         writeln!(f, "Profit Factor: {:.2}", self.profit_factor())?;
         writeln!(f, "Sharpe Ratio (risk-free rate = 0.0): {:.2}", self.sharpe_ratio(0.0))?;
         writeln!(f, "Win Rate: {:.2}%", self.win_rate())?;
+        writeln!(f, "Time-Weighted Return: {:.2}%", self.time_weighted_return())?;
+        if let Some(irr) = self.money_weighted_return() {
+            writeln!(f, "Money-Weighted Return (IRR): {:.2}%", irr)?;
+        }
         Ok(())
     }
 }
@@ -369,3 +612,88 @@ fn win_rate_all_winning() {
     let metrics = Metrics::new(events, 10000.0);
     assert_eq!(metrics.win_rate(), 100.0); // 1 win out of 1 trade
 }
+
+#[cfg(test)]
+fn wallet_update(balance: f64) -> Event {
+    Event::WalletUpdate {
+        pnl: 0.0,
+        fees: 0.0,
+        free: balance,
+        locked: 0.0,
+        balance,
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn returns_with_cashflows() {
+    let start = DateTime::from_timestamp_secs(1735689600).unwrap(); // 2025-01-01
+    let mid = start + chrono::Duration::days(365);
+    let end = mid + chrono::Duration::days(365);
+    let events = vec![
+        wallet_update(1100.0),
+        Event::Deposit {
+            amount: 900.0,
+            time: mid,
+        },
+        wallet_update(2000.0),
+        wallet_update(2200.0),
+    ];
+    let metrics = Metrics::new(events, 1000.0).with_period(start, end);
+
+    // +10% then +10%, the deposit is not a profit
+    assert!((metrics.time_weighted_return() - 21.0).abs() < 1e-9);
+    assert_eq!(metrics.max_drawdown(), 0.0);
+    let irr = metrics.money_weighted_return().unwrap();
+    assert!((irr - 10.0).abs() < 0.1);
+}
+
+#[cfg(test)]
+#[test]
+fn returns_with_withdrawal() {
+    let events = vec![
+        wallet_update(900.0),
+        Event::Withdrawal {
+            amount: 400.0,
+            time: DateTime::from_timestamp_secs(1735689600).unwrap(),
+        },
+        wallet_update(500.0),
+        wallet_update(550.0),
+    ];
+    let metrics = Metrics::new(events, 1000.0);
+
+    // -10% then +10%
+    assert!((metrics.time_weighted_return() + 1.0).abs() < 1e-9);
+    assert!((metrics.max_drawdown() - 10.0).abs() < 1e-9);
+    assert!(metrics.money_weighted_return().is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn equity_curve_takes_precedence() {
+    let snapshot = |equity: f64| {
+        let candle = CandleBuilder::builder()
+            .open(1.0)
+            .high(1.0)
+            .low(1.0)
+            .close(1.0)
+            .volume(1.0)
+            .open_time(DateTime::from_timestamp_secs(0).unwrap())
+            .close_time(DateTime::from_timestamp_secs(1).unwrap())
+            .build()
+            .unwrap();
+        let bt = Backtest::new(vec![candle.clone()], equity, None).unwrap();
+        Event::Equity(bt.equity_snapshot(&candle).unwrap())
+    };
+    // the wallet updates ignore the open positions
+    let events = vec![
+        wallet_update(1000.0),
+        snapshot(1000.0),
+        snapshot(800.0),
+        wallet_update(1000.0),
+        snapshot(1000.0),
+    ];
+    let metrics = Metrics::new(events, 1000.0);
+    assert_eq!(metrics.max_drawdown(), 20.0);
+    assert_eq!(metrics.time_weighted_return(), 0.0);
+}
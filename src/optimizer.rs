@@ -5,9 +5,9 @@
 //! `ParameterCombination` trait defines how to generate parameter sets.
 
 use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 
-use crate::engine::{Backtest, Candle};
+use crate::engine::{Backtest, Candle, Strategy};
 use crate::errors::{Error, Result};
 
 use rayon::prelude::*;
@@ -75,24 +75,71 @@ impl<PS: ParameterCombination> Optimizer<PS> {
         PS: Sync,
         TR: Fn(&PS::T) -> Result<T> + Sync,
         S: FnMut(&mut Backtest, &mut T, &Candle) -> Result<()> + Send,
+    {
+        let strategy = Mutex::new(strategy);
+        let transformers = &transformers;
+
+        self.run_combinations(|| {
+            let mut strategy_guard = strategy.lock().map_err(|e| Error::MutexPoisoned(e.to_string()))?;
+            Ok(move |backtest: &mut Backtest, param_set: &PS::T| {
+                let mut transformer = transformers(param_set)?;
+                backtest.run(|bt, candle| strategy_guard(bt, &mut transformer, candle))
+            })
+        })
+    }
+
+    /// Optimizes a [`Strategy`] by testing all parameter combinations.
+    ///
+    /// Each combination runs its own strategy, so the strategies are not shared between threads.
+    ///
+    /// # Arguments
+    /// * `strategy` - Function that builds the strategy of a parameter combination.
+    ///
+    /// # Returns
+    /// A vector of tuples containing each parameter combination and its resulting balance.
+    ///
+    /// # Errors
+    /// Returns an error if a strategy cannot be built or if backtest execution fails.
+    pub fn with_strategy<S, F>(&self, strategy: F) -> Result<Vec<(PS::T, f64)>>
+    where
+        PS: Sync,
+        S: Strategy,
+        F: Fn(&PS::T) -> Result<S> + Sync,
+    {
+        self.run_combinations(|| {
+            Ok(|backtest: &mut Backtest, param_set: &PS::T| {
+                let mut strategy = strategy(param_set)?;
+                backtest.run_strategy(&mut strategy)
+            })
+        })
+    }
+
+    /// Runs a backtest for each parameter combination, the combinations being split in chunks between threads.
+    ///
+    /// # Arguments
+    /// * `runner` - Function that builds, once per chunk, the function running a combination on a backtest.
+    ///
+    /// # Returns
+    /// A vector of tuples containing each parameter combination and its resulting balance.
+    fn run_combinations<R, F>(&self, runner: F) -> Result<Vec<(PS::T, f64)>>
+    where
+        PS: Sync,
+        R: FnMut(&mut Backtest, &PS::T) -> Result<()>,
+        F: Fn() -> Result<R> + Sync,
     {
         let num_cpus = num_cpus::get();
-        let strategy = Arc::new(Mutex::new(strategy));
         let combinations = PS::generate();
-        let chunk_size = ((combinations.len() + num_cpus - 1) / num_cpus).max(1);
+        let chunk_size = combinations.len().div_ceil(num_cpus).max(1);
 
         let chunk_results = combinations
             .par_chunks(chunk_size)
             .map::<_, Result<_>>(|par_combinations| {
                 let mut backtest = Backtest::new(self.data.clone(), self.initial_balance, self.market_fees)?;
                 let mut local_results = Vec::with_capacity(par_combinations.len());
-
-                let strategy_arc = Arc::clone(&strategy);
-                let mut strategy_guard = strategy_arc.lock().map_err(|e| Error::MutexPoisoned(e.to_string()))?;
+                let mut run = runner()?;
 
                 for param_set in par_combinations {
-                    let mut transformer = transformers(param_set)?;
-                    backtest.run(|bt, candle| strategy_guard(bt, &mut transformer, candle))?;
+                    run(&mut backtest, param_set)?;
                     local_results.push((param_set.clone(), backtest.total_balance()));
                     backtest.reset();
                 }
@@ -214,3 +261,43 @@ fn optimizer_with_ema_macd() {
 
     assert!(!result.is_empty(), "No optimization results returned");
 }
+
+#[cfg(test)]
+#[test]
+fn optimizer_with_strategy() {
+    use crate::prelude::*;
+
+    struct Thresholds;
+
+    impl ParameterCombination for Thresholds {
+        type T = f64;
+
+        fn generate() -> Vec<Self::T> {
+            vec![95.0, 105.0, 115.0]
+        }
+    }
+
+    // buys once above the threshold and sells at the last close
+    struct Breakout {
+        threshold: f64,
+    }
+
+    impl Strategy for Breakout {
+        fn on_candle(&mut self, ctx: &mut StrategyContext, candle: &Candle) -> Result<()> {
+            if candle.close() > self.threshold && ctx.positions().count() == 0 && ctx.orders().count() == 0 {
+                ctx.place_order(Order::from((OrderType::Market(candle.close()), 1.0, OrderSide::Buy)))?;
+            }
+            Ok(())
+        }
+
+        fn on_finish(&mut self, ctx: &mut StrategyContext) -> Result<()> {
+            let close = ctx.candle().ok_or(Error::CandleNotFound)?.close();
+            ctx.close_all_positions(close)
+        }
+    }
+
+    let opt = Optimizer::<Thresholds>::new(get_data(), 1_000.0, None);
+    let result = opt.with_strategy(|&threshold| Ok(Breakout { threshold })).unwrap();
+
+    assert_eq!(result, vec![(95.0, 1_020.0), (105.0, 1_010.0), (115.0, 1_000.0)]);
+}